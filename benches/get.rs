@@ -0,0 +1,69 @@
+#![feature(test)]
+
+extern crate test;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use test::Bencher;
+
+use cmap::{Map, U32Hasher};
+
+// small enough for the trie to stay in cache, so that the cost of walking
+// the trie, computing the child index at every level, is not hidden by
+// cache misses.
+const N_KEYS: usize = 1000;
+
+fn load(n: usize) -> (Map<u32, u64, U32Hasher>, Vec<u32>) {
+    let mut rng = SmallRng::from_seed(0_u128.to_le_bytes());
+    let mut map: Map<u32, u64, U32Hasher> = Map::new(1, U32Hasher::default());
+    let keys: Vec<u32> = (0..n).map(|_| rng.gen()).collect();
+    for (i, key) in keys.iter().enumerate() {
+        map.set(*key, i as u64);
+    }
+    (map, keys)
+}
+
+#[bench]
+fn bench_get(b: &mut Bencher) {
+    let (map, keys) = load(N_KEYS);
+    let mut i = 0;
+    b.iter(|| {
+        i = (i + 1) % keys.len();
+        map.get(&keys[i])
+    })
+}
+
+#[bench]
+fn bench_get_missing(b: &mut Bencher) {
+    let (map, _) = load(N_KEYS);
+    let mut rng = SmallRng::from_seed(1_u128.to_le_bytes());
+    let keys: Vec<u32> = (0..N_KEYS).map(|_| rng.gen()).collect();
+    let mut i = 0;
+    b.iter(|| {
+        i = (i + 1) % keys.len();
+        map.get(&keys[i])
+    })
+}
+
+// large trie, where gets are bound by memory latency.
+#[bench]
+fn bench_get_large(b: &mut Bencher) {
+    let (map, keys) = load(1_000_000);
+    let mut rng = SmallRng::from_seed(2_u128.to_le_bytes());
+    b.iter(|| map.get(&keys[rng.gen::<usize>() % keys.len()]))
+}
+
+#[bench]
+fn bench_frozen_get_large(b: &mut Bencher) {
+    let (map, keys) = load(1_000_000);
+    let frozen = map.freeze();
+    let mut rng = SmallRng::from_seed(2_u128.to_le_bytes());
+    b.iter(|| frozen.get(&keys[rng.gen::<usize>() % keys.len()]).copied())
+}
+
+#[bench]
+fn bench_frozen_compact_get_large(b: &mut Bencher) {
+    let (map, keys) = load(1_000_000);
+    let frozen = map.freeze_compact();
+    let mut rng = SmallRng::from_seed(2_u128.to_le_bytes());
+    b.iter(|| frozen.get(&keys[rng.gen::<usize>() % keys.len()]).copied())
+}
@@ -0,0 +1,171 @@
+//! Module implement [WriteBatch], to apply a group of mutations in one go.
+//!
+//! Mutations are staged in a batch, obtained via [Map::batch], and applied
+//! together via [WriteBatch::apply]. Keys are hashed while staging, and on
+//! apply mutations are sorted in the order of their path in the trie, so
+//! that mutations on the same sub-trie are applied back to back, while the
+//! path is still in cache. Mutations on the same key are coalesced into
+//! the last one, and garbage is collected once for the whole batch,
+//! irrespective of [Map::set_gc_period].
+//!
+//! ```
+//! use cmap::{DefaultHasher, Map};
+//!
+//! let mut map: Map<u64, u64> = Map::new(1, DefaultHasher::new());
+//! map.set(30, 300);
+//!
+//! let mut batch = map.batch();
+//! batch.set(10, 100).set(20, 200).set(10, 1000).remove(30);
+//! assert_eq!(batch.apply(), 3);
+//!
+//! assert_eq!(map.get(&10), Some(1000));
+//! assert_eq!(map.get(&30), None);
+//! ```
+//!
+//! Mutations within a batch are not atomic, concurrent readers can observe
+//! the batch partially applied.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::{map::slots128, DefaultHasher, Map};
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return a new [WriteBatch] for this map instance. Mutations staged
+    /// in the batch are discarded, unless applied.
+    pub fn batch(&mut self) -> WriteBatch<'_, K, V, H> {
+        WriteBatch {
+            map: self,
+            ops: Vec::default(),
+        }
+    }
+}
+
+/// WriteBatch stages mutations on a [Map], refer to [Map::batch].
+pub struct WriteBatch<'a, K, V, H = DefaultHasher> {
+    map: &'a mut Map<K, V, H>,
+    ops: Vec<(u128, Op<K, V>)>,
+}
+
+enum Op<K, V> {
+    Set(K, V),
+    Remove(K),
+}
+
+impl<'a, K, V, H> WriteBatch<'a, K, V, H> {
+    /// Return the number of mutations staged in this batch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Return whether any mutation is staged in this batch.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Stage `key` to be set to `value`.
+    pub fn set(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let hash = self.map.to_trie_hash(&key);
+        self.ops.push((hash, Op::Set(key, value)));
+        self
+    }
+
+    /// Stage `key` to be removed.
+    pub fn remove(&mut self, key: K) -> &mut Self
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let hash = self.map.to_trie_hash(&key);
+        self.ops.push((hash, Op::Remove(key)));
+        self
+    }
+
+    /// Apply the staged mutations, and return the number of mutations
+    /// applied after coalescing mutations on the same key.
+    pub fn apply(self) -> usize
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        let WriteBatch { map, mut ops } = self;
+
+        // stable sort, mutations on the same key stay in the staged order.
+        ops.sort_by_key(|(hash, _)| to_path(*hash));
+        let ops = coalesce(ops);
+
+        let n_sets = ops
+            .iter()
+            .filter(|(_, op)| matches!(op, Op::Set(..)))
+            .count();
+        map.reserve(n_sets);
+
+        let n = ops.len();
+        for (hash, op) in ops.into_iter() {
+            match op {
+                Op::Set(key, value) => {
+                    map.do_update(key, hash, |_| Some(value.clone()));
+                }
+                Op::Remove(key) => {
+                    let (_, compact, _) = map.do_remove(&key, hash, |_| true);
+                    if compact {
+                        map.do_compact(&key, hash)
+                    }
+                }
+            }
+        }
+        map.force_gc();
+
+        n
+    }
+}
+
+// Order of the hash's path in the trie, which consumes the hash 4 bits at
+// a time, from the lower bits, refer to iter::to_walk_order.
+fn to_path(hash: u128) -> u128 {
+    slots128(hash)
+        .iter()
+        .fold(0, |path, w| (path << 4) | (*w as u128))
+}
+
+// Keep only the last mutation for each key, mutations on the same key are
+// adjacent, sharing the same hash, after sorting by path.
+fn coalesce<K, V>(ops: Vec<(u128, Op<K, V>)>) -> Vec<(u128, Op<K, V>)>
+where
+    K: PartialEq,
+{
+    let mut coalesced: Vec<(u128, Op<K, V>)> = Vec::with_capacity(ops.len());
+    let mut from = 0; // start of mutations sharing the last hash.
+    for (hash, op) in ops.into_iter() {
+        match coalesced.last() {
+            Some((last, _)) if *last == hash => (),
+            _ => from = coalesced.len(),
+        }
+        let key = op.as_key();
+        match coalesced[from..]
+            .iter()
+            .position(|(_, o)| o.as_key() == key)
+        {
+            Some(off) => coalesced[from + off] = (hash, op),
+            None => coalesced.push((hash, op)),
+        }
+    }
+    coalesced
+}
+
+impl<K, V> Op<K, V> {
+    fn as_key(&self) -> &K {
+        match self {
+            Op::Set(key, _) => key,
+            Op::Remove(key) => key,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "batch_test.rs"]
+mod batch_test;
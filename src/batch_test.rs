@@ -0,0 +1,136 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, thread};
+
+use super::*;
+
+#[test]
+fn test_batch() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let key_max = [16, 1000, 100_000][rng.gen::<usize>() % 3];
+    let batch_size = [1, 10, 1000][rng.gen::<usize>() % 3];
+    println!(
+        "test_batch seed:{} key_max:{} batch_size:{}",
+        seed, key_max, batch_size
+    );
+
+    let mut map: Map<u64, u64> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+    for _i in 0..100 {
+        let mut batch = map.batch();
+        let mut keys = BTreeMap::new();
+        for _j in 0..batch_size {
+            let key = rng.gen::<u64>() % key_max;
+            keys.insert(key, ());
+            match rng.gen::<u8>() % 3 {
+                0 => {
+                    batch.remove(key);
+                    btmap.remove(&key);
+                }
+                _ => {
+                    let value = rng.gen::<u64>();
+                    batch.set(key, value);
+                    btmap.insert(key, value);
+                }
+            }
+        }
+        assert_eq!(batch.len(), batch_size);
+        assert_eq!(batch.apply(), keys.len());
+
+        let gc = map.gc_stats();
+        assert_eq!((gc.n_reclaims, gc.n_garbage), (0, 0), "{:?}", gc);
+    }
+    assert_eq!(map.len(), btmap.len());
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value), "key:{}", key);
+    }
+    map.validate();
+
+    // batch that is not applied is discarded.
+    let mut batch = map.batch();
+    batch.set(key_max + 1, 0);
+    assert!(!batch.is_empty());
+    std::mem::drop(batch);
+    assert_eq!(map.get(&(key_max + 1)), None);
+}
+
+#[test]
+fn test_batch_concurrent() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    println!(
+        "test_batch_concurrent seed:{} n_threads:{}",
+        seed, n_threads
+    );
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            let mut btmap = BTreeMap::new();
+            for _i in 0..100 {
+                let mut batch = map.batch();
+                for _j in 0..100 {
+                    // keys are disjoint across threads.
+                    let key = ((rng.gen::<u64>() % 1000) * 8) + (id as u64);
+                    match rng.gen::<u8>() % 3 {
+                        0 => {
+                            batch.remove(key);
+                            btmap.remove(&key);
+                        }
+                        _ => {
+                            batch.set(key, key);
+                            btmap.insert(key, key);
+                        }
+                    }
+                }
+                batch.apply();
+            }
+            btmap
+        }));
+    }
+    let mut btmap = BTreeMap::new();
+    for handle in handles.into_iter() {
+        btmap.extend(handle.join().unwrap());
+    }
+    assert_eq!(map.len(), btmap.len());
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value), "key:{}", key);
+    }
+    map.flush_gc();
+    map.validate();
+}
+
+#[test]
+fn test_coalesce() {
+    // keys colliding on the hash are not coalesced.
+    let ops: Vec<(u128, Op<u64, u64>)> = vec![
+        (1, Op::Set(10, 100)),
+        (1, Op::Set(20, 200)),
+        (1, Op::Remove(10)),
+        (2, Op::Set(10, 300)),
+        (3, Op::Set(30, 300)),
+        (3, Op::Set(30, 400)),
+    ];
+    let ops: Vec<(u128, u64, Option<u64>)> = coalesce(ops)
+        .into_iter()
+        .map(|(hash, op)| match op {
+            Op::Set(key, value) => (hash, key, Some(value)),
+            Op::Remove(key) => (hash, key, None),
+        })
+        .collect();
+    assert_eq!(
+        ops,
+        vec![
+            (1, 10, None),
+            (1, 20, Some(200)),
+            (2, 10, Some(300)),
+            (3, 30, Some(400))
+        ]
+    );
+}
@@ -0,0 +1,191 @@
+//! Module implement a structural invariant checker for [Map].
+//!
+//! Unlike [Map::validate], that panics on the first broken invariant in
+//! debug builds, [Map::check_invariants] walks the entire trie and returns
+//! the first [Violation] as error, so that it can be called from
+//! application's integration tests, say after heavy concurrent churn.
+//!
+//! ```
+//! use cmap::{DefaultHasher, Map};
+//!
+//! let mut map: Map<u64, u64> = Map::new(1, DefaultHasher::new());
+//! (0..1000).for_each(|key| { map.set(key, key); });
+//! (0..500).for_each(|key| { map.remove(&key); });
+//!
+//! let report = map.check_invariants().unwrap();
+//! assert_eq!(report.n_items, 500);
+//! ```
+
+use std::{
+    error,
+    fmt::{self, Display},
+    hash::{BuildHasher, Hash},
+    result,
+    sync::atomic::Ordering::SeqCst,
+};
+
+use crate::{
+    map::{Child, Node},
+    HashWidth, Map,
+};
+
+/// Summary of the trie structure, returned by [Map::check_invariants].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// Number of trie nodes, including the root node.
+    pub n_nodes: usize,
+    /// Number of collision lists.
+    pub n_lists: usize,
+    /// Number of entries.
+    pub n_items: usize,
+    /// Depth of the deepest node, root node is at depth 1.
+    pub max_depth: usize,
+}
+
+/// Broken structural invariant, found by [Map::check_invariants]. `depth`
+/// is the depth of the offending node, root node is at depth 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// Number of bits set in the trie node's bitmap does not agree with the
+    /// number of children.
+    Bitmap {
+        depth: usize,
+        bmp: u16,
+        n_childs: usize,
+    },
+    /// Trie node, other than the root node, without children.
+    EmptyTrie { depth: usize },
+    /// Tomb node left behind by a remove, that was not compacted.
+    Tomb { depth: usize },
+    /// Child slot that is neither a leaf nor a deeper node.
+    EmptyChild { depth: usize },
+    /// Collision list with less than 2 entries, or not at the last level.
+    List { depth: usize, n_items: usize },
+    /// Entry stored under a path that does not match its hash.
+    HashPath { depth: usize },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Violation::*;
+
+        match self {
+            Bitmap {
+                depth,
+                bmp,
+                n_childs,
+            } => write!(f, "depth:{} bitmap:{:x} n_childs:{}", depth, bmp, n_childs),
+            EmptyTrie { depth } => write!(f, "depth:{} empty trie node", depth),
+            Tomb { depth } => write!(f, "depth:{} tomb node", depth),
+            EmptyChild { depth } => write!(f, "depth:{} empty child", depth),
+            List { depth, n_items } => {
+                write!(f, "depth:{} list n_items:{}", depth, n_items)
+            }
+            HashPath { depth } => write!(f, "depth:{} entry not on its hash path", depth),
+        }
+    }
+}
+
+impl error::Error for Violation {}
+
+impl<K, V, H> Map<K, V, H>
+where
+    K: Hash,
+    H: BuildHasher,
+{
+    /// Walk the entire trie and verify its structural invariants, return
+    /// a [Report] on the trie structure, or the first [Violation] found.
+    ///
+    /// * Bits set in a trie node's bitmap shall match its children.
+    /// * Trie nodes, other than the root node, shall have children.
+    /// * There shall be no tomb nodes.
+    /// * Collision lists shall have at least 2 entries and shall be at the
+    ///   last level of the trie.
+    /// * Every entry shall be on the path computed from its hash.
+    ///
+    /// Concurrent removes leave tomb nodes behind till they are compacted,
+    /// hence call this method once concurrent writes have completed.
+    pub fn check_invariants(&self) -> result::Result<Report, Violation> {
+        let seqno = self.pin_epoch();
+        let mut report = Report::default();
+        let mut path = vec![];
+        let res = self.check_node(self.as_root_node(), &mut path, &mut report);
+        self.unpin_epoch(seqno);
+        res.map(|_| report)
+    }
+
+    fn check_node(
+        &self,
+        node: &Node<K, V>,
+        path: &mut Vec<u8>,
+        report: &mut Report,
+    ) -> result::Result<(), Violation> {
+        let depth = path.len() + 1;
+        report.max_depth = usize::max(report.max_depth, depth);
+
+        match node {
+            Node::Tomb { .. } => Err(Violation::Tomb { depth }),
+            Node::Trie { bmp, childs } => {
+                report.n_nodes += 1;
+                let n_childs = childs.len();
+                if (bmp.count_ones() as usize) != n_childs {
+                    return Err(Violation::Bitmap {
+                        depth,
+                        bmp: *bmp,
+                        n_childs,
+                    });
+                } else if depth > 1 && n_childs == 0 {
+                    return Err(Violation::EmptyTrie { depth });
+                }
+
+                // children are ordered by the bits set in the bitmap.
+                let ws = (0..16_u8).filter(|w| (bmp & (1 << w)) != 0);
+                for (w, child) in ws.zip(childs.iter()) {
+                    path.push(w);
+                    match unsafe { child.load(SeqCst).as_ref().unwrap() } {
+                        Child::Leaf(item) => {
+                            report.n_items += 1;
+                            self.check_path(&item.key, path)?;
+                        }
+                        Child::Deep(inode) => {
+                            let node =
+                                unsafe { inode.node.load(SeqCst).as_ref().unwrap() };
+                            self.check_node(node, path, report)?;
+                        }
+                        Child::None => return Err(Violation::EmptyChild { depth }),
+                    }
+                    path.pop();
+                }
+                Ok(())
+            }
+            Node::List { items } => {
+                let n_items = items.len();
+                if n_items < 2 || path.len() != self.hash_width().to_depth() {
+                    return Err(Violation::List { depth, n_items });
+                }
+                report.n_lists += 1;
+                report.n_items += n_items;
+                for item in items.iter() {
+                    self.check_path(&item.key, path)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn check_path(&self, key: &K, path: &[u8]) -> result::Result<(), Violation> {
+        let (mut ws, _) = self.to_slots(self.to_trie_hash(key));
+        if self.hash_width() == HashWidth::Rehash {
+            self.rehash_slots(key, &mut ws, &mut false);
+        }
+        if ws[..path.len()] == *path {
+            Ok(())
+        } else {
+            Err(Violation::HashPath { depth: path.len() })
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "check_test.rs"]
+mod check_test;
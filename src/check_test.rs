@@ -0,0 +1,83 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, thread};
+
+use super::*;
+use crate::{iter::iter_test::CollisionHasher, DefaultHasher};
+
+#[test]
+fn test_check_invariants() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let width = [
+        HashWidth::Bits32,
+        HashWidth::Bits64,
+        HashWidth::Bits128,
+        HashWidth::Rehash,
+    ][rng.gen::<usize>() % 4];
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    println!(
+        "test_check_invariants seed:{} width:{:?} n_threads:{}",
+        seed, width, n_threads
+    );
+
+    check_with(seed, width, n_threads, 10_000, DefaultHasher::new());
+    // collision hasher can only hash u32 keys, hence no re-hash.
+    let width = HashWidth::Bits32;
+    check_with(seed, width, n_threads, 1_000, CollisionHasher::default());
+}
+
+fn check_with<H>(seed: u128, width: HashWidth, n_threads: usize, n_ops: u64, hb: H)
+where
+    H: 'static + Send + Sync + BuildHasher + Clone,
+{
+    let map: Map<u32, u64, H> = Map::with_hash_width(n_threads + 1, hb, width);
+    let report = map.check_invariants().unwrap();
+    assert_eq!(report.n_nodes, 1);
+    assert_eq!(report.n_items, 0);
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+            for i in 0..n_ops {
+                // each thread writes its own set of keys.
+                let key = (rng.gen::<u32>() % 10_000) * (n_threads as u32) + (id as u32);
+                match rng.gen::<u8>() % 3 {
+                    0 => assert_eq!(map.remove(&key), btmap.remove(&key)),
+                    _ => assert_eq!(map.set(key, i), btmap.insert(key, i)),
+                }
+            }
+            btmap
+        }));
+    }
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for handle in handles.into_iter() {
+        btmap.extend(handle.join().unwrap())
+    }
+
+    let report = map.check_invariants().unwrap();
+    println!("test_check_invariants {:?}", report);
+    assert_eq!(report.n_items, btmap.len());
+    assert_eq!(report.n_items, map.len());
+    let stats = map.validate();
+    assert_eq!(report.n_lists, stats.n_lists);
+    assert_eq!(report.n_nodes + report.n_lists, stats.n_nodes);
+}
+
+#[test]
+fn test_violation() {
+    let err = Violation::Bitmap {
+        depth: 2,
+        bmp: 0x11,
+        n_childs: 1,
+    };
+    assert_eq!(err.to_string(), "depth:2 bitmap:11 n_childs:1");
+    assert_eq!(
+        Violation::HashPath { depth: 3 }.to_string(),
+        "depth:3 entry not on its hash path"
+    );
+}
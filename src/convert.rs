@@ -0,0 +1,73 @@
+//! Conversions between [Map] and std collections.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{DefaultHasher, Map};
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return a [HashMap] of all entries in the map, taken from a point in
+    /// time snapshot, refer to [Map::snapshot_iter].
+    pub fn to_hash_map(&self) -> HashMap<K, V>
+    where
+        K: Clone + Eq + Hash,
+        V: Clone,
+    {
+        self.snapshot_iter().collect()
+    }
+}
+
+impl<K, V, H> Extend<(K, V)> for Map<K, V, H>
+where
+    K: Clone + PartialEq + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    /// Insert entries from `iter`, replacing the value for keys that are
+    /// already present. Memory for mutations is reserved upfront, using the
+    /// iterator's size hint.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.set(key, value);
+        }
+    }
+}
+
+/// Create a map, with [DefaultHasher] and concurrency level of 1, from all
+/// the entries in [HashMap]. Entries are loaded using [Map::bulk_load].
+impl<K, V, S> From<HashMap<K, V, S>> for Map<K, V>
+where
+    K: Clone + PartialEq + Hash,
+    V: Clone,
+{
+    fn from(items: HashMap<K, V, S>) -> Map<K, V> {
+        let mut map = Map::new(1, DefaultHasher::new());
+        map.bulk_load(items).expect("bulk_load on a new map");
+        map
+    }
+}
+
+/// Create a map, with [DefaultHasher] and concurrency level of 1, from all
+/// the entries in [BTreeMap]. Entries are loaded using [Map::bulk_load].
+impl<K, V> From<BTreeMap<K, V>> for Map<K, V>
+where
+    K: Clone + PartialEq + Hash,
+    V: Clone,
+{
+    fn from(items: BTreeMap<K, V>) -> Map<K, V> {
+        let mut map = Map::new(1, DefaultHasher::new());
+        map.bulk_load(items).expect("bulk_load on a new map");
+        map
+    }
+}
+
+#[cfg(test)]
+#[path = "convert_test.rs"]
+mod convert_test;
@@ -0,0 +1,40 @@
+use rand::Rng;
+
+use super::*;
+use crate::iter::iter_test::seed_keys;
+
+#[test]
+fn test_convert() {
+    let (_, mut rng, n_keys) = seed_keys("test_convert");
+
+    let mut hmap: HashMap<u32, u64> = HashMap::new();
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>(), rng.gen::<u64>());
+        hmap.insert(key, value);
+        btmap.insert(key, value);
+    }
+
+    let map: Map<u32, u64> = hmap.clone().into();
+    assert_eq!(map.len(), hmap.len());
+    assert_eq!(map.to_hash_map(), hmap);
+    map.validate();
+
+    let mut map: Map<u32, u64> = btmap.clone().into();
+    assert_eq!(map.to_hash_map(), hmap);
+
+    // extend shall replace values for existing keys.
+    let items: Vec<(u32, u64)> = btmap.iter().map(|(k, v)| (*k, v + 1)).collect();
+    map.extend(items.clone());
+    let key: u32 = rng.gen();
+    map.extend(Some((key, 0)));
+    btmap.extend(items);
+    btmap.insert(key, 0);
+
+    let mut items: Vec<(u32, u64)> = map.iter().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+
+    map.validate();
+}
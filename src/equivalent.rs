@@ -0,0 +1,53 @@
+//! Heterogeneous key lookup, in the likes of [hashbrown][hashbrown].
+//!
+//! [Map] lookups accept any type `Q` that implements [Equivalent] for the
+//! key type `K`. Types that `K` can borrow as, like `&str` for `String`,
+//! are covered by a blanket implementation. For other types, say looking
+//! up a `(u64, String)` key without constructing an owned key, implement
+//! `Equivalent` on a type that hashes exactly like the key:
+//!
+//! ```
+//! use std::hash::{Hash, Hasher};
+//! use cmap::{DefaultHasher, Equivalent, Map};
+//!
+//! struct KeyRef<'a>(u64, &'a str);
+//!
+//! impl<'a> Hash for KeyRef<'a> {
+//!     fn hash<S: Hasher>(&self, state: &mut S) {
+//!         (self.0, self.1).hash(state)
+//!     }
+//! }
+//!
+//! impl<'a> Equivalent<(u64, String)> for KeyRef<'a> {
+//!     fn equivalent(&self, key: &(u64, String)) -> bool {
+//!         self.0 == key.0 && self.1 == key.1
+//!     }
+//! }
+//!
+//! let mut map: Map<(u64, String), u64> = Map::new(1, DefaultHasher::new());
+//! map.set((10, "ten".to_string()), 100);
+//! assert_eq!(map.get(&KeyRef(10, "ten")), Some(100));
+//! ```
+//!
+//! [hashbrown]: https://docs.rs/hashbrown
+//! [Map]: crate::Map
+
+use std::borrow::Borrow;
+
+/// Key equivalence, used for lookups. Implementations must hash `self` the
+/// same way as the equivalent key, otherwise lookups will not find it.
+pub trait Equivalent<K: ?Sized> {
+    /// Return whether `self` is equivalent to `key`.
+    fn equivalent(&self, key: &K) -> bool;
+}
+
+impl<Q, K> Equivalent<K> for Q
+where
+    Q: PartialEq + ?Sized,
+    K: Borrow<Q> + ?Sized,
+{
+    #[inline]
+    fn equivalent(&self, key: &K) -> bool {
+        self == key.borrow()
+    }
+}
@@ -0,0 +1,253 @@
+//! Module implement [FrozenMap], a read-only view of a [Map].
+//!
+//! FrozenMap is built from a point in time snapshot of the map, refer to
+//! [Map::snapshot_iter]. Building a frozen view is a deep copy, every entry
+//! is cloned into a sorted vector, costing O(n) time and memory for a map
+//! of n entries. Once built, it shares nothing with the map it was frozen
+//! from, hence reads need no epoch management, the frozen view does not
+//! hold back garbage collection on the map, and subsequent writes to the map
+//! are not visible via the frozen view. Cloning a frozen view is cheap,
+//! clones share the same copy via [Arc].
+//!
+//! Entries are held in walk order, same as that of the map, refer to
+//! [to_walk_order]. Hence a frozen view can be merged with the live map, to
+//! compute the changes since the view was frozen, refer to
+//! [Map::changes_since].
+
+use std::{
+    cmp,
+    hash::{BuildHasher, Hash},
+    iter::Cloned,
+    slice,
+    sync::Arc,
+};
+
+use crate::{
+    iter::{to_walk_order, Merge, Merger, Ordered},
+    map::key_to_hash32,
+    DefaultHasher, Equivalent, Map,
+};
+
+impl<K, V, H> Map<K, V, H> {
+    /// Freeze the current state of the map into a read-only [FrozenMap].
+    /// Entries are cloned from a point in time snapshot of the map, this is
+    /// a full copy, O(n) in time and memory, and not a shared reference to
+    /// the map's trie.
+    pub fn freeze(&self) -> FrozenMap<K, V, H>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: Clone + BuildHasher,
+    {
+        self.freeze_with(false)
+    }
+
+    /// Same as [Map::freeze], and index the entries by the upper bits of
+    /// their walk order, so that a lookup is a single indexed load followed
+    /// by a search among a handful of entries, instead of a binary search
+    /// over all the entries. Index takes upto a word for each entry, suited
+    /// for serving reads off a large map once it is loaded.
+    pub fn freeze_compact(&self) -> FrozenMap<K, V, H>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: Clone + BuildHasher,
+    {
+        self.freeze_with(true)
+    }
+
+    fn freeze_with(&self, compact: bool) -> FrozenMap<K, V, H>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: Clone + BuildHasher,
+    {
+        // snapshot is already in walk order.
+        let mut entries: Vec<(u32, K, V)> = self
+            .snapshot_iter()
+            .map(|(key, value)| (to_walk_order(self.to_hash(&key)), key, value))
+            .collect();
+        entries.shrink_to_fit();
+
+        // about one entry for each bucket.
+        let bits = match entries.len() {
+            n if compact && n > 1 => {
+                cmp::min(usize::BITS - 1 - n.leading_zeros(), MAX_INDEX_BITS)
+            }
+            _ => 0,
+        };
+        let mut offsets = vec![0; (1 << bits) + 1];
+        for (order, _, _) in entries.iter() {
+            offsets[to_bucket(*order, bits) + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+
+        FrozenMap {
+            hash_builder: self.hash_builder.clone(),
+            entries: Arc::new(entries),
+            bits,
+            offsets: Arc::new(offsets),
+        }
+    }
+}
+
+// Upper limit on the bits indexed by freeze_compact().
+const MAX_INDEX_BITS: u32 = 24;
+
+// Bucket for walk `order`, indexed by its upper `bits`.
+#[inline]
+fn to_bucket(order: u32, bits: u32) -> usize {
+    ((order as u64) >> (32 - bits)) as usize
+}
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return an iterator over entries that changed since `frozen` was
+    /// frozen from this map, or from any other map using an equivalent
+    /// hasher. Yield [Merge::Left] for entries added since, [Merge::Right]
+    /// for entries removed since and [Merge::Both], with the current value
+    /// followed by the frozen value, for entries whose value has changed.
+    ///
+    /// Live map and the frozen view do not share trie nodes, hence both
+    /// are walked in full, though neither of them is buffered. Cost is
+    /// O(n + m), for n entries in the map and m entries in the frozen view,
+    /// however few the changes. Consistency guarantees, for the live map,
+    /// are same as that of [Map::iter].
+    pub fn changes_since<'a>(
+        &'a self,
+        frozen: &'a FrozenMap<K, V, H>,
+    ) -> Changes<'a, K, V, H>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        let right = frozen.entries.iter().cloned();
+        Changes {
+            inner: Merger::new(Ordered(self.iter()), right),
+        }
+    }
+}
+
+/// Changes is returned by [Map::changes_since].
+pub struct Changes<'a, K, V, H = DefaultHasher> {
+    inner: FrozenMerger<'a, K, V, H>,
+}
+
+type FrozenMerger<'a, K, V, H> =
+    Merger<K, V, Ordered<'a, K, V, H>, Cloned<slice::Iter<'a, (u32, K, V)>>>;
+
+impl<'a, K, V, H> Iterator for Changes<'a, K, V, H>
+where
+    K: Clone + PartialEq + Hash,
+    V: Clone + PartialEq,
+    H: BuildHasher,
+{
+    type Item = Merge<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .find(|item| !matches!(item, Merge::Both(_, new, old) if new == old))
+    }
+}
+
+/// FrozenMap is a read-only view of the map, returned by [Map::freeze].
+///
+/// A frozen view owns a copy of every entry, sorted in walk order, hence it
+/// takes as much memory as the keys and values it was frozen with. Lookups
+/// are a binary search over the copy, or an indexed bucket search for a
+/// view returned by [Map::freeze_compact].
+pub struct FrozenMap<K, V, H = DefaultHasher> {
+    hash_builder: H,
+    entries: Arc<Vec<(u32, K, V)>>,
+    // entries for bucket `b` are in offsets[b]..offsets[b+1], refer to
+    // Map::freeze_compact.
+    bits: u32,
+    offsets: Arc<Vec<usize>>,
+}
+
+impl<K, V, H> Clone for FrozenMap<K, V, H>
+where
+    H: Clone,
+{
+    fn clone(&self) -> Self {
+        FrozenMap {
+            hash_builder: self.hash_builder.clone(),
+            entries: Arc::clone(&self.entries),
+            bits: self.bits,
+            offsets: Arc::clone(&self.offsets),
+        }
+    }
+}
+
+impl<K, V, H> FrozenMap<K, V, H> {
+    /// Return the number of entries in the frozen view.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return whether the frozen view is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return the value for `key`, if present.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let order = to_walk_order(key_to_hash32(key, self.hash_builder.build_hasher()));
+        let b = to_bucket(order, self.bits);
+        let entries = &self.entries[self.offsets[b]..self.offsets[b + 1]];
+        let off = entries.partition_point(|(o, _, _)| *o < order);
+        entries[off..]
+            .iter()
+            .take_while(|(o, _, _)| *o == order)
+            .find(|(_, k, _)| key.equivalent(k))
+            .map(|(_, _, value)| value)
+    }
+
+    /// Return whether `key` is present.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Return an iterator over all entries in the frozen view, in walk
+    /// order.
+    pub fn iter(&self) -> FrozenIter<'_, K, V> {
+        FrozenIter {
+            iter: self.entries.iter(),
+        }
+    }
+}
+
+/// FrozenIter is returned by [FrozenMap::iter], yield a reference to each
+/// entry in the frozen view.
+pub struct FrozenIter<'a, K, V> {
+    iter: slice::Iter<'a, (u32, K, V)>,
+}
+
+impl<'a, K, V> Iterator for FrozenIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, key, value) = self.iter.next()?;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for FrozenIter<'a, K, V> {}
+
+#[cfg(test)]
+#[path = "frozen_test.rs"]
+mod frozen_test;
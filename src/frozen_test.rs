@@ -0,0 +1,161 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, thread};
+
+use super::*;
+use crate::iter::{
+    iter_test::{load_keys, seed_keys, with_hashers, CollisionHasher},
+    Merge,
+};
+
+#[test]
+fn test_freeze() {
+    let (seed, _, n_keys) = seed_keys("test_freeze");
+
+    with_hashers!(freeze_with, seed, n_keys);
+}
+
+fn freeze_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: 'static + Send + Sync + BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::new(2, hash_builder);
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let frozen = map.freeze();
+    assert_eq!(frozen.len(), btmap.len());
+    assert_eq!(frozen.is_empty(), btmap.is_empty());
+
+    // later writes to the map are not visible via the frozen view.
+    for key in btmap.keys() {
+        map.remove(key);
+    }
+    let key: u32 = rng.gen();
+    map.set(key, 0);
+    assert_eq!(frozen.contains_key(&key), btmap.contains_key(&key));
+
+    let handle = {
+        let (frozen, btmap) = (frozen.clone(), btmap.clone());
+        thread::spawn(move || {
+            for (key, value) in btmap.iter() {
+                assert_eq!(frozen.get(key), Some(value), "key:{}", key);
+            }
+        })
+    };
+    handle.join().unwrap();
+
+    let mut items: Vec<(u32, u64)> = frozen.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(frozen.iter().len(), items.len());
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_changes_since() {
+    let (seed, _, n_keys) = seed_keys("test_changes_since");
+
+    with_hashers!(changes_since_with, seed, n_keys);
+}
+
+fn changes_since_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let space = (n_keys as u32).saturating_mul(2).max(1);
+    let mut map: Map<u32, u64, H> = Map::new(1, hash_builder);
+    let mut old: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>() % space, rng.gen::<u64>() % 4);
+        map.set(key, value);
+        old.insert(key, value);
+    }
+
+    let frozen = map.freeze();
+    assert_eq!(map.changes_since(&frozen).count(), 0);
+
+    // values are drawn from a small space, so some of the sets are no-op.
+    let mut new = old.clone();
+    for _i in 0..(n_keys / 10) {
+        let (key, value) = (rng.gen::<u32>() % space, rng.gen::<u64>() % 4);
+        match rng.gen::<u8>() % 2 {
+            0 => {
+                map.set(key, value);
+                new.insert(key, value);
+            }
+            _ => {
+                map.remove(&key);
+                new.remove(&key);
+            }
+        }
+    }
+
+    let mut items: Vec<Merge<u32, u64>> = map.changes_since(&frozen).collect();
+    let mut refs = vec![];
+    for (key, value) in new.iter() {
+        match old.get(key) {
+            Some(v) if v == value => (),
+            Some(v) => refs.push(Merge::Both(*key, *value, *v)),
+            None => refs.push(Merge::Left(*key, *value)),
+        }
+    }
+    for (key, value) in old.iter() {
+        if !new.contains_key(key) {
+            refs.push(Merge::Right(*key, *value))
+        }
+    }
+
+    let to_key = |item: &Merge<u32, u64>| match item {
+        Merge::Left(k, _) | Merge::Right(k, _) | Merge::Both(k, _, _) => *k,
+    };
+    items.sort_unstable_by_key(to_key);
+    refs.sort_unstable_by_key(to_key);
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_freeze_compact() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [0, 1, 2, 10, 1000, 100_000][rng.gen::<usize>() % 6];
+    println!("test_freeze_compact seed:{} n_keys:{}", seed, n_keys);
+
+    with_hashers!(freeze_compact_with, seed, n_keys);
+}
+
+fn freeze_compact_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: 'static + Send + Sync + BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::new(1, hash_builder);
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let (frozen, compact) = (map.freeze(), map.freeze_compact());
+    assert_eq!(compact.len(), btmap.len());
+    assert_eq!(compact.offsets.len(), (1 << compact.bits) + 1);
+    assert!(compact.bits <= MAX_INDEX_BITS);
+    assert!((1_usize << compact.bits) <= cmp::max(btmap.len(), 1));
+
+    for (key, value) in btmap.iter() {
+        assert_eq!(compact.get(key), Some(value), "key:{}", key);
+    }
+    for _i in 0..1000 {
+        let key: u32 = rng.gen();
+        assert_eq!(compact.get(&key), btmap.get(&key), "key:{}", key);
+    }
+
+    // same entries, in the same order.
+    let a: Vec<(&u32, &u64)> = frozen.iter().collect();
+    let b: Vec<(&u32, &u64)> = compact.iter().collect();
+    assert_eq!(a, b);
+
+    // compact view also merges with the live map.
+    map.set(rng.gen(), 0);
+    assert_eq!(map.changes_since(&compact).count(), 1);
+}
@@ -1,16 +1,191 @@
+#[cfg(feature = "leak-check")]
+use std::collections::HashSet;
 use std::{
-    fmt, ptr, result,
+    fmt, mem, ptr, result,
     sync::{
-        atomic::{AtomicPtr, AtomicU64, Ordering::SeqCst},
-        Arc,
+        atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering::SeqCst},
+        mpsc, Arc, Mutex, MutexGuard,
     },
+    time,
 };
 
-use crate::{map::Child, map::Node};
+use crate::{map::Child, map::Item, map::Node};
 
 // pub const EPOCH_PERIOD: time::Duration = time::Duration::from_millis(10);
 pub const MAX_POOL_SIZE: usize = 1024;
 
+/// Policy for memory released into a full pool, refer to
+/// [Map::set_pool_size][crate::Map::set_pool_size].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Spill {
+    /// Release the memory back to the allocator.
+    #[default]
+    Drop,
+    /// Push the memory into a pool shared by all the clones of the map,
+    /// holding upto `n` allocations of each kind. Instances whose pool is
+    /// empty will allocate from the shared pool before falling back to the
+    /// allocator.
+    Shared(usize),
+}
+
+const HISTOGRAM_BUCKETS: usize = 32;
+
+/// Histogram of durations, in power-of-two buckets of microseconds, refer
+/// to [GcStats][crate::GcStats].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [u64; HISTOGRAM_BUCKETS],
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed: time::Duration) {
+        let micros = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        let n = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[n.min(HISTOGRAM_BUCKETS - 1)] += 1;
+    }
+
+    fn merge(&mut self, other: &Histogram) {
+        for (a, b) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *a += b
+        }
+    }
+
+    /// Return the count of durations in each bucket. Bucket `i` counts
+    /// durations less than `2^i` microseconds, and not less than `2^(i-1)`,
+    /// last bucket counts all the durations beyond.
+    pub fn as_buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Return the number of durations recorded.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Return the upper bound of the bucket holding the `p`th percentile,
+    /// `p` is between 0.0 and 100.0. Return ZERO if histogram is empty.
+    pub fn percentile(&self, p: f64) -> time::Duration {
+        let rank = ((self.count() as f64) * p.clamp(0.0, 100.0) / 100.0).ceil() as u64;
+        let mut acc = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            acc += n;
+            if *n > 0 && acc >= rank {
+                return time::Duration::from_micros(1 << i);
+            }
+        }
+        time::Duration::ZERO
+    }
+
+    /// Return the upper bound of the longest duration recorded.
+    pub fn max(&self) -> time::Duration {
+        self.percentile(100.0)
+    }
+}
+
+// Latency histograms for garbage collection, shared by all the clones of a
+// map, accumulated as instances are dropped.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Latency {
+    // time taken by garbage_collect() passes.
+    pub gc_pass: Histogram,
+    // time between logical removal and physical free of reclaims.
+    pub reclaim_age: Histogram,
+}
+
+impl Latency {
+    pub fn merge(&mut self, other: &Latency) {
+        self.gc_pass.merge(&other.gc_pass);
+        self.reclaim_age.merge(&other.reclaim_age);
+    }
+}
+
+// Pools shared by all the clones of a map, refer to Spill::Shared.
+pub struct Pools<K, V> {
+    child_pool: Vec<Box<Child<K, V>>>,
+    node_trie_pool: Vec<Box<Node<K, V>>>,
+    node_list_pool: Vec<Box<Node<K, V>>>,
+    node_tomb_pool: Vec<Box<Node<K, V>>>,
+    #[allow(clippy::vec_box)]
+    reclaim_pool: Vec<Box<Reclaim<K, V>>>,
+
+    // allocations retired by all the clones, and pending collection.
+    #[cfg(feature = "leak-check")]
+    retired: HashSet<usize>,
+}
+
+impl<K, V> Default for Pools<K, V> {
+    fn default() -> Self {
+        Pools {
+            child_pool: Vec::default(),
+            node_trie_pool: Vec::default(),
+            node_list_pool: Vec::default(),
+            node_tomb_pool: Vec::default(),
+            reclaim_pool: Vec::default(),
+            #[cfg(feature = "leak-check")]
+            retired: HashSet::default(),
+        }
+    }
+}
+
+impl<K, V> Pools<K, V> {
+    #[allow(clippy::vec_box)]
+    fn to_bytes(&self) -> usize {
+        let nodes = [
+            &self.node_trie_pool,
+            &self.node_list_pool,
+            &self.node_tomb_pool,
+        ];
+        pools_bytes(&self.child_pool, &nodes, &self.reclaim_pool)
+    }
+
+    fn len(&self) -> usize {
+        self.child_pool.len()
+            + self.node_trie_pool.len()
+            + self.node_list_pool.len()
+            + self.node_tomb_pool.len()
+            + self.reclaim_pool.len()
+    }
+}
+
+// pop from the local pool, fall back to the shared pool.
+macro_rules! acquire {
+    ($this:ident, $pool:ident) => {{
+        match $this.$pool.pop() {
+            Some(val) => Some(val),
+            None => match $this.spill {
+                Spill::Shared(_) => $this.to_shared().$pool.pop(),
+                Spill::Drop => None,
+            },
+        }
+    }};
+}
+
+// push into the local pool, spill over to the shared pool, if allowed,
+// drop otherwise.
+macro_rules! release {
+    ($this:ident, $pool:ident, $val:ident) => {{
+        if $this.$pool.len() < $this.pool_size {
+            $this.$pool.push($val)
+        } else {
+            let spilled = match $this.spill {
+                Spill::Shared(n) => {
+                    let mut shared = $this.to_shared();
+                    if shared.$pool.len() < n {
+                        shared.$pool.push($val);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                Spill::Drop => false,
+            };
+            if !spilled {
+                $this.n_frees += 1
+            }
+        }
+    }};
+}
+
 // CAS operation
 
 pub struct Cas<K, V> {
@@ -28,19 +203,34 @@ pub struct Cas<K, V> {
 
     n_allocs: usize,
     n_frees: usize,
+    n_retries: usize,
+    n_compacts: usize,
+    // retries by operation, counting both CAS failures and tombs.
+    n_set_retries: usize,
+    n_remove_retries: usize,
+    n_compact_retries: usize,
+    latency: Latency,
+
+    pool_size: usize,
+    spill: Spill,
+    shared: Arc<Mutex<Pools<K, V>>>,
+
+    // items reclaimed, to be dropped by the destructor thread.
+    drops: Vec<Item<K, V>>,
+    dropper: Option<mpsc::Sender<Vec<Item<K, V>>>>,
 }
 
 impl<K, V> Drop for Cas<K, V> {
     fn drop(&mut self) {
-        debug_assert!(
+        paranoid_assert!(
             self.older.is_empty(),
             "invariant Cas::older should be ZERO on drop"
         );
-        debug_assert!(
+        paranoid_assert!(
             self.newer.is_empty(),
             "invariant Cas::newer should be ZERO on drop"
         );
-        debug_assert!(
+        paranoid_assert!(
             self.reclaims.is_empty(),
             "invariant Cas::reclaims should be ZERO on drop"
         );
@@ -61,7 +251,7 @@ impl<K, V> Drop for Cas<K, V> {
 }
 
 impl<K, V> Cas<K, V> {
-    pub fn new() -> Self {
+    pub fn new(shared: Arc<Mutex<Pools<K, V>>>) -> Self {
         Cas {
             reclaims: Vec::with_capacity(64),
             older: Vec::with_capacity(64),
@@ -75,17 +265,135 @@ impl<K, V> Cas<K, V> {
 
             n_allocs: 0,
             n_frees: 0,
+            n_retries: 0,
+            n_compacts: 0,
+            n_set_retries: 0,
+            n_remove_retries: 0,
+            n_compact_retries: 0,
+            latency: Latency::default(),
+
+            pool_size: MAX_POOL_SIZE,
+            spill: Spill::default(),
+            shared,
+
+            drops: Vec::default(),
+            dropper: None,
+        }
+    }
+
+    /// Hand over reclaimed items to `dropper`, instead of dropping them
+    /// inline.
+    pub fn set_dropper(&mut self, dropper: Option<mpsc::Sender<Vec<Item<K, V>>>>) {
+        self.dropper = dropper
+    }
+
+    pub fn to_dropper(&self) -> Option<mpsc::Sender<Vec<Item<K, V>>>> {
+        self.dropper.clone()
+    }
+
+    // send the reclaimed items to the destructor thread, if it has exited
+    // the items are dropped here.
+    fn flush_drops(&mut self) {
+        if let Some(dropper) = self.dropper.as_ref() {
+            if !self.drops.is_empty() {
+                dropper.send(mem::take(&mut self.drops)).ok();
+            }
+        }
+    }
+
+    fn to_shared(&self) -> MutexGuard<'_, Pools<K, V>> {
+        self.shared.lock().expect("pool lock poisoned")
+    }
+
+    /// Cap each pool to `size` allocations, and spill over as per `spill`.
+    /// Pools that are already larger than `size` are spilled right away.
+    pub fn set_pool_size(&mut self, size: usize, spill: Spill) {
+        self.pool_size = size;
+        self.spill = spill;
+
+        while self.child_pool.len() > size {
+            let val = self.child_pool.pop().unwrap();
+            release!(self, child_pool, val);
+        }
+        while self.node_trie_pool.len() > size {
+            let val = self.node_trie_pool.pop().unwrap();
+            release!(self, node_trie_pool, val);
+        }
+        while self.node_list_pool.len() > size {
+            let val = self.node_list_pool.pop().unwrap();
+            release!(self, node_list_pool, val);
+        }
+        while self.node_tomb_pool.len() > size {
+            let val = self.node_tomb_pool.pop().unwrap();
+            release!(self, node_tomb_pool, val);
+        }
+        while self.reclaim_pool.len() > size {
+            let val = self.reclaim_pool.pop().unwrap();
+            release!(self, reclaim_pool, val);
         }
     }
 
+    pub fn to_spill(&self) -> Spill {
+        self.spill
+    }
+
+    // bytes held by the pools of this instance and the shared pool.
+    pub fn to_pool_bytes(&self) -> usize {
+        let nodes = [
+            &self.node_trie_pool,
+            &self.node_list_pool,
+            &self.node_tomb_pool,
+        ];
+        let n = pools_bytes(&self.child_pool, &nodes, &self.reclaim_pool);
+        n + self.to_shared().to_bytes()
+    }
+
+    // bytes held by reclaims pending garbage collection.
+    pub fn to_reclaim_bytes(&self) -> usize {
+        self.reclaims.iter().map(|r| r.to_bytes()).sum()
+    }
+
+    // track allocations retired via a successful swing(), retiring the
+    // same allocation twice would lead to a double free.
+    #[cfg(feature = "leak-check")]
+    fn retire(&self, addr: usize) {
+        if !self.to_shared().retired.insert(addr) {
+            panic!("leak-check: allocation {:x} retired twice", addr)
+        }
+    }
+
+    #[cfg(feature = "leak-check")]
+    fn unretire(&self, addr: usize) {
+        if !self.to_shared().retired.remove(&addr) {
+            panic!("leak-check: allocation {:x} was not retired", addr)
+        }
+    }
+
+    // number of allocations retired, by all the clones, and pending
+    // collection.
+    #[cfg(feature = "leak-check")]
+    pub fn to_retired_count(&self) -> usize {
+        self.to_shared().retired.len()
+    }
+
+    pub fn to_latency(&self) -> Latency {
+        self.latency
+    }
+
+    // return the latency recorded since the last call, used to publish
+    // the latency of long running instances.
+    pub fn take_latency(&mut self) -> Latency {
+        mem::take(&mut self.latency)
+    }
+
+    // number of allocations held in the pool shared by all the clones.
+    pub fn to_shared_pooled_count(&self) -> usize {
+        self.to_shared().len()
+    }
+
     pub fn to_pools_len(&self) -> usize {
-        self.child_pool.len()
-            + self.node_trie_pool.len()
-            + self.node_list_pool.len()
-            + self.node_tomb_pool.len()
-            + self.reclaim_pool.len()
-            + self.reclaims.len()
-            + self.reclaims.iter().map(|r| r.items.len()).sum::<usize>()
+        let (n_reclaims, n_items) = self.to_reclaim_counts();
+        self.to_pooled_count() + n_reclaims + n_items
     }
 
     pub fn to_alloc_count(&self) -> usize {
@@ -96,6 +404,58 @@ impl<K, V> Cas<K, V> {
         self.n_frees
     }
 
+    pub fn to_retry_count(&self) -> usize {
+        self.n_retries
+    }
+
+    pub fn to_compact_count(&self) -> usize {
+        self.n_compacts
+    }
+
+    pub fn count_frees(&mut self, n: usize) {
+        self.n_frees += n
+    }
+
+    pub fn count_compact(&mut self) {
+        self.n_compacts += 1
+    }
+
+    pub fn to_op_retry_counts(&self) -> (usize, usize, usize) {
+        (
+            self.n_set_retries,
+            self.n_remove_retries,
+            self.n_compact_retries,
+        )
+    }
+
+    pub fn count_set_retries(&mut self, n: usize) {
+        self.n_set_retries += n
+    }
+
+    pub fn count_remove_retries(&mut self, n: usize) {
+        self.n_remove_retries += n
+    }
+
+    pub fn count_compact_retries(&mut self, n: usize) {
+        self.n_compact_retries += n
+    }
+
+    // number of allocations held in pools, for reuse.
+    pub fn to_pooled_count(&self) -> usize {
+        self.child_pool.len()
+            + self.node_trie_pool.len()
+            + self.node_list_pool.len()
+            + self.node_tomb_pool.len()
+            + self.reclaim_pool.len()
+    }
+
+    // number of reclaims, and the allocations held by them, pending on
+    // garbage collection.
+    pub fn to_reclaim_counts(&self) -> (usize, usize) {
+        let n_items = self.reclaims.iter().map(|r| r.items.len()).sum::<usize>();
+        (self.reclaims.len(), n_items)
+    }
+
     pub fn has_reclaims(&self) -> bool {
         !self.reclaims.is_empty()
     }
@@ -123,39 +483,23 @@ impl<K, V> Cas<K, V> {
     }
 
     pub fn alloc_node(&mut self, variant: char) -> Box<Node<K, V>> {
-        match variant {
-            'l' => match self.node_list_pool.pop() {
-                Some(val) => val,
-                None => {
-                    self.n_allocs += 1;
-                    Box::new(Node::List {
-                        items: Vec::with_capacity(2), // **IMPORTANT**
-                    })
-                }
-            },
-            't' => match self.node_trie_pool.pop() {
-                Some(val) => val,
-                None => {
-                    self.n_allocs += 1;
-                    Box::new(Node::Trie {
-                        bmp: 0,
-                        childs: Vec::with_capacity(1),
-                    })
-                }
-            },
-            'b' => match self.node_tomb_pool.pop() {
-                Some(val) => val,
-                None => {
-                    self.n_allocs += 1;
-                    Box::new(Node::Tomb { item: None })
-                }
-            },
+        let val = match variant {
+            'l' => acquire!(self, node_list_pool),
+            't' => acquire!(self, node_trie_pool),
+            'b' => acquire!(self, node_tomb_pool),
             _ => unreachable!(),
+        };
+        match val {
+            Some(val) => val,
+            None => {
+                self.n_allocs += 1;
+                new_node(variant)
+            }
         }
     }
 
     pub fn alloc_child(&mut self) -> Box<Child<K, V>> {
-        match self.child_pool.pop() {
+        match acquire!(self, child_pool) {
             Some(val) => val,
             None => {
                 self.n_allocs += 1;
@@ -165,7 +509,7 @@ impl<K, V> Cas<K, V> {
     }
 
     pub fn alloc_reclaim(&mut self) -> Box<Reclaim<K, V>> {
-        match self.reclaim_pool.pop() {
+        match acquire!(self, reclaim_pool) {
             Some(val) => val,
             None => {
                 self.n_allocs += 1;
@@ -174,54 +518,123 @@ impl<K, V> Cas<K, V> {
         }
     }
 
+    /// Pre-populate the pools, so that the next `n` mutations, in the
+    /// absence of garbage collection, need not hit the allocator. Will not
+    /// populate a pool beyond its configured size.
+    pub fn reserve(&mut self, n: usize) {
+        let n = usize::min(n, self.pool_size);
+        // every mutation allocate atleast a trie-node, child and reclaim, list
+        // nodes and tomb nodes are allocated only on collisions and compaction.
+        let m = n / 8;
+
+        while self.node_trie_pool.len() < n {
+            self.n_allocs += 1;
+            self.node_trie_pool.push(new_node('t'))
+        }
+        while self.node_list_pool.len() < m {
+            self.n_allocs += 1;
+            self.node_list_pool.push(new_node('l'))
+        }
+        while self.node_tomb_pool.len() < m {
+            self.n_allocs += 1;
+            self.node_tomb_pool.push(new_node('b'))
+        }
+        while self.child_pool.len() < n {
+            self.n_allocs += 1;
+            self.child_pool.push(Box::new(Child::default()))
+        }
+        while self.reclaim_pool.len() < n {
+            self.n_allocs += 1;
+            self.reclaim_pool.push(Box::new(Reclaim::default()))
+        }
+    }
+
+    /// Release all pooled memory, including the shared pool, back to the
+    /// allocator, and shrink the book-keeping vectors. Pending reclaims are
+    /// left untouched. Return the number of allocations released.
+    pub fn trim(&mut self) -> usize {
+        let n = {
+            let mut shared = self.to_shared();
+            let n = shared.len();
+            shared.child_pool = Vec::default();
+            shared.node_trie_pool = Vec::default();
+            shared.node_list_pool = Vec::default();
+            shared.node_tomb_pool = Vec::default();
+            shared.reclaim_pool = Vec::default();
+            n
+        };
+        let n = n + self.to_pooled_count();
+        self.n_frees += n;
+
+        self.child_pool = Vec::default();
+        self.node_trie_pool = Vec::default();
+        self.node_list_pool = Vec::default();
+        self.node_tomb_pool = Vec::default();
+        self.reclaim_pool = Vec::default();
+
+        self.reclaims.shrink_to_fit();
+        self.older.shrink_to_fit();
+        self.newer.shrink_to_fit();
+
+        n
+    }
+
     pub fn free_node(&mut self, mut node: Box<Node<K, V>>) {
-        let pool = match node.as_mut() {
+        let deferred = self.dropper.is_some();
+        match node.as_mut() {
             Node::Trie { bmp, childs } => {
                 *bmp = 0;
                 childs.clear();
-                &mut self.node_trie_pool
+                release!(self, node_trie_pool, node)
             }
             Node::List { items } => {
-                items.clear();
-                &mut self.node_list_pool
+                if deferred {
+                    self.drops.append(items)
+                } else {
+                    items.clear()
+                }
+                release!(self, node_list_pool, node)
+            }
+            Node::Tomb { item } => {
+                match item.take() {
+                    Some(item) if deferred => self.drops.push(item),
+                    _ => (),
+                }
+                release!(self, node_tomb_pool, node)
             }
-            Node::Tomb { .. } => &mut self.node_tomb_pool,
-        };
-        if pool.len() < MAX_POOL_SIZE {
-            pool.push(node)
-        } else {
-            self.n_frees += 1
         }
     }
 
-    pub fn free_child(&mut self, child: Box<Child<K, V>>) {
-        if self.child_pool.len() < MAX_POOL_SIZE {
-            self.child_pool.push(child)
-        } else {
-            self.n_frees += 1
+    pub fn free_child(&mut self, mut child: Box<Child<K, V>>) {
+        // drop the item, if any, while the child is pooled.
+        match mem::take(child.as_mut()) {
+            Child::Leaf(item) if self.dropper.is_some() => self.drops.push(item),
+            _ => (),
         }
+        release!(self, child_pool, child)
     }
 
     pub fn free_reclaim(&mut self, reclaim: Box<Reclaim<K, V>>) {
-        if self.reclaim_pool.len() < MAX_POOL_SIZE {
-            self.reclaim_pool.push(reclaim)
-        } else {
-            self.n_frees += 1
-        }
+        release!(self, reclaim_pool, reclaim)
     }
 
     pub fn swing<T>(
         &mut self,
-        epoch: &Arc<AtomicU64>,
+        epoch: &AtomicU64,
         loc: &AtomicPtr<T>,
         old: *mut T,
         new: *mut T,
     ) -> bool {
         match loc.compare_exchange(old, new, SeqCst, SeqCst) {
             Ok(_) => {
+                // older values are retired only once swing succeeds,
+                // concurrent writers may speculatively free the same values.
+                #[cfg(feature = "leak-check")]
+                self.older.iter().for_each(|om| self.retire(om.to_addr()));
                 let r = {
                     let mut r = self.alloc_reclaim();
                     r.epoch = Some(epoch.load(SeqCst));
+                    r.since = Some(time::Instant::now());
                     r.drain_items_from(&mut self.older);
                     r
                 };
@@ -230,6 +643,7 @@ impl<K, V> Cas<K, V> {
                 true
             }
             Err(_) => {
+                self.n_retries += 1;
                 unsafe { self.older.set_len(0) }; // leak older values.
                 while let Some(om) = self.newer.pop() {
                     match om {
@@ -238,18 +652,30 @@ impl<K, V> Cas<K, V> {
                         OwnedMem::None => (),
                     }
                 }
+                self.flush_drops();
                 false
             }
         }
     }
 
     pub fn garbage_collect(&mut self, gc_epoch: u64) {
+        let since = time::Instant::now();
+        paranoid_assert!(
+            self.reclaims.iter().all(|r| r.epoch.is_some()),
+            "pending reclaim without epoch"
+        );
         let n = self.reclaims.len();
         for i in (0..n).rev() {
             match self.reclaims[i].epoch {
                 Some(epoch) if epoch < gc_epoch => {
                     let mut r = self.reclaims.remove(i);
+                    if let Some(removed) = r.since.take() {
+                        let age = since.saturating_duration_since(removed);
+                        self.latency.reclaim_age.record(age);
+                    }
                     while let Some(om) = r.items.pop() {
+                        #[cfg(feature = "leak-check")]
+                        self.unretire(om.to_addr());
                         match om {
                             OwnedMem::Child(val) => self.free_child(val),
                             OwnedMem::Node(val) => self.free_node(val),
@@ -262,37 +688,168 @@ impl<K, V> Cas<K, V> {
                 Some(_) | None => (),
             }
         }
+        self.flush_drops();
+        // passes with nothing pending are not accounted.
+        if n > 0 {
+            self.latency.gc_pass.record(since.elapsed())
+        }
     }
 
     pub fn validate(&self) {
         let n = self.reclaims.len();
-        debug_assert!(n < 512, "reclaims:{}", n);
+        paranoid_assert!(n < 512, "reclaims:{}", n);
 
         let n = self.older.len();
-        debug_assert!(n < 512, "older:{}", n);
+        paranoid_assert!(n < 512, "older:{}", n);
 
         let n = self.newer.len();
-        debug_assert!(n < 512, "newer:{}", n);
+        paranoid_assert!(n < 512, "newer:{}", n);
 
         let n = self.child_pool.len();
-        debug_assert!(n < 512, "child_pool:{}", n);
+        paranoid_assert!(n <= self.pool_size, "child_pool:{}", n);
 
         let n = self.node_trie_pool.len();
-        debug_assert!(n < 512, "node_trie_pool:{}", n);
+        paranoid_assert!(n <= self.pool_size, "node_trie_pool:{}", n);
 
         let n = self.node_list_pool.len();
-        debug_assert!(n < 512, "node_list_pool:{}", n);
+        paranoid_assert!(n <= self.pool_size, "node_list_pool:{}", n);
 
         let n = self.node_tomb_pool.len();
-        debug_assert!(n < 512, "node_tomb_pool:{}", n);
+        paranoid_assert!(n <= self.pool_size, "node_tomb_pool:{}", n);
 
         let n = self.reclaim_pool.len();
-        debug_assert!(n < 512, "reclaim_pool:{}", n);
+        paranoid_assert!(n <= self.pool_size, "reclaim_pool:{}", n);
+    }
+}
+
+// Background garbage collector shared by all the clones of a map, refer to
+// Map::spawn_gc. While running, mutating threads hand over their reclaims
+// to the collector instead of collecting them inline. Reclaims left over
+// by dropped instances are queued here as well, for the surviving ones.
+pub struct Collector<K, V> {
+    queue: Mutex<Queue<K, V>>,
+    // number of reclaims in queue, to skip the lock when there are none.
+    n_queued: AtomicUsize,
+}
+
+struct Queue<K, V> {
+    running: bool,
+    #[allow(clippy::vec_box)]
+    reclaims: Vec<Box<Reclaim<K, V>>>,
+}
+
+impl<K, V> Default for Collector<K, V> {
+    fn default() -> Self {
+        let queue = Queue {
+            running: false,
+            reclaims: Vec::default(),
+        };
+        Collector {
+            queue: Mutex::new(queue),
+            n_queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<K, V> Collector<K, V> {
+    fn to_queue(&self) -> MutexGuard<'_, Queue<K, V>> {
+        self.queue.lock().expect("collector lock poisoned")
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.to_queue().running
+    }
+
+    // return false if the collector is already running.
+    pub fn start(&self) -> bool {
+        let mut queue = self.to_queue();
+        let ok = !queue.running;
+        queue.running = true;
+        ok
+    }
+
+    pub fn stop(&self) {
+        self.to_queue().running = false
+    }
+
+    // move reclaims from `cas` to the collector, return false if the
+    // collector is not running, in which case `cas` is left untouched.
+    pub fn hand_off(&self, cas: &mut Cas<K, V>) -> bool {
+        let mut queue = self.to_queue();
+        if queue.running {
+            queue.reclaims.append(&mut cas.reclaims);
+            self.n_queued.store(queue.reclaims.len(), SeqCst);
+        }
+        queue.running
+    }
+
+    // move reclaims from `cas` to the collector, irrespective of whether
+    // the collector is running.
+    pub fn give_back(&self, cas: &mut Cas<K, V>) {
+        if cas.reclaims.is_empty() {
+            return;
+        }
+        let mut queue = self.to_queue();
+        queue.reclaims.append(&mut cas.reclaims);
+        self.n_queued.store(queue.reclaims.len(), SeqCst);
+    }
+
+    // move all reclaims held by the collector to `cas`.
+    pub fn take_back(&self, cas: &mut Cas<K, V>) {
+        if self.n_queued.load(SeqCst) == 0 {
+            return;
+        }
+        let mut queue = self.to_queue();
+        cas.reclaims.append(&mut queue.reclaims);
+        self.n_queued.store(0, SeqCst);
+    }
+
+    // bytes held by reclaims held by the collector.
+    pub fn to_reclaim_bytes(&self) -> usize {
+        self.to_queue().reclaims.iter().map(|r| r.to_bytes()).sum()
+    }
+
+    // number of reclaims, and the allocations held by them, held by the
+    // collector.
+    pub fn to_reclaim_counts(&self) -> (usize, usize) {
+        let queue = self.to_queue();
+        let n_items = queue.reclaims.iter().map(|r| r.items.len()).sum::<usize>();
+        (queue.reclaims.len(), n_items)
+    }
+}
+
+#[allow(clippy::vec_box)]
+fn pools_bytes<K, V>(
+    childs: &[Box<Child<K, V>>],
+    nodes: &[&Vec<Box<Node<K, V>>>],
+    reclaims: &[Box<Reclaim<K, V>>],
+) -> usize {
+    let n = childs.len() * mem::size_of::<Child<K, V>>();
+    let m: usize = nodes
+        .iter()
+        .flat_map(|pool| pool.iter())
+        .map(|node| node.to_node_bytes())
+        .sum();
+    n + m + reclaims.iter().map(|r| r.to_bytes()).sum::<usize>()
+}
+
+fn new_node<K, V>(variant: char) -> Box<Node<K, V>> {
+    match variant {
+        'l' => Box::new(Node::List {
+            items: Vec::with_capacity(2), // **IMPORTANT**
+        }),
+        't' => Box::new(Node::Trie {
+            bmp: 0,
+            childs: Vec::with_capacity(1),
+        }),
+        'b' => Box::new(Node::Tomb { item: None }),
+        _ => unreachable!(),
     }
 }
 
 pub struct Reclaim<K, V> {
     epoch: Option<u64>,
+    since: Option<time::Instant>,
     items: Vec<OwnedMem<K, V>>,
 }
 
@@ -316,14 +873,30 @@ impl<K, V> Default for Reclaim<K, V> {
     fn default() -> Self {
         Reclaim {
             epoch: None,
+            since: None,
             items: Vec::with_capacity(2),
         }
     }
 }
 
 impl<K, V> Reclaim<K, V> {
+    // bytes held by this reclaim and the allocations pending on it.
+    fn to_bytes(&self) -> usize {
+        let n = self.items.capacity() * mem::size_of::<OwnedMem<K, V>>();
+        let m: usize = self
+            .items
+            .iter()
+            .map(|item| match item {
+                OwnedMem::Child(_) => mem::size_of::<Child<K, V>>(),
+                OwnedMem::Node(node) => node.to_node_bytes(),
+                OwnedMem::None => 0,
+            })
+            .sum();
+        mem::size_of::<Reclaim<K, V>>() + n + m
+    }
+
     fn drain_items_from(&mut self, items: &mut Vec<OwnedMem<K, V>>) {
-        debug_assert!(self.items.is_empty(), "reclaim items {}", self.items.len());
+        paranoid_assert!(self.items.is_empty(), "reclaim items {}", self.items.len());
 
         self.items.reserve_exact(items.len());
         unsafe {
@@ -339,14 +912,21 @@ pub enum Mem<K, V> {
     Node(*mut Node<K, V>),
 }
 
+#[derive(Default)]
 enum OwnedMem<K, V> {
     Child(Box<Child<K, V>>),
     Node(Box<Node<K, V>>),
+    #[default]
     None,
 }
 
-impl<K, V> Default for OwnedMem<K, V> {
-    fn default() -> Self {
-        OwnedMem::None
+#[cfg(feature = "leak-check")]
+impl<K, V> OwnedMem<K, V> {
+    fn to_addr(&self) -> usize {
+        match self {
+            OwnedMem::Child(val) => val.as_ref() as *const Child<K, V> as usize,
+            OwnedMem::Node(val) => val.as_ref() as *const Node<K, V> as usize,
+            OwnedMem::None => 0,
+        }
     }
 }
@@ -1,34 +1,176 @@
 use fasthash::city::crc::{Hash128, Hasher128};
 
 use std::{
+    collections::hash_map::RandomState,
     convert::TryInto,
     hash::{BuildHasher, Hasher},
 };
 
-/// Type uses google's city hash to convert [Hash]able key into ``u32``.
+/// Type uses keyed SipHash-1-3 to convert [Hash]able key into ``u32``.
+///
+/// Keys are randomly generated for every new instance, and shared by its
+/// clones, hence hash-flooding attacks with untrusted keys, that would
+/// otherwise force deep collision lists, are not practical. Create a new
+/// instance for every new map, so that two maps don't share collision
+/// patterns. Applications that trust their keys can opt into faster hashers
+/// like [CityHasher].
+#[derive(Clone)]
 pub struct DefaultHasher {
-    hash_builder: Hash128,
+    k0: u64,
+    k1: u64,
 }
 
 impl DefaultHasher {
-    #[allow(clippy::new_without_default)] // TODO: Hash128 does not implement default.
+    /// Create a new hasher with random keys.
+    #[allow(clippy::new_without_default)]
     pub fn new() -> DefaultHasher {
+        let state = RandomState::new();
+        DefaultHasher {
+            k0: state.hash_one(0_u64),
+            k1: state.hash_one(1_u64),
+        }
+    }
+
+    /// Create a new hasher keyed with `seed`, hashes are deterministic for
+    /// the same seed. Useful for testing and for hashing that must match
+    /// across processes, but not to be used with untrusted keys, unless the
+    /// seed is kept secret.
+    pub fn with_seed(seed: u128) -> DefaultHasher {
         DefaultHasher {
+            k0: seed as u64,
+            k1: (seed >> 64) as u64,
+        }
+    }
+}
+
+impl BuildHasher for DefaultHasher {
+    type Hasher = SipHasher13;
+
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        SipHasher13::new_with_keys(self.k0, self.k1)
+    }
+}
+
+/// SipHash-1-3 hasher, returned by [DefaultHasher]. This is the same
+/// algorithm used by [std::collections::HashMap].
+#[derive(Clone)]
+pub struct SipHasher13 {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    tail: u64,
+    ntail: usize,
+    length: usize,
+}
+
+impl SipHasher13 {
+    fn new_with_keys(k0: u64, k1: u64) -> SipHasher13 {
+        SipHasher13 {
+            v0: k0 ^ 0x736f6d6570736575,
+            v1: k1 ^ 0x646f72616e646f6d,
+            v2: k0 ^ 0x6c7967656e657261,
+            v3: k1 ^ 0x7465646279746573,
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    #[inline]
+    fn round(&mut self) {
+        self.v0 = self.v0.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(13);
+        self.v1 ^= self.v0;
+        self.v0 = self.v0.rotate_left(32);
+        self.v2 = self.v2.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(16);
+        self.v3 ^= self.v2;
+        self.v0 = self.v0.wrapping_add(self.v3);
+        self.v3 = self.v3.rotate_left(21);
+        self.v3 ^= self.v0;
+        self.v2 = self.v2.wrapping_add(self.v1);
+        self.v1 = self.v1.rotate_left(17);
+        self.v1 ^= self.v2;
+        self.v2 = self.v2.rotate_left(32);
+    }
+
+    #[inline]
+    fn compress(&mut self, m: u64) {
+        self.v3 ^= m;
+        self.round();
+        self.v0 ^= m;
+    }
+}
+
+impl Hasher for SipHasher13 {
+    fn write(&mut self, mut msg: &[u8]) {
+        self.length += msg.len();
+
+        if self.ntail > 0 {
+            while self.ntail < 8 && !msg.is_empty() {
+                self.tail |= (msg[0] as u64) << (8 * self.ntail);
+                self.ntail += 1;
+                msg = &msg[1..];
+            }
+            if self.ntail < 8 {
+                return;
+            }
+            self.compress(self.tail);
+            self.tail = 0;
+            self.ntail = 0;
+        }
+
+        let mut chunks = msg.chunks_exact(8);
+        for chunk in chunks.by_ref() {
+            self.compress(u64::from_le_bytes(chunk.try_into().unwrap()))
+        }
+        for (i, byte) in chunks.remainder().iter().enumerate() {
+            self.tail |= (*byte as u64) << (8 * i);
+        }
+        self.ntail = chunks.remainder().len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        let b: u64 = (((self.length as u64) & 0xff) << 56) | self.tail;
+
+        state.compress(b);
+        state.v2 ^= 0xff;
+        state.round();
+        state.round();
+        state.round();
+
+        state.v0 ^ state.v1 ^ state.v2 ^ state.v3
+    }
+}
+
+/// Type uses google's city hash to convert [Hash]able key into ``u32``.
+/// City hash is not keyed, use this only with trusted keys.
+pub struct CityHasher {
+    hash_builder: Hash128,
+}
+
+impl CityHasher {
+    #[allow(clippy::new_without_default)] // TODO: Hash128 does not implement default.
+    pub fn new() -> CityHasher {
+        CityHasher {
             hash_builder: Hash128,
         }
     }
 }
 
-impl Clone for DefaultHasher {
+impl Clone for CityHasher {
     #[inline]
     fn clone(&self) -> Self {
-        DefaultHasher {
+        CityHasher {
             hash_builder: Hash128,
         }
     }
 }
 
-impl BuildHasher for DefaultHasher {
+impl BuildHasher for CityHasher {
     type Hasher = Hasher128;
 
     #[inline]
@@ -37,6 +179,16 @@ impl BuildHasher for DefaultHasher {
     }
 }
 
+/// [BuildHasher] from [ahash] package, randomly keyed for every new
+/// instance. **Available only with `ahash` feature.**
+#[cfg(feature = "ahash")]
+pub type AHasher = ahash::RandomState;
+
+/// [BuildHasher] from [fxhash] package, fast but not keyed, use this only
+/// with trusted keys. **Available only with `fxhash` feature.**
+#[cfg(feature = "fxhash")]
+pub type FxHasher = fxhash::FxBuildHasher;
+
 /// Type implement [BuildHasher] optimized for ``u32`` key set.
 #[derive(Clone, Default)]
 pub struct U32Hasher {
@@ -59,8 +211,225 @@ impl Hasher for U32Hasher {
         self.key = u32::from_le_bytes(bytes.try_into().unwrap());
     }
 
+    // upper hash marker, refer to key_to_upper64() in map.rs, ignored.
+    #[inline]
+    fn write_u8(&mut self, _marker: u8) {}
+
     #[inline]
     fn finish(&self) -> u64 {
         self.key.into()
     }
 }
+
+/// Type implement [BuildHasher] for ``u64`` key set, like random
+/// identifiers, whose bits can be consumed by the trie as is. Use this with
+/// [HashWidth::Bits64], so that all the 64 bits of the key index the trie
+/// instead of folding them into 32 bits.
+///
+/// [HashWidth::Bits64]: crate::HashWidth::Bits64
+#[derive(Clone, Default)]
+pub struct U64Hasher {
+    key: u64,
+}
+
+impl BuildHasher for U64Hasher {
+    type Hasher = Self;
+
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        self.clone()
+    }
+}
+
+impl Hasher for U64Hasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        debug_assert!(bytes.len() == 8, "for U64Hasher invalid bytes:{:?}", bytes);
+        self.key = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    // upper hash marker, refer to key_to_upper64() in map.rs, ignored.
+    #[inline]
+    fn write_u8(&mut self, _marker: u8) {}
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.key
+    }
+}
+
+/// Type implement [BuildHasher] for ``u128`` key set, like random UUIDs,
+/// whose bits can be consumed by the trie as is. Use this with
+/// [HashWidth::Bits128], lower 64 bits of the key index the first 16
+/// levels of the trie and upper 64 bits the next 16 levels.
+///
+/// [HashWidth::Bits128]: crate::HashWidth::Bits128
+#[derive(Clone, Default)]
+pub struct U128Hasher {
+    key: u128,
+    upper: bool,
+}
+
+impl BuildHasher for U128Hasher {
+    type Hasher = Self;
+
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        self.clone()
+    }
+}
+
+impl Hasher for U128Hasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        debug_assert!(
+            bytes.len() == 16,
+            "for U128Hasher invalid bytes:{:?}",
+            bytes
+        );
+        self.key = u128::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    // upper hash marker, refer to key_to_upper64() in map.rs.
+    #[inline]
+    fn write_u8(&mut self, _marker: u8) {
+        self.upper = true;
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        match self.upper {
+            true => (self.key >> 64) as u64,
+            false => self.key as u64,
+        }
+    }
+}
+
+// Multiplier for multiply-shift hashing, 2^64 divided by golden ratio.
+const KEY_HASH_MULT: u64 = 0x9E3779B97F4A7C15;
+
+/// Trait for fixed width keys that can be hashed with a single
+/// multiply-shift, refer to [KeyHasher]. Implemented for all the primitive
+/// integer types.
+pub trait KeyHash {
+    /// Return the 64-bit hash for this key.
+    fn key_hash(&self) -> u64;
+}
+
+macro_rules! impl_key_hash {
+    ($($t:ty),*) => {$(
+        impl KeyHash for $t {
+            #[inline]
+            fn key_hash(&self) -> u64 {
+                (*self as u64).wrapping_mul(KEY_HASH_MULT)
+            }
+        }
+    )*};
+}
+
+impl_key_hash!(u8, u16, u32, u64, usize);
+
+// signed integers hash same as their unsigned counterpart, same as Hash.
+macro_rules! impl_key_hash_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl KeyHash for $t {
+            #[inline]
+            fn key_hash(&self) -> u64 {
+                (*self as $u).key_hash()
+            }
+        }
+    )*};
+}
+
+impl_key_hash_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize, i128 => u128);
+
+impl KeyHash for u128 {
+    #[inline]
+    fn key_hash(&self) -> u64 {
+        ((*self as u64) ^ ((*self >> 64) as u64)).wrapping_mul(KEY_HASH_MULT)
+    }
+}
+
+/// Type implement [BuildHasher] for fixed width keys, that hash the key
+/// using [KeyHash], instead of a hasher's state machine. For integer keys,
+/// and types deriving [Hash] over a single integer, hash of the key is
+/// same as its [KeyHash::key_hash]. Other keys are hashed 8 bytes at a
+/// time, same as [fxhash][fxhash].
+///
+/// Like [CityHasher], this is not keyed, use this only with trusted keys.
+///
+/// [fxhash]: https://github.com/cbreeden/fxhash
+#[derive(Clone, Default)]
+pub struct KeyHasher {
+    code: u64,
+}
+
+impl BuildHasher for KeyHasher {
+    type Hasher = Self;
+
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        self.clone()
+    }
+}
+
+impl KeyHasher {
+    #[inline]
+    fn add(&mut self, code: u64) {
+        self.code = self.code.rotate_left(5) ^ code;
+    }
+}
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in chunks.by_ref() {
+            self.write_u64(u64::from_le_bytes(chunk.try_into().unwrap()))
+        }
+        let mut tail = [0_u8; 8];
+        let rem = chunks.remainder();
+        if !rem.is_empty() {
+            tail[..rem.len()].copy_from_slice(rem);
+            self.write_u64(u64::from_le_bytes(tail))
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(i.key_hash())
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(i.key_hash())
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(i.key_hash())
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i.key_hash())
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.add(i.key_hash())
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i.key_hash())
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.code
+    }
+}
+
+#[cfg(test)]
+#[path = "hasher_test.rs"]
+mod hasher_test;
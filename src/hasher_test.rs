@@ -0,0 +1,207 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, fmt::Debug, hash::Hash};
+
+use super::*;
+
+#[test]
+fn test_default_hasher() {
+    let (a, b) = (DefaultHasher::new(), DefaultHasher::new());
+    let c = a.clone();
+
+    // clones share the keys, new instances are keyed differently.
+    let keys: Vec<u64> = (0..100).collect();
+    assert!(keys.iter().all(|k| a.hash_one(k) == c.hash_one(k)));
+    assert!(keys.iter().any(|k| a.hash_one(k) != b.hash_one(k)));
+}
+
+#[test]
+fn test_city_hasher() {
+    let (a, b) = (CityHasher::new(), CityHasher::new());
+
+    let keys: Vec<u64> = (0..100).collect();
+    assert!(keys.iter().all(|k| a.hash_one(k) == b.hash_one(k)));
+}
+
+#[test]
+fn test_sip_hasher13() {
+    use std::collections::hash_map;
+
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_sip_hasher13 seed:{}", seed);
+
+    // std's DefaultHasher is SipHash-1-3 with zero keys.
+    for _i in 0..1000 {
+        let mut a = SipHasher13::new_with_keys(0, 0);
+        let mut b = hash_map::DefaultHasher::new();
+        for _j in 0..rng.gen::<usize>() % 4 {
+            let bytes: Vec<u8> =
+                (0..rng.gen::<usize>() % 20).map(|_| rng.gen()).collect();
+            a.write(&bytes);
+            b.write(&bytes);
+        }
+        assert_eq!(a.finish(), b.finish());
+    }
+
+    let (a, b) = (
+        DefaultHasher::with_seed(seed),
+        DefaultHasher::with_seed(seed),
+    );
+    let keys: Vec<u64> = (0..100).collect();
+    assert!(keys.iter().all(|k| a.hash_one(k) == b.hash_one(k)));
+    let c = DefaultHasher::with_seed(seed.wrapping_add(1));
+    assert!(keys.iter().any(|k| a.hash_one(k) != c.hash_one(k)));
+}
+
+#[cfg(all(feature = "ahash", feature = "fxhash"))]
+#[test]
+fn test_feature_hashers() {
+    use crate::Map;
+
+    let mut amap: Map<u64, u64, AHasher> = Map::new(1, AHasher::new());
+    let mut fmap: Map<u64, u64, FxHasher> = Map::new(1, FxHasher::default());
+    for key in 0..10_000 {
+        amap.set(key, key + 1);
+        fmap.set(key, key + 1);
+    }
+    for key in 0..10_000 {
+        assert_eq!(amap.get(&key), Some(key + 1));
+        assert_eq!(fmap.get(&key), Some(key + 1));
+    }
+}
+
+#[test]
+fn test_identity_hashers() {
+    use crate::{HashWidth, Map};
+
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_identity_hashers seed:{}", seed);
+
+    let mut map: Map<u64, u64, U64Hasher> =
+        Map::with_hash_width(1, U64Hasher::default(), HashWidth::Bits64);
+    let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let (key, value) = (rng.gen::<u64>(), rng.gen::<u64>());
+        assert_eq!(map.hash_key(&key), key);
+        let hash = map.to_trie_hash(&key);
+        assert_eq!(hash >> 32, (key >> 32) as u128);
+        assert_eq!(hash as u32, (key ^ (key >> 32)) as u32);
+        assert_eq!(map.set(key, value), btmap.insert(key, value));
+    }
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value));
+    }
+    assert_eq!(map.validate().n_lists, 0);
+
+    let mut map: Map<u128, u64, U128Hasher> =
+        Map::with_hash_width(1, U128Hasher::default(), HashWidth::Bits128);
+    let mut btmap: BTreeMap<u128, u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let (key, value) = (rng.gen::<u128>(), rng.gen::<u64>());
+        assert_eq!(map.hash_key(&key), key as u64);
+        let hash = map.to_trie_hash(&key);
+        assert_eq!(hash >> 32, key >> 32);
+        assert_eq!(hash as u32, (key ^ (key >> 32)) as u32);
+        assert_eq!(map.set(key, value), btmap.insert(key, value));
+    }
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value));
+    }
+    assert_eq!(map.validate().n_lists, 0);
+}
+
+#[test]
+fn test_hash_widths() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_hash_widths seed:{}", seed);
+
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen::<u64>() % 100_000).collect();
+    hash_widths_with("default", DefaultHasher::new(), keys.clone());
+    hash_widths_with("city", CityHasher::new(), keys.clone());
+    hash_widths_with("key", KeyHasher::default(), keys.clone());
+    hash_widths_with("u64", U64Hasher::default(), keys.clone());
+    #[cfg(feature = "ahash")]
+    hash_widths_with("ahash", AHasher::new(), keys.clone());
+    #[cfg(feature = "fxhash")]
+    hash_widths_with("fxhash", FxHasher::default(), keys.clone());
+
+    let keys = keys.iter().map(|k| *k as u32).collect();
+    hash_widths_with("u32", U32Hasher::default(), keys);
+    let keys = (0..10_000).map(|_| rng.gen::<u128>()).collect();
+    hash_widths_with("u128", U128Hasher::default(), keys);
+}
+
+fn hash_widths_with<K, H>(name: &str, hash_builder: H, keys: Vec<K>)
+where
+    K: Clone + Ord + Hash + Debug,
+    H: BuildHasher + Clone,
+{
+    use crate::{HashWidth, Map};
+
+    for width in [
+        HashWidth::Bits32,
+        HashWidth::Bits64,
+        HashWidth::Bits128,
+        HashWidth::Rehash,
+    ] {
+        let mut map: Map<K, usize, H> =
+            Map::with_hash_width(1, hash_builder.clone(), width);
+        let mut btmap: BTreeMap<K, usize> = BTreeMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(map.set(key.clone(), i), btmap.insert(key.clone(), i));
+            if i % 3 == 0 {
+                let key = &keys[i / 2];
+                assert_eq!(map.remove(key), btmap.remove(key), "{} {:?}", name, width);
+            }
+        }
+        for (key, value) in btmap.iter() {
+            assert_eq!(map.get(key), Some(*value), "{} {:?}", name, width);
+        }
+        assert_eq!(map.len(), btmap.len(), "{} {:?}", name, width);
+        map.validate();
+    }
+}
+
+#[test]
+fn test_key_hasher() {
+    use crate::{HashWidth, Map};
+
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_key_hasher seed:{}", seed);
+
+    #[derive(Hash)]
+    struct Id(u64);
+
+    let hb = KeyHasher::default();
+    for _i in 0..1000 {
+        let (a, b, c) = (rng.gen::<u32>(), rng.gen::<u64>(), rng.gen::<u128>());
+        assert_eq!(hb.hash_one(a), a.key_hash());
+        assert_eq!(hb.hash_one(b), b.key_hash());
+        assert_eq!(hb.hash_one(c), c.key_hash());
+        assert_eq!(hb.hash_one(Id(b)), b.key_hash());
+        assert_eq!(hb.hash_one(a as i32), (a as i32).key_hash());
+    }
+
+    for width in [HashWidth::Bits32, HashWidth::Bits128, HashWidth::Rehash].iter() {
+        let mut map: Map<u64, u64, KeyHasher> =
+            Map::with_hash_width(1, KeyHasher::default(), *width);
+        let mut smap: Map<String, u64, KeyHasher> =
+            Map::with_hash_width(1, KeyHasher::default(), *width);
+        let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+        for _i in 0..10_000 {
+            let (key, value) = (rng.gen::<u64>() % 100_000, rng.gen::<u64>());
+            assert_eq!(map.set(key, value), btmap.insert(key, value));
+            smap.set(key.to_string(), value);
+        }
+        for (key, value) in btmap.iter() {
+            assert_eq!(map.get(key), Some(*value));
+            assert_eq!(smap.get(key.to_string().as_str()), Some(*value));
+        }
+        map.validate();
+        smap.validate();
+    }
+}
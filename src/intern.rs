@@ -0,0 +1,234 @@
+//! Module implement key interning, to store identical keys only once.
+//!
+//! Large keys, like URL paths, that are repeated across maps or across
+//! copy-on-write versions of the trie, can be interned into a shared
+//! [Interner]. Interning a key return an [Interned] handle, that is
+//! cheap to clone, and hashes and compares same as the key it refers to.
+//! Use the handle as the key type, say `Map<Interned<str>, V>`, and
+//! lookup the map using `&str`.
+//!
+//! Handles are reference counted, every trie node holding a handle,
+//! including nodes that are waiting for garbage collection, keeps its key
+//! alive. Once all the handles for a key are dropped, call
+//! [Interner::purge] to release the key from the interner.
+//!
+//! ```
+//! use cmap::{DefaultHasher, Interned, Interner, Map};
+//!
+//! let mut interner: Interner<str> = Interner::new(1, DefaultHasher::new());
+//! let mut map: Map<Interned<str>, u64> = Map::new(1, DefaultHasher::new());
+//!
+//! map.set(interner.intern("/index.html"), 10);
+//! map.set(interner.intern("/index.html"), 20);
+//! assert_eq!(map.get("/index.html"), Some(20));
+//! assert_eq!(interner.len(), 1);
+//! ```
+
+use std::{
+    borrow::Borrow,
+    cmp,
+    fmt::{self, Debug, Display},
+    hash::{BuildHasher, Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+use crate::{DefaultHasher, Map};
+
+/// Handle to an interned key, refer to [Interner::intern].
+pub struct Interned<T: ?Sized>(Arc<T>);
+
+impl<T: ?Sized> Clone for Interned<T> {
+    fn clone(&self) -> Self {
+        Interned(Arc::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> Deref for Interned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for Interned<T> {
+    fn borrow(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for Interned<T> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.0.hash(state)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for Interned<T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for Interned<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for Interned<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: ?Sized + Display> Display for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: ?Sized> Interned<T> {
+    /// Return the number of handles, and trie nodes, referring to this key.
+    pub fn ref_count(this: &Self) -> usize {
+        Arc::strong_count(&this.0)
+    }
+
+    /// Return whether both handles refer to the same interned key.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+// Each interned key is held twice by the interner's trie, as key and as
+// value, refer to Interner::purge.
+const POOL_REFS: usize = 2;
+
+/// Interner is a concurrent pool of interned keys, backed by a [Map].
+///
+/// Same as [Map], the interner shall be cloned for each thread, and its
+/// concurrency level supplied upfront. Clones share the same pool.
+pub struct Interner<T: ?Sized, H = DefaultHasher> {
+    pool: Map<Interned<T>, Interned<T>, H>,
+}
+
+impl<T: ?Sized, H> Clone for Interner<T, H>
+where
+    H: Clone,
+{
+    fn clone(&self) -> Self {
+        Interner {
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized, H> Interner<T, H> {
+    /// Create a new interner, refer to [Map::new] for `concurrency` and
+    /// `hash_builder`.
+    pub fn new(concurrency: usize, hash_builder: H) -> Interner<T, H>
+    where
+        H: Clone,
+    {
+        Interner {
+            pool: Map::new(concurrency, hash_builder),
+        }
+    }
+
+    /// Return the number of keys interned.
+    pub fn len(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Return whether the interner is empty.
+    pub fn is_empty(&self) -> bool {
+        self.pool.is_empty()
+    }
+}
+
+impl<T: ?Sized, H> Interner<T, H>
+where
+    T: Hash + Eq,
+    H: BuildHasher,
+{
+    /// Intern `key` and return its handle. If an identical key is already
+    /// interned, return a handle to the same key, and `key` is dropped.
+    /// Concurrent calls interning the same key shall return handles to the
+    /// same key.
+    pub fn intern<Q>(&mut self, key: Q) -> Interned<T>
+    where
+        Q: Borrow<T> + Into<Arc<T>>,
+    {
+        let code = self.pool.hash_key(key.borrow());
+        if let Some(handle) = self.pool.get_hashed(code, key.borrow()) {
+            return handle;
+        }
+
+        let hash = self.pool.to_trie_hash_from(code, key.borrow());
+        let handle = Interned(key.into());
+        let mut interned = None;
+        let (seqno, _) = self.pool.do_update(handle.clone(), hash, |old| match old {
+            Some(old) => {
+                interned = Some(old.clone());
+                None
+            }
+            None => Some(handle.clone()),
+        });
+        self.pool.do_gc(seqno);
+
+        interned.unwrap_or(handle)
+    }
+
+    /// Return the handle for `key`, if it is already interned.
+    pub fn get(&self, key: &T) -> Option<Interned<T>> {
+        self.pool.get(key)
+    }
+
+    /// Release the keys that are no longer referred by any handle outside
+    /// this interner, and return the number of keys released. Keys still
+    /// referred by trie nodes that are waiting for garbage collection,
+    /// either in other clones of this interner or in maps using the handle,
+    /// are released by a later call.
+    ///
+    /// A key concurrently interned while being released is not lost, but
+    /// subsequent calls to intern the same key shall return a new handle.
+    pub fn purge(&mut self) -> usize {
+        self.pool.force_gc();
+
+        let mut handles = vec![];
+        self.pool.for_each(|handle, _| {
+            if Interned::ref_count(handle) == POOL_REFS {
+                handles.push(handle.clone())
+            }
+        });
+
+        let mut n = 0;
+        for handle in handles.into_iter() {
+            // account for `handle` held by this loop.
+            let refs = POOL_REFS + 1;
+            let hash = self.pool.to_trie_hash(&handle);
+            let (seqno, compact, res) = self
+                .pool
+                .do_remove(&handle, hash, |value| Interned::ref_count(value) <= refs);
+            if compact {
+                self.pool.do_compact(&handle, hash)
+            }
+            self.pool.do_gc(seqno);
+            n += res.map(|_| 1).unwrap_or(0);
+        }
+
+        n
+    }
+}
+
+#[cfg(test)]
+#[path = "intern_test.rs"]
+mod intern_test;
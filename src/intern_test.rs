@@ -0,0 +1,70 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, mem, thread};
+
+use super::*;
+
+#[test]
+fn test_interner() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4, 8][rng.gen::<usize>() % 4];
+    let n_keys = [1, 10, 1000, 10_000][rng.gen::<usize>() % 4];
+    println!(
+        "test_interner seed:{} n_threads:{} n_keys:{}",
+        seed, n_threads, n_keys
+    );
+
+    let mut interner: Interner<str> = Interner::new(n_threads + 1, DefaultHasher::new());
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let seed = seed + ((id as u128) * 100);
+        let mut interner = interner.clone();
+        let handle = thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            let mut map: Map<Interned<str>, u64> = Map::new(1, DefaultHasher::new());
+            let mut btmap: BTreeMap<String, u64> = BTreeMap::new();
+            for _i in 0..(n_keys * 4) {
+                let key = format!("/path/to/{}", rng.gen::<usize>() % n_keys);
+                let value = rng.gen::<u64>();
+                map.set(interner.intern(key.as_str()), value);
+                btmap.insert(key, value);
+            }
+            for (key, value) in btmap.iter() {
+                assert_eq!(map.get(key.as_str()), Some(*value), "key:{}", key);
+            }
+            map.validate();
+            map.keys().collect::<Vec<Interned<str>>>()
+        });
+        handles.push(handle);
+    }
+
+    let mut keys: BTreeMap<String, Interned<str>> = BTreeMap::new();
+    for handle in handles.into_iter() {
+        for key in handle.join().unwrap().into_iter() {
+            let val = keys.entry(key.to_string()).or_insert_with(|| key.clone());
+            assert!(Interned::ptr_eq(val, &key), "key:{}", key);
+        }
+    }
+    assert_eq!(interner.len(), keys.len());
+
+    for (key, handle) in keys.iter() {
+        let val = interner.intern(key.clone());
+        assert!(Interned::ptr_eq(&val, handle), "key:{}", key);
+        assert!(Interned::ptr_eq(&interner.get(key).unwrap(), handle));
+    }
+
+    // keys still referred are not released.
+    let n = keys.len() / 2;
+    let held: Vec<(String, Interned<str>)> = keys.into_iter().take(n).collect();
+    while interner.purge() > 0 {}
+    assert_eq!(interner.len(), held.len());
+    for (key, handle) in held.iter() {
+        assert!(Interned::ptr_eq(&interner.intern(key.as_str()), handle));
+    }
+
+    mem::drop(held);
+    while interner.purge() > 0 {}
+    assert!(interner.is_empty());
+}
@@ -0,0 +1,1441 @@
+//! Module implement iterators over [Map] entries.
+//!
+//! Iterators walk the hash-trie depth first, in the order of hash-slots,
+//! while keeping the map instance pinned to an epoch. Long running
+//! iterators periodically exit and re-enter the epoch, and resume the walk
+//! from their position on the latest version of the trie, refer to
+//! [Map::set_repin_period]. Iterators are weakly consistent:
+//!
+//! * Every key is observed at most once.
+//! * Keys that are not updated during the iteration are always observed.
+//! * Concurrent updates, from other clones of the map, may or may not be
+//!   observed.
+//!
+//! Iterators, other than the parallel ones, visit entries in the ascending
+//! order of [to_walk_order] of their hash, and entries colliding on the
+//! same hash are visited together. This order is canonical, maps using the
+//! same hasher visit common keys in the same relative order, irrespective
+//! of their history, refer to [Map::merge_iter].
+//!
+//! Holding an iterator, that is not periodically re-pinned, stalls garbage
+//! collection for all instances of the map, hence it is better not to keep
+//! them alive for long.
+//!
+//! To share a single pin across several reads, use a [Guard], refer to its
+//! documentation for the anomalies that can be observed under concurrent
+//! writes.
+
+use std::{
+    convert::TryFrom,
+    hash::{BuildHasher, Hash},
+    ops::{self, ControlFlow},
+    slice,
+    sync::{
+        atomic::{AtomicPtr, Ordering::SeqCst},
+        Arc,
+    },
+    vec,
+};
+
+use crate::{
+    map::{hamming_distance, slots, Child, Item, Node},
+    DefaultHasher, Equivalent, Error, Map, Result,
+};
+
+impl<K, V, H> Map<K, V, H> {
+    /// Pin this instance to the current epoch and return a [Guard]. Reads
+    /// done via the guard share the pin and return references into the map
+    /// that are valid for the life-time of the guard. For a batch of reads,
+    /// say thousands of get() in a tight loop, this enters and exits the
+    /// epoch only once.
+    pub fn guard(&self) -> Guard<'_, K, V, H> {
+        let seqno = self.pin_epoch();
+        Guard { map: self, seqno }
+    }
+}
+
+/// Guard is returned by [Map::guard], it keeps the map instance pinned to
+/// an epoch until dropped. Memory reachable from the map, as of when the
+/// guard was created or any time after, shall not be reclaimed while the
+/// guard is alive.
+///
+/// Guards can nest with other reads on the same map instance. Holding a
+/// guard stalls garbage collection for all instances of the map, and note
+/// that dropping a map instance waits for its garbage to be reclaimed,
+/// hence do not block on dropping other instances while holding a guard.
+///
+/// Anomalies
+/// ---------
+///
+/// Each read done via the guard is atomic with respect to the key it
+/// reads, but reads are not isolated from concurrent writes done by other
+/// instances of the map. Following anomalies can be observed:
+///
+/// * Non-repeatable reads, two get() calls for the same key can return
+///   different values.
+/// * Read skew, get() on two different keys observe them at different
+///   instants, hence the pair of values may never have co-existed in the
+///   map.
+/// * Iteration, using [Guard::iter], observe each key at most once and
+///   always observe keys that are not updated during the iteration. Keys
+///   inserted, updated or removed during the iteration may or may not be
+///   observed, and a value observed is the value at the instant its node
+///   was visited.
+/// * Phantoms, Map::len() or any count computed by iterating may never have
+///   been the actual size of the map at any given instant.
+///
+/// For a point in time view, use [Map::snapshot_iter].
+pub struct Guard<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    seqno: u64,
+}
+
+impl<'a, K, V, H> Drop for Guard<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> Guard<'a, K, V, H> {
+    /// Get a reference to the value for `key`, valid for the life-time of
+    /// the guard.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.map.lookup(key, self.map.to_trie_hash(key))
+    }
+
+    /// Same as [Guard::get], with `hash` already computed for `key`, refer
+    /// to [Map::get_hashed].
+    pub fn get_hashed<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.map.lookup(key, self.map.to_trie_hash_from(hash, key))
+    }
+
+    /// Same as [Guard::get], return the key as stored in the map along with
+    /// its value, refer to [Map::get_key_value].
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let item = self.map.lookup_item(key, self.map.to_trie_hash(key))?;
+        Some((&item.key, &item.value))
+    }
+
+    /// Return an iterator over all {key, value} entries in the map, yielding
+    /// references valid for the life-time of the guard.
+    pub fn iter(&self) -> GuardIter<'_, K, V> {
+        GuardIter {
+            walker: Walker::new(self.map.as_root_node()),
+        }
+    }
+
+    /// Return an iterator over entries whose key's hash share the same
+    /// prefix, refer to [Map::scan_prefix].
+    pub fn scan_prefix(&self, prefix: u32, n_bits: usize) -> Result<GuardIter<'_, K, V>>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        if n_bits > 32 {
+            err_at!(InvalidInput, msg: "n_bits:{} is more than 32", n_bits)
+        } else {
+            let mask = ((1_u64 << n_bits) - 1) as u32;
+            let walker = self.map.prefix_walker(prefix & mask, n_bits);
+            Ok(GuardIter { walker })
+        }
+    }
+}
+
+/// GuardIter is returned by [Guard::iter], yield reference to each entry.
+pub struct GuardIter<'g, K, V> {
+    walker: Walker<'g, K, V>,
+}
+
+impl<'g, K, V> Iterator for GuardIter<'g, K, V> {
+    type Item = (&'g K, &'g V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.walker.next()?;
+        Some((&item.key, &item.value))
+    }
+}
+
+/// Number of entries visited by [Map::for_each] before re-entering the epoch.
+pub const FOR_EACH_PERIOD: usize = 1024;
+
+/// Default number of entries yielded by an iterator before re-entering the
+/// epoch, refer to [Map::set_repin_period].
+pub const REPIN_PERIOD: usize = 1024;
+
+impl<K, V, H> Map<K, V, H> {
+    /// Iterators returned by iter(), iter_with_hash(), keys(), values() and
+    /// scan_filter() exit and re-enter the epoch after every `period`
+    /// entries, so that a long running iteration would not stall garbage
+    /// collection. Default is [REPIN_PERIOD], and 0 disables re-pinning.
+    /// Setting applies only to this instance, for iterators created after
+    /// the call.
+    pub fn set_repin_period(&mut self, period: usize) -> &mut Self {
+        self.repin_period = period;
+        self
+    }
+
+    /// Return an iterator over all {key, value} entries in the map, yielding
+    /// a clone of each entry in walk order. Iterator is weakly consistent
+    /// with concurrent writes, and re-pins itself every
+    /// [set_repin_period][Map::set_repin_period] entries.
+    pub fn iter(&self) -> Iter<'_, K, V, H> {
+        Iter {
+            cursor: Cursor::new(self),
+        }
+    }
+
+    /// Return an iterator over all entries in the map, along with the 32-bit
+    /// hash of their key, as used by the trie. Hash is the one the entry is
+    /// indexed with, so that callers need not re-hash the key, say, to
+    /// partition entries. Otherwise same as [Map::iter].
+    pub fn iter_with_hash(&self) -> IterWithHash<'_, K, V, H> {
+        IterWithHash {
+            cursor: Cursor::new(self),
+        }
+    }
+
+    /// Consume the map and return an iterator over all its keys. When all
+    /// the other clones of the map are dropped, entries are moved out of
+    /// the map, otherwise keys are cloned from the shared map.
+    pub fn into_keys(self) -> IntoKeys<K, V>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        IntoKeys {
+            items: self.into_items().into_iter(),
+        }
+    }
+
+    /// Consume the map and return an iterator over all its values, refer to
+    /// [Map::into_keys] for details.
+    pub fn into_values(self) -> IntoValues<K, V>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        IntoValues {
+            items: self.into_items().into_iter(),
+        }
+    }
+
+    /// Return an iterator over a point in time snapshot of the map. Unlike
+    /// iter(), entries reflect the state of the map at a single instant in
+    /// between the call and its return.
+    ///
+    /// Snapshot is taken by walking the trie twice, while pinned, until two
+    /// consecutive walks visit the very same nodes. Under heavy concurrent
+    /// writes, this can take several walks. Memory cost is a reference per
+    /// entry, held until the iterator is dropped.
+    pub fn snapshot_iter(&self) -> SnapshotIter<'_, K, V, H> {
+        let seqno = self.pin_epoch();
+
+        // pinned nodes are not reclaimed, hence not re-used, till unpinned.
+        // If two walks see the same node at every hop, none of the nodes
+        // have changed in between the walks.
+        let (_, mut trail) = self.collect_with_trail();
+        let items = loop {
+            let (items, other_trail) = self.collect_with_trail();
+            if trail == other_trail {
+                break items;
+            }
+            trail = other_trail;
+        };
+
+        SnapshotIter {
+            map: self,
+            items: items.into_iter(),
+            seqno,
+        }
+    }
+
+    /// Scan the map in chunks, resuming from `token`. Start a fresh scan
+    /// with token `0`. Return about `limit` entries and the token for the
+    /// next chunk, or None if the scan is complete. Entries colliding on
+    /// the same hash are always returned in the same chunk, hence a chunk
+    /// can exceed the `limit`. A `limit` of 0 is treated as 1, so that every
+    /// call makes progress.
+    ///
+    /// Instance is pinned to an epoch only for the duration of a call.
+    /// Across calls, keys that are not updated during the scan are
+    /// observed exactly once, concurrent updates may or may not be seen.
+    pub fn scan_from(&self, token: u64, limit: usize) -> (Vec<(K, V)>, Option<u64>)
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        // token is the position in walk order, that is nibble reversed hash,
+        // token beyond u32::MAX marks the end of scan.
+        let from = match u32::try_from(token) {
+            Ok(from) => from,
+            Err(_) => return (vec![], None),
+        };
+        let limit = usize::max(limit, 1);
+
+        let seqno = self.pin_epoch();
+
+        let mut items = vec![];
+        let mut next = None;
+        let mut last = None;
+        for item in Walker::new_at(self.as_root_node(), to_walk_order(from)) {
+            let order = to_walk_order(self.to_hash(&item.key));
+            if order < from {
+                continue;
+            } else if items.len() >= limit && last != Some(order) {
+                next = Some(order as u64);
+                break;
+            }
+            items.push((item.key.clone(), item.value.clone()));
+            last = Some(order);
+        }
+
+        self.unpin_epoch(seqno);
+        (items, next)
+    }
+
+    /// Return an iterator yielding batches of about `chunk_size` entries,
+    /// built on top of [Map::scan_from]. Instance is pinned to an epoch only
+    /// while a batch is fetched, hence a slow consumer does not stall
+    /// garbage collection. Consistency guarantees are the same as that of
+    /// [Map::scan_from].
+    pub fn iter_chunks(&self, chunk_size: usize) -> Chunks<'_, K, V, H> {
+        Chunks {
+            map: self,
+            chunk_size: usize::max(chunk_size, 1),
+            token: Some(0),
+        }
+    }
+
+    /// Divide the map into `n` disjoint iterators, each covering a
+    /// contiguous range of hash prefixes, that can be handed over to
+    /// different threads. For well distributed hashes, partitions hold
+    /// roughly the same number of entries.
+    ///
+    /// This instance stays pinned to an epoch until all the partitions are
+    /// dropped, and the consistency guarantees are the same as that of
+    /// [Map::iter]. Partitions can be dropped from any thread, hence this
+    /// instance is borrowed mutably for their life-time.
+    pub fn partitions(&mut self, n: usize) -> Vec<PartitionIter<'_, K, V, H>> {
+        let this: &Self = self;
+        let seqno = this.pin_epoch();
+        let pin = Arc::new(Pin { map: this, seqno });
+
+        let span = 1_u64 << 32;
+        let n = n as u64;
+        (0..n)
+            .map(|i| {
+                let (from, till) = ((span * i) / n, (span * (i + 1)) / n);
+                let root = this.as_root_node();
+                PartitionIter {
+                    map: this,
+                    walker: Walker::new_at(root, to_walk_order(from as u32)),
+                    from,
+                    till,
+                    _pin: Arc::clone(&pin),
+                }
+            })
+            .collect()
+    }
+
+    /// Call `callb` for each {key, value} entry in the map, without cloning
+    /// keys and values and without allocating iterator state. This instance
+    /// exits and re-enters the epoch after every [FOR_EACH_PERIOD] entries,
+    /// so that a long running walk would not stall garbage collection.
+    /// Consistency guarantees are the same as that of [Map::iter].
+    pub fn for_each<F>(&self, mut callb: F)
+    where
+        K: Hash,
+        H: BuildHasher,
+        F: FnMut(&K, &V),
+    {
+        let mut from = None;
+        loop {
+            let seqno = self.pin_epoch();
+            let mut budget = FOR_EACH_PERIOD;
+            let res = self.visit(self.as_root_node(), 0, from, &mut budget, &mut callb);
+            self.unpin_epoch(seqno);
+
+            match res {
+                ControlFlow::Continue(()) => break,
+                ControlFlow::Break(order) => from = Some(order),
+            }
+        }
+    }
+
+    /// Call `callb` for each {key, value} entry in the map, and replace the
+    /// value with what `callb` leaves behind in `&mut V`. Values are
+    /// rewritten in place, in a single traversal, node by node: leaves of
+    /// a trie node are rewritten in runs, each run swung into the trie as
+    /// one copy of the node, using the same CAS machinery as [Map::set].
+    /// Hence concurrent updates to an entry are not lost, but on CAS failure
+    /// `callb` will be called again with the latest values of the run.
+    ///
+    /// This instance exits and re-enters the epoch after every
+    /// [FOR_EACH_PERIOD] entries, same as [Map::for_each]. Entries inserted
+    /// concurrently may or may not be visited, entries removed before their
+    /// turn are left untouched. Keys colliding on the lower 32 bits of the
+    /// hash, with [HashWidth::Bits64] and wider, are held in a subtrie
+    /// below the 8th level, and are rewritten per key, descending from the
+    /// root for each key, same as [Map::set].
+    ///
+    /// [HashWidth::Bits64]: crate::HashWidth::Bits64
+    pub fn for_each_mut<F>(&mut self, mut callb: F)
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+        F: FnMut(&K, &mut V),
+    {
+        self.do_rewrite(&mut callb)
+    }
+
+    /// Return an iterator that removes and yields entries for which `pred`
+    /// returns true. Entries are matched in batches of [FOR_EACH_PERIOD]
+    /// keys, same as [Map::for_each], and each matching entry is removed
+    /// using the same CAS loop as [Map::remove], only if `pred` still
+    /// returns true for its latest value. Hence an entry concurrently
+    /// updated to a non-matching value is not removed, and `pred` can be
+    /// called more than once for the same entry.
+    ///
+    /// Entries are removed as the iterator is advanced, dropping the
+    /// iterator leaves the remaining entries untouched.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, H, F>
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        ExtractIf {
+            map: self,
+            pred,
+            keys: vec![].into_iter(),
+            from: Some(None),
+        }
+    }
+
+    /// Fold every {key, value} entry in the map into an accumulator,
+    /// starting with `init`. Same as [Map::for_each], keys and values are
+    /// not cloned.
+    pub fn fold<B, F>(&self, init: B, mut callb: F) -> B
+    where
+        K: Hash,
+        H: BuildHasher,
+        F: FnMut(B, &K, &V) -> B,
+    {
+        let mut acc = Some(init);
+        self.for_each(|key, value| acc = Some(callb(acc.take().unwrap(), key, value)));
+        acc.unwrap()
+    }
+
+    // Visit items under `node` in walk order. While `from` is Some, the
+    // walk is on the path leading to `from`, items below it are skipped.
+    // Break with the position of the next item once the budget is spent.
+    fn visit<F>(
+        &self,
+        node: &Node<K, V>,
+        depth: usize,
+        from: Option<u32>,
+        budget: &mut usize,
+        callb: &mut F,
+    ) -> ControlFlow<u32>
+    where
+        K: Hash,
+        H: BuildHasher,
+        F: FnMut(&K, &V),
+    {
+        match node {
+            Node::Trie { .. } if depth == 8 => {
+                // below the 32-bit walk order, subtrie is a collision group.
+                let items: Vec<&Item<K, V>> = Walker::new(node).collect();
+                self.visit_items(items.into_iter(), from, budget, callb)
+            }
+            Node::Trie { bmp, childs } => {
+                let (off, path) = match from {
+                    Some(order) => {
+                        match hamming_distance(slots(to_walk_order(order))[depth], *bmp) {
+                            (true, n) => (n, Some(n)),
+                            (false, n) => (n, None),
+                        }
+                    }
+                    None => (0, None),
+                };
+                for (n, ptr) in childs.iter().enumerate().skip(off) {
+                    let from = if path == Some(n) { from } else { None };
+                    match unsafe { ptr.load(SeqCst).as_ref().unwrap() } {
+                        Child::Leaf(item) => {
+                            let items = slice::from_ref(item).iter();
+                            self.visit_items(items, from, budget, callb)?
+                        }
+                        Child::Deep(inode) => {
+                            let node =
+                                unsafe { inode.node.load(SeqCst).as_ref().unwrap() };
+                            self.visit(node, depth + 1, from, budget, callb)?
+                        }
+                        Child::None => unreachable!(),
+                    }
+                }
+                ControlFlow::Continue(())
+            }
+            Node::Tomb { item: Some(item) } => {
+                let items = slice::from_ref(item).iter();
+                self.visit_items(items, from, budget, callb)
+            }
+            Node::Tomb { item: None } => ControlFlow::Continue(()),
+            Node::List { items } => self.visit_items(items.iter(), from, budget, callb),
+        }
+    }
+
+    // items share the same hash, collision lists are visited as a whole.
+    fn visit_items<'b, I, F>(
+        &self,
+        items: I,
+        from: Option<u32>,
+        budget: &mut usize,
+        callb: &mut F,
+    ) -> ControlFlow<u32>
+    where
+        K: 'b + Hash,
+        V: 'b,
+        H: BuildHasher,
+        I: ExactSizeIterator<Item = &'b Item<K, V>> + Clone,
+        F: FnMut(&K, &V),
+    {
+        let first = match items.clone().next() {
+            Some(item) => item,
+            None => return ControlFlow::Continue(()),
+        };
+        let to_order = || to_walk_order(self.to_hash(&first.key));
+        match from {
+            Some(from) if to_order() < from => return ControlFlow::Continue(()),
+            _ if *budget == 0 => return ControlFlow::Break(to_order()),
+            _ => (),
+        }
+
+        *budget = budget.saturating_sub(items.len());
+        for item in items {
+            callb(&item.key, &item.value)
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Return an iterator over entries whose key's hash share the same
+    /// prefix, refer to [Map::subtree] for details on `prefix` and `n_bits`.
+    /// Only the subtree under the prefix is walked. Consistency guarantees
+    /// are the same as that of [Map::iter].
+    pub fn scan_prefix(
+        &self,
+        prefix: u32,
+        n_bits: usize,
+    ) -> Result<PrefixIter<'_, K, V, H>>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        if n_bits > 32 {
+            err_at!(InvalidInput, msg: "n_bits:{} is more than 32", n_bits)
+        } else {
+            let mask = ((1_u64 << n_bits) - 1) as u32;
+            Ok(self.prefix_iter(prefix & mask, n_bits))
+        }
+    }
+
+    // `prefix` shall be masked to its lower `n_bits`.
+    pub(crate) fn prefix_iter(
+        &self,
+        prefix: u32,
+        n_bits: usize,
+    ) -> PrefixIter<'_, K, V, H>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let seqno = self.pin_epoch();
+        let walker = self.prefix_walker(prefix, n_bits);
+        PrefixIter {
+            map: self,
+            walker,
+            seqno,
+        }
+    }
+
+    // Walker over the subtree under `prefix`, the returned walker borrows
+    // trie nodes, hence instance shall stay pinned while it is in use.
+    pub(crate) fn prefix_walker(&self, prefix: u32, n_bits: usize) -> Walker<'_, K, V>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let ws = slots(prefix);
+        let (levels, rem) = (n_bits / 4, n_bits % 4);
+        // leaf items can sit above the prefix depth, check their full hash.
+        let mask = ((1_u64 << n_bits) - 1) as u32;
+        let covers = |item: &Item<K, V>| (self.to_hash(&item.key) & mask) == prefix;
+
+        let mut node = self.as_root_node();
+        for w in ws[..levels].iter() {
+            node = match node {
+                Node::Trie { bmp, childs } => match hamming_distance(*w, *bmp) {
+                    (false, _) => return Walker::empty(),
+                    (true, n) => {
+                        match unsafe { childs[n].load(SeqCst).as_ref().unwrap() } {
+                            Child::Leaf(item) if covers(item) => {
+                                return Walker::from_item(item)
+                            }
+                            Child::Leaf(_) => return Walker::empty(),
+                            Child::Deep(inode) => unsafe {
+                                inode.node.load(SeqCst).as_ref().unwrap()
+                            },
+                            Child::None => unreachable!(),
+                        }
+                    }
+                },
+                Node::Tomb { item: Some(item) } if covers(item) => {
+                    return Walker::from_item(item)
+                }
+                Node::Tomb { .. } => return Walker::empty(),
+                Node::List { .. } => unreachable!(),
+            };
+        }
+
+        match node {
+            Node::Trie { bmp, childs } if rem > 0 => {
+                // partial slot, walk all the childs sharing the lower bits.
+                let (mask, w) = ((1_u8 << rem) - 1, ws[levels]);
+                let childs = (0..16)
+                    .filter(|x| (bmp & (1 << x)) != 0)
+                    .enumerate()
+                    .filter(|(_, x)| (x & mask) == (w & mask))
+                    .map(|(i, _)| unsafe { childs[i].load(SeqCst).as_ref().unwrap() })
+                    .collect();
+                Walker::from_childs(childs)
+            }
+            Node::Tomb { item: Some(item) } if rem > 0 && !covers(item) => {
+                Walker::empty()
+            }
+            node => Walker::new(node),
+        }
+    }
+
+    fn collect_with_trail(&self) -> (Vec<&Item<K, V>>, Vec<usize>) {
+        let mut walker = Walker::new_with_trail(self.as_root_node());
+        let items: Vec<&Item<K, V>> = walker.by_ref().collect();
+        (items, walker.trail.unwrap_or_default())
+    }
+
+    /// Return an iterator over all keys in the map, values are not cloned.
+    /// Walk and re-pinning are same as that of [Map::iter].
+    pub fn keys(&self) -> Keys<'_, K, V, H> {
+        Keys {
+            cursor: Cursor::new(self),
+        }
+    }
+
+    /// Return an iterator over all values in the map, keys are not cloned.
+    /// Values are yielded in the walk order of their keys, same as that of
+    /// [Map::keys].
+    pub fn values(&self) -> Values<'_, K, V, H> {
+        Values {
+            cursor: Cursor::new(self),
+        }
+    }
+
+    /// Co-iterate this map and `other` in walk order, yielding a [Merge]
+    /// for every key found in either of the maps. Neither side is buffered,
+    /// except for entries colliding on the same hash. Both maps must be
+    /// built using equivalent hashers, otherwise the orders won't match.
+    /// Each map is pinned to its own epoch and the consistency guarantees,
+    /// for each side, are same as that of [Map::iter].
+    pub fn merge_iter<'a>(&'a self, other: &'a Map<K, V, H>) -> MergeIter<'a, K, V, H>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        MergeIter {
+            inner: Merger::new(Ordered(self.iter()), Ordered(other.iter())),
+        }
+    }
+
+    /// Return an iterator over entries for which `pred` returns true. The
+    /// predicate is called with references into the map, only matching
+    /// entries are cloned, making this cheaper than filtering [Map::iter]
+    /// for selective scans over large values.
+    pub fn scan_filter<F>(&self, pred: F) -> ScanFilter<'_, K, V, H, F>
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        ScanFilter {
+            cursor: Cursor::new(self),
+            pred,
+        }
+    }
+}
+
+/// SnapshotIter is returned by [Map::snapshot_iter], yield a clone of each
+/// entry in the snapshot.
+pub struct SnapshotIter<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    items: vec::IntoIter<&'a Item<K, V>>,
+    seqno: u64,
+}
+
+impl<'a, K, V, H> Drop for SnapshotIter<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> Iterator for SnapshotIter<'a, K, V, H>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.items.next()?;
+        Some((item.key.clone(), item.value.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<'a, K, V, H> ExactSizeIterator for SnapshotIter<'a, K, V, H>
+where
+    K: Clone,
+    V: Clone,
+{
+}
+
+/// ScanFilter is returned by [Map::scan_filter], yield a clone of each
+/// entry matching the predicate.
+pub struct ScanFilter<'a, K, V, H, F> {
+    cursor: Cursor<'a, K, V, H>,
+    pred: F,
+}
+
+impl<'a, K, V, H, F> Iterator for ScanFilter<'a, K, V, H, F>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+    F: FnMut(&K, &V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.cursor.next_item()?;
+            if (self.pred)(&item.key, &item.value) {
+                break Some((item.key.clone(), item.value.clone()));
+            }
+        }
+    }
+}
+
+/// Merge is yielded by [MergeIter], for each key in either of the maps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Merge<K, V> {
+    /// Key is present only in the left map, the one merge_iter() called on.
+    Left(K, V),
+    /// Key is present only in the right map.
+    Right(K, V),
+    /// Key is present in both the maps, left value followed by right value.
+    Both(K, V, V),
+}
+
+/// MergeIter is returned by [Map::merge_iter].
+pub struct MergeIter<'a, K, V, H = DefaultHasher> {
+    inner: MapMerger<'a, K, V, H>,
+}
+
+type MapMerger<'a, K, V, H> = Merger<K, V, Ordered<'a, K, V, H>, Ordered<'a, K, V, H>>;
+
+impl<'a, K, V, H> Iterator for MergeIter<'a, K, V, H>
+where
+    K: Clone + PartialEq + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = Merge<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+// Iterate over entries along with their position in walk order.
+pub(crate) struct Ordered<'a, K, V, H>(pub(crate) Iter<'a, K, V, H>);
+
+impl<'a, K, V, H> Iterator for Ordered<'a, K, V, H>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = (u32, K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.0.next()?;
+        Some((to_walk_order(self.0.cursor.map.to_hash(&key)), key, value))
+    }
+}
+
+// Merge two sources of entries, each ascending in walk order.
+pub(crate) struct Merger<K, V, L, R> {
+    left: L,
+    right: R,
+    lnext: Option<(u32, K, V)>,
+    rnext: Option<(u32, K, V)>,
+    pending: vec::IntoIter<Merge<K, V>>,
+}
+
+impl<K, V, L, R> Merger<K, V, L, R>
+where
+    L: Iterator<Item = (u32, K, V)>,
+    R: Iterator<Item = (u32, K, V)>,
+{
+    pub(crate) fn new(mut left: L, mut right: R) -> Self {
+        Merger {
+            lnext: left.next(),
+            rnext: right.next(),
+            left,
+            right,
+            pending: vec![].into_iter(),
+        }
+    }
+}
+
+impl<K, V, L, R> Iterator for Merger<K, V, L, R>
+where
+    K: PartialEq,
+    L: Iterator<Item = (u32, K, V)>,
+    R: Iterator<Item = (u32, K, V)>,
+{
+    type Item = Merge<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.pending.next() {
+            return Some(item);
+        }
+
+        let (lorder, rorder) = match (&self.lnext, &self.rnext) {
+            (None, None) => return None,
+            (Some(_), None) => (0, u64::MAX),
+            (None, Some(_)) => (u64::MAX, 0),
+            (Some((l, _, _)), Some((r, _, _))) => (*l as u64, *r as u64),
+        };
+
+        if lorder < rorder {
+            let (_, key, value) = self.lnext.take().unwrap();
+            self.lnext = self.left.next();
+            Some(Merge::Left(key, value))
+        } else if rorder < lorder {
+            let (_, key, value) = self.rnext.take().unwrap();
+            self.rnext = self.right.next();
+            Some(Merge::Right(key, value))
+        } else {
+            self.pending = self.merge_group(lorder as u32).into_iter();
+            self.pending.next()
+        }
+    }
+}
+
+impl<K, V, L, R> Merger<K, V, L, R>
+where
+    K: PartialEq,
+    L: Iterator<Item = (u32, K, V)>,
+    R: Iterator<Item = (u32, K, V)>,
+{
+    // Merge all entries at walk position `order`, from the left and right.
+    // Colliding keys can be in any order within their group.
+    fn merge_group(&mut self, order: u32) -> Vec<Merge<K, V>> {
+        let mut rgroup = vec![];
+        while matches!(&self.rnext, Some((o, _, _)) if *o == order) {
+            let (_, key, value) = self.rnext.take().unwrap();
+            rgroup.push((key, value));
+            self.rnext = self.right.next();
+        }
+
+        let mut items = vec![];
+        while matches!(&self.lnext, Some((o, _, _)) if *o == order) {
+            let (_, key, lvalue) = self.lnext.take().unwrap();
+            match rgroup.iter().position(|(k, _)| k == &key) {
+                Some(off) => {
+                    let (_, rvalue) = rgroup.remove(off);
+                    items.push(Merge::Both(key, lvalue, rvalue))
+                }
+                None => items.push(Merge::Left(key, lvalue)),
+            }
+            self.lnext = self.left.next();
+        }
+        items.extend(rgroup.into_iter().map(|(k, v)| Merge::Right(k, v)));
+        items
+    }
+}
+
+/// ExtractIf is returned by [Map::extract_if], yield each entry removed
+/// from the map.
+pub struct ExtractIf<'a, K, V, H, F> {
+    map: &'a mut Map<K, V, H>,
+    pred: F,
+    keys: vec::IntoIter<K>,
+    // Some(None) to start the walk, None once the walk is complete.
+    from: Option<Option<u32>>,
+}
+
+impl<'a, K, V, H, F> Iterator for ExtractIf<'a, K, V, H, F>
+where
+    K: Clone + PartialEq + Hash,
+    V: Clone,
+    H: BuildHasher,
+    F: FnMut(&K, &V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for key in self.keys.by_ref() {
+                let hash = self.map.to_trie_hash(&key);
+                let pred = &mut self.pred;
+                let (seqno, compact, res) =
+                    self.map.do_remove(&key, hash, |value| pred(&key, value));
+                if compact {
+                    self.map.do_compact(&key, hash)
+                }
+                self.map.do_gc(seqno);
+                if let Some(value) = res {
+                    return Some((key, value));
+                }
+            }
+
+            let from = self.from?;
+            let map: &Map<K, V, H> = self.map;
+            let mut keys = vec![];
+            let seqno = map.pin_epoch();
+            let mut budget = FOR_EACH_PERIOD;
+            let res = {
+                let pred = &mut self.pred;
+                let mut collect = |key: &K, value: &V| {
+                    if pred(key, value) {
+                        keys.push(key.clone())
+                    }
+                };
+                map.visit(map.as_root_node(), 0, from, &mut budget, &mut collect)
+            };
+            map.unpin_epoch(seqno);
+
+            self.keys = keys.into_iter();
+            self.from = match res {
+                ControlFlow::Continue(()) => None,
+                ControlFlow::Break(order) => Some(Some(order)),
+            };
+        }
+    }
+}
+
+/// Chunks is returned by [Map::iter_chunks], yield a batch of cloned
+/// entries at a time.
+pub struct Chunks<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    chunk_size: usize,
+    token: Option<u64>,
+}
+
+impl<'a, K, V, H> Iterator for Chunks<'a, K, V, H>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = Vec<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (chunk, token) = self.map.scan_from(self.token?, self.chunk_size);
+        self.token = token;
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+/// PartitionIter is returned by [Map::partitions], yield a clone of each
+/// entry in the partition.
+pub struct PartitionIter<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    walker: Walker<'a, K, V>,
+    from: u64,
+    till: u64,
+    _pin: Arc<Pin<'a, K, V, H>>,
+}
+
+impl<'a, K, V, H> PartitionIter<'a, K, V, H> {
+    /// Return the range of hash prefixes, in walk order, covered by this
+    /// partition.
+    pub fn to_range(&self) -> ops::Range<u64> {
+        self.from..self.till
+    }
+}
+
+impl<'a, K, V, H> Iterator for PartitionIter<'a, K, V, H>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.walker.next()?;
+            let order = to_walk_order(self.map.to_hash(&item.key)) as u64;
+            if order >= self.till {
+                // rest of the walk is beyond this partition.
+                self.walker = Walker::empty();
+                break None;
+            } else if order >= self.from {
+                break Some((item.key.clone(), item.value.clone()));
+            }
+        }
+    }
+}
+
+// Pin shared by partitions, last one to drop shall unpin the epoch.
+struct Pin<'a, K, V, H> {
+    map: &'a Map<K, V, H>,
+    seqno: u64,
+}
+
+impl<'a, K, V, H> Drop for Pin<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+/// PrefixIter is returned by [Map::scan_prefix] and
+/// [Subtree::iter][crate::Subtree::iter], yield a clone of each entry under
+/// the prefix.
+pub struct PrefixIter<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    walker: Walker<'a, K, V>,
+    seqno: u64,
+}
+
+impl<'a, K, V, H> Drop for PrefixIter<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> Iterator for PrefixIter<'a, K, V, H>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.walker.next()?;
+        Some((item.key.clone(), item.value.clone()))
+    }
+}
+
+/// Iter is returned by [Map::iter], yield a clone of each entry in the map.
+pub struct Iter<'a, K, V, H = DefaultHasher> {
+    cursor: Cursor<'a, K, V, H>,
+}
+
+impl<'a, K, V, H> Iterator for Iter<'a, K, V, H>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.cursor.next_item()?;
+        Some((item.key.clone(), item.value.clone()))
+    }
+}
+
+/// IterWithHash is returned by [Map::iter_with_hash], yield a clone of each
+/// entry in the map, along with its 32-bit hash.
+pub struct IterWithHash<'a, K, V, H = DefaultHasher> {
+    cursor: Cursor<'a, K, V, H>,
+}
+
+impl<'a, K, V, H> Iterator for IterWithHash<'a, K, V, H>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = (u32, K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.cursor.next_item()?;
+        let hash = self.cursor.map.to_hash(&item.key);
+        Some((hash, item.key.clone(), item.value.clone()))
+    }
+}
+
+/// IntoKeys is returned by [Map::into_keys], yield owned keys.
+pub struct IntoKeys<K, V> {
+    items: vec::IntoIter<Item<K, V>>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.items.next()?.key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+/// IntoValues is returned by [Map::into_values], yield owned values.
+pub struct IntoValues<K, V> {
+    items: vec::IntoIter<Item<K, V>>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.items.next()?.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+/// Keys is returned by [Map::keys], yield a clone of each key in the map.
+pub struct Keys<'a, K, V, H = DefaultHasher> {
+    cursor: Cursor<'a, K, V, H>,
+}
+
+impl<'a, K, V, H> Iterator for Keys<'a, K, V, H>
+where
+    K: Clone + Hash,
+    H: BuildHasher,
+{
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.cursor.next_item()?.key.clone())
+    }
+}
+
+/// Values is returned by [Map::values], yield a clone of each value in the
+/// map.
+pub struct Values<'a, K, V, H = DefaultHasher> {
+    cursor: Cursor<'a, K, V, H>,
+}
+
+impl<'a, K, V, H> Iterator for Values<'a, K, V, H>
+where
+    K: Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.cursor.next_item()?.value.clone())
+    }
+}
+
+// Cursor walk the hash-trie for an iterator, while pinned to an epoch.
+// After every `period` entries, on the boundary of a collision group, the
+// epoch is exited and re-entered and the walk resumes from its position in
+// walk order, on the latest version of the trie.
+struct Cursor<'a, K, V, H> {
+    map: &'a Map<K, V, H>,
+    walker: Walker<'a, K, V>,
+    seqno: u64,
+    period: usize,
+    count: usize,
+    last: Option<u32>,
+}
+
+impl<'a, K, V, H> Drop for Cursor<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> Cursor<'a, K, V, H> {
+    fn new(map: &'a Map<K, V, H>) -> Self {
+        let seqno = map.pin_epoch();
+        Cursor {
+            map,
+            walker: Walker::new(map.as_root_node()),
+            seqno,
+            period: map.repin_period,
+            count: 0,
+            last: None,
+        }
+    }
+
+    // Returned reference is valid only till the next call.
+    fn next_item(&mut self) -> Option<&'a Item<K, V>>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let item = self.walker.next()?;
+        if self.period == 0 || self.count < self.period {
+            self.count += 1;
+            return Some(item);
+        }
+
+        let order = to_walk_order(self.map.to_hash(&item.key));
+        match self.last {
+            None => {
+                self.last = Some(order);
+                return Some(item);
+            }
+            Some(last) if last == order => return Some(item),
+            Some(_) => (),
+        }
+
+        // item is the first of its group, drop all references into the trie
+        // before exiting the epoch.
+        self.walker = Walker::empty();
+        self.map.unpin_epoch(self.seqno);
+        self.seqno = self.map.pin_epoch();
+        (self.count, self.last) = (1, None);
+
+        let root = self.map.as_root_node();
+        self.walker = Walker::new_at(root, to_walk_order(order));
+        let map = self.map;
+        self.walker
+            .find(|item| to_walk_order(map.to_hash(&item.key)) >= order)
+    }
+}
+
+// Walker does a depth first walk on the hash-trie, yielding reference to
+// each leaf item. Caller should make sure that the map is pinned to an
+// epoch for the life-time of the walker.
+pub(crate) struct Walker<'a, K, V> {
+    stack: Vec<Frame<'a, K, V>>,
+    // when enabled, record address of every node visited by the walk, in
+    // walk order.
+    trail: Option<Vec<usize>>,
+}
+
+enum Frame<'a, K, V> {
+    Trie {
+        bmp: u16,
+        childs: &'a [AtomicPtr<Child<K, V>>],
+        off: usize,
+    },
+    Items {
+        items: &'a [Item<K, V>],
+        off: usize,
+    },
+}
+
+impl<'a, K, V> Frame<'a, K, V> {
+    fn new(node: &'a Node<K, V>) -> Self {
+        match node {
+            Node::Trie { bmp, childs } => Frame::Trie {
+                bmp: *bmp,
+                childs,
+                off: 0,
+            },
+            Node::Tomb { item: Some(item) } => Frame::Items {
+                items: slice::from_ref(item),
+                off: 0,
+            },
+            Node::Tomb { item: None } => Frame::Items { items: &[], off: 0 },
+            Node::List { items } => Frame::Items { items, off: 0 },
+        }
+    }
+}
+
+impl<'a, K, V> Walker<'a, K, V> {
+    pub(crate) fn new(root: &'a Node<K, V>) -> Self {
+        Walker {
+            stack: vec![Frame::new(root)],
+            trail: None,
+        }
+    }
+
+    // Walk the subtree under a child of a trie node.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn from_child(child: &'a Child<K, V>) -> Self {
+        Walker::from_childs(vec![child])
+    }
+
+    // Walk the subtrees under childs, in the given order.
+    fn from_childs(childs: Vec<&'a Child<K, V>>) -> Self {
+        let stack = childs
+            .into_iter()
+            .rev()
+            .map(|child| match child {
+                Child::Leaf(item) => Frame::Items {
+                    items: slice::from_ref(item),
+                    off: 0,
+                },
+                Child::Deep(inode) => {
+                    Frame::new(unsafe { inode.node.load(SeqCst).as_ref().unwrap() })
+                }
+                Child::None => unreachable!(),
+            })
+            .collect();
+        Walker { stack, trail: None }
+    }
+
+    fn from_item(item: &'a Item<K, V>) -> Self {
+        Walker {
+            stack: vec![Frame::Items {
+                items: slice::from_ref(item),
+                off: 0,
+            }],
+            trail: None,
+        }
+    }
+
+    fn empty() -> Self {
+        Walker {
+            stack: vec![],
+            trail: None,
+        }
+    }
+
+    pub(crate) fn new_with_trail(root: &'a Node<K, V>) -> Self {
+        Walker {
+            stack: vec![Frame::new(root)],
+            trail: Some(vec![root as *const Node<K, V> as usize]),
+        }
+    }
+
+    // Position the walker such that items hashed below `hash`, in the walk
+    // order, are skipped. Leaf items sitting above the full depth and
+    // collision lists are yielded as is, caller should filter them.
+    pub(crate) fn new_at(root: &'a Node<K, V>, hash: u32) -> Self {
+        let mut walker = Walker::new(root);
+        for w in slots(hash).iter() {
+            let node = match walker.stack.last_mut() {
+                Some(Frame::Trie { bmp, childs, off }) => {
+                    match hamming_distance(*w, *bmp) {
+                        (false, n) => {
+                            *off = n;
+                            break;
+                        }
+                        (true, n) => {
+                            match unsafe { childs[n].load(SeqCst).as_ref().unwrap() } {
+                                Child::Leaf(_) => {
+                                    *off = n;
+                                    break;
+                                }
+                                Child::Deep(inode) => {
+                                    *off = n + 1;
+                                    unsafe { inode.node.load(SeqCst).as_ref().unwrap() }
+                                }
+                                Child::None => unreachable!(),
+                            }
+                        }
+                    }
+                }
+                Some(Frame::Items { .. }) | None => break,
+            };
+            walker.push(node);
+        }
+        walker
+    }
+
+    fn push(&mut self, node: &'a Node<K, V>) {
+        if let Some(trail) = self.trail.as_mut() {
+            trail.push(node as *const Node<K, V> as usize)
+        }
+        self.stack.push(Frame::new(node));
+    }
+}
+
+impl<'a, K, V> Iterator for Walker<'a, K, V> {
+    type Item = &'a Item<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = match self.stack.last_mut()? {
+                Frame::Trie { childs, off, .. } => {
+                    let childs: &'a [AtomicPtr<Child<K, V>>] = childs;
+                    match childs.get(*off) {
+                        Some(ptr) => {
+                            *off += 1;
+                            match unsafe { ptr.load(SeqCst).as_ref().unwrap() } {
+                                Child::Leaf(item) => break Some(item),
+                                Child::Deep(inode) => unsafe {
+                                    inode.node.load(SeqCst).as_ref().unwrap()
+                                },
+                                Child::None => unreachable!(),
+                            }
+                        }
+                        None => {
+                            self.stack.pop();
+                            continue;
+                        }
+                    }
+                }
+                Frame::Items { items, off } => {
+                    let items: &'a [Item<K, V>] = items;
+                    match items.get(*off) {
+                        Some(item) => {
+                            *off += 1;
+                            break Some(item);
+                        }
+                        None => {
+                            self.stack.pop();
+                            continue;
+                        }
+                    }
+                }
+            };
+            self.push(node);
+        }
+    }
+}
+
+/// Return the position of a 32-bit key hash in walk order. Walk order
+/// ascend with the slots from lower nibble to higher nibble of the hash,
+/// which is same as ascending order of nibble reversed hash. The conversion
+/// is its own inverse.
+pub fn to_walk_order(hash: u32) -> u32 {
+    let mut order = 0;
+    for w in slots(hash).iter() {
+        order = (order << 4) | (*w as u32);
+    }
+    order
+}
+
+#[cfg(test)]
+#[path = "iter_test.rs"]
+pub(crate) mod iter_test;
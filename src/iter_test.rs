@@ -0,0 +1,905 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    hash::Hasher,
+    mem, thread,
+};
+
+use super::*;
+use crate::HashWidth;
+
+#[test]
+fn test_iter() {
+    let (_, mut rng, n_keys) = seed_keys("test_iter");
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut items: Vec<(u32, u64)> = map.iter().collect();
+    items.sort_unstable();
+    let mut keys: Vec<u32> = map.keys().collect();
+    keys.sort_unstable();
+    let mut values: Vec<u64> = map.values().collect();
+    values.sort_unstable();
+
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+    assert_eq!(keys, refs.iter().map(|(k, _)| *k).collect::<Vec<u32>>());
+    let mut refs: Vec<u64> = refs.into_iter().map(|(_, v)| v).collect();
+    refs.sort_unstable();
+    assert_eq!(values, refs);
+}
+
+#[test]
+fn test_iter_concurrent() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [1000, 10_000, 100_000][rng.gen::<usize>() % 3];
+    println!("test_iter_concurrent seed:{} n_keys:{}", seed, n_keys);
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    with_writer(seed, n_keys, &mut map, 4, |map| {
+        let items: Vec<(u32, u64)> = map.iter().collect();
+        for (key, value) in items.iter().filter(|(k, _)| *k < (n_keys / 2)) {
+            assert_eq!(*key as u64, *value)
+        }
+        items.into_iter().map(|(k, _)| k).collect()
+    });
+}
+
+#[test]
+fn test_iter_repin() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [1000, 10_000, 100_000][rng.gen::<usize>() % 3];
+    println!("test_iter_repin seed:{} n_keys:{}", seed, n_keys);
+
+    for period in [0, 1, 3, REPIN_PERIOD] {
+        iter_repin_with(seed, n_keys, period, DefaultHasher::new());
+        // force collisions, re-pinning shall not split a collision group.
+        iter_repin_with(seed, n_keys / 10, period, CollisionHasher::default());
+    }
+}
+
+fn iter_repin_with<H>(seed: u128, n_keys: u32, period: usize, hash_builder: H)
+where
+    H: 'static + Send + BuildHasher + Clone,
+{
+    let mut map: Map<u32, u64, H> = Map::new(2, hash_builder);
+    map.set_repin_period(period);
+    with_writer(seed, n_keys, &mut map, 4, |map| map.keys().collect());
+}
+
+#[test]
+fn test_iter_with_hash() {
+    let (_, mut rng, n_keys) = seed_keys("test_iter_with_hash");
+
+    let mut map: Map<u32, u64> = Map::new(1, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut last = 0;
+    let mut items = vec![];
+    for (hash, key, value) in map.iter_with_hash() {
+        assert_eq!(hash, map.to_hash(&key));
+        // hashes ascend in walk order.
+        assert!(to_walk_order(hash) >= last, "{:x} {:x}", hash, last);
+        last = to_walk_order(hash);
+        items.push((key, value));
+    }
+
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_keys_no_clone() {
+    use std::sync::atomic::AtomicUsize;
+
+    static N_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug)]
+    struct Value;
+
+    impl Clone for Value {
+        fn clone(&self) -> Self {
+            N_CLONES.fetch_add(1, SeqCst);
+            Value
+        }
+    }
+
+    let mut map: Map<u32, Value> = Map::new(1, DefaultHasher::new());
+    for key in 0..1000 {
+        map.set(key, Value);
+    }
+
+    let n = N_CLONES.load(SeqCst);
+    let mut keys: Vec<u32> = map.keys().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, (0..1000).collect::<Vec<u32>>());
+    assert_eq!(N_CLONES.load(SeqCst), n);
+}
+
+#[test]
+fn test_scan_filter() {
+    use std::sync::atomic::AtomicUsize;
+
+    static N_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Value(u64);
+
+    impl Clone for Value {
+        fn clone(&self) -> Self {
+            N_CLONES.fetch_add(1, SeqCst);
+            Value(self.0)
+        }
+    }
+
+    let (_, mut rng, n_keys) = seed_keys("test_scan_filter");
+    let modulo = [1, 2, 10, 100][rng.gen::<usize>() % 4];
+    println!("test_scan_filter modulo:{}", modulo);
+
+    let mut map: Map<u32, Value> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>(), rng.gen::<u64>());
+        map.set(key, Value(value));
+        btmap.insert(key, value);
+    }
+
+    let n = N_CLONES.load(SeqCst);
+    let mut items: Vec<(u32, u64)> = map
+        .scan_filter(|_, v| v.0 % modulo == 0)
+        .map(|(k, v)| (k, v.0))
+        .collect();
+    items.sort_unstable();
+
+    let refs: Vec<(u32, u64)> =
+        btmap.into_iter().filter(|(_, v)| v % modulo == 0).collect();
+    assert_eq!(items, refs);
+    // only matching values are cloned.
+    assert_eq!(N_CLONES.load(SeqCst) - n, refs.len());
+}
+
+#[test]
+fn test_into_keys_values() {
+    use std::sync::atomic::AtomicUsize;
+
+    static N_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Value(u64);
+
+    impl Clone for Value {
+        fn clone(&self) -> Self {
+            N_CLONES.fetch_add(1, SeqCst);
+            Value(self.0)
+        }
+    }
+
+    let (_, mut rng, n_keys) = seed_keys("test_into_keys_values");
+
+    let mut map: Map<u32, Value, CollisionHasher> =
+        Map::new(2, CollisionHasher::default());
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..(n_keys / 10) {
+        let (key, value) = (rng.gen::<u32>(), rng.gen::<u64>());
+        map.set(key, Value(value));
+        btmap.insert(key, value);
+    }
+    // a dropped clone shall not count as the map being shared.
+    mem::drop(map.clone());
+
+    // all other clones are dropped, values are moved out of the trie.
+    let n = N_CLONES.load(SeqCst);
+    let mut values: Vec<u64> = map.into_values().map(|v| v.0).collect();
+    assert_eq!(N_CLONES.load(SeqCst), n);
+    values.sort_unstable();
+    let mut refs: Vec<u64> = btmap.values().copied().collect();
+    refs.sort_unstable();
+    assert_eq!(values, refs);
+
+    // with other clones alive, keys are cloned out of the shared map.
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    for (key, value) in btmap.iter() {
+        map.set(*key, *value);
+    }
+    let other = map.clone();
+    let keys: Vec<u32> = map.into_keys().collect();
+    assert_eq!(keys.len(), btmap.len());
+    let mut items: Vec<(u32, u64)> = other.iter().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_snapshot_iter() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [1000, 10_000][rng.gen::<usize>() % 2];
+    println!("test_snapshot_iter seed:{} n_keys:{}", seed, n_keys);
+
+    let map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+
+    // writer inserts keys in ascending order and then removes them in
+    // ascending order, hence any snapshot shall hold a contiguous range.
+    let mut writer = map.clone();
+    let handle = thread::spawn(move || {
+        for key in 0..n_keys {
+            writer.set(key, key as u64);
+        }
+        for key in 0..n_keys {
+            writer.remove(&key);
+        }
+        writer
+    });
+
+    let mut n_snapshots = 0;
+    loop {
+        let done = handle.is_finished();
+
+        let iter = map.snapshot_iter();
+        let n = iter.len();
+        let mut items: Vec<(u32, u64)> = iter.collect();
+        assert_eq!(n, items.len());
+        items.sort_unstable();
+        if let (Some((start, _)), Some((end, _))) = (items.first(), items.last()) {
+            let refs: Vec<(u32, u64)> = (*start..=*end).map(|k| (k, k as u64)).collect();
+            assert_eq!(items, refs);
+        }
+        n_snapshots += 1;
+
+        if done {
+            assert!(items.is_empty());
+            break;
+        }
+    }
+    println!("test_snapshot_iter n_snapshots:{}", n_snapshots);
+
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_scan_from() {
+    let (seed, mut rng, n_keys) = seed_keys("test_scan_from");
+    let limit = [0, 1, 7, 100, 1000][rng.gen::<usize>() % 5];
+    println!("test_scan_from limit:{}", limit);
+
+    scan_from_with(seed, n_keys, limit, DefaultHasher::new());
+    // force collisions.
+    scan_from_with(seed, n_keys / 10, limit, CollisionHasher::default());
+    // limit of 0 is treated as 1.
+    scan_from_with(seed, 100, 0, DefaultHasher::new());
+}
+
+fn scan_from_with<H>(seed: u128, n_keys: usize, limit: usize, hash_builder: H)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::new(2, hash_builder);
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let (mut items, mut token) = (vec![], Some(0));
+    let mut n_chunks = 0;
+    while let Some(from) = token {
+        let (chunk, next) = map.scan_from(from, limit);
+        if let Some(next) = next {
+            assert!(next >= from, "{} {}", next, from);
+        }
+        // every call makes progress, even with a limit of 0.
+        assert!(!chunk.is_empty() || next.is_none());
+        assert_ne!(next, Some(from));
+
+        items.extend(chunk);
+        token = next;
+        n_chunks += 1;
+    }
+    println!("test_scan_from n_chunks:{}", n_chunks);
+
+    // chunks shall follow the walk order of iter().
+    let refs: Vec<(u32, u64)> = map.iter().collect();
+    assert_eq!(items.len(), refs.len());
+    let mut refs = refs.into_iter();
+    for (key, value) in items.clone().into_iter() {
+        // order within collision list may change, only the hash is
+        // ordered.
+        let (k, v) = refs.next().unwrap();
+        assert_eq!(map.to_hash(&key), map.to_hash(&k));
+        assert_eq!(btmap.get(&k), Some(&v));
+        assert_eq!(btmap.get(&key), Some(&value));
+    }
+
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+// Hash u32 keys into 256 buckets.
+#[derive(Clone, Default)]
+pub(crate) struct CollisionHasher {
+    key: u32,
+}
+
+impl BuildHasher for CollisionHasher {
+    type Hasher = Self;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        self.clone()
+    }
+}
+
+impl Hasher for CollisionHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.key = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    // upper hash marker, refer to key_to_upper64() in map.rs, keys collide
+    // on both the halves.
+    fn write_u8(&mut self, _marker: u8) {}
+
+    fn finish(&self) -> u64 {
+        (self.key & 0xFF).into()
+    }
+}
+
+// Seed a test run and pick the number of keys to load, the seed is printed
+// along with `name`, so that a failing run can be replayed.
+pub(crate) fn seed_keys(name: &str) -> (u128, SmallRng, usize) {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [0, 1, 10, 1000, 100_000][rng.gen::<usize>() % 5];
+    println!("{} seed:{} n_keys:{}", name, seed, n_keys);
+    (seed, rng, n_keys)
+}
+
+// Set `n_keys` random entries in `map`, and return them as a reference map.
+pub(crate) fn load_keys<H>(
+    map: &mut Map<u32, u64, H>,
+    rng: &mut SmallRng,
+    n_keys: usize,
+) -> BTreeMap<u32, u64>
+where
+    H: BuildHasher,
+{
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>(), rng.gen::<u64>());
+        map.set(key, value);
+        btmap.insert(key, value);
+    }
+    btmap
+}
+
+// Call `$with(seed, n_keys, hash_builder)` with DefaultHasher, and again with
+// CollisionHasher on a tenth of the keys, to force collisions.
+macro_rules! with_hashers {
+    ($with:ident, $seed:expr, $n_keys:expr) => {{
+        $with($seed, $n_keys, DefaultHasher::new());
+        $with($seed, $n_keys / 10, CollisionHasher::default());
+    }};
+}
+pub(crate) use with_hashers;
+
+// Set keys 0..n_keys in `map`, each valued as the key, and call `scan`
+// `rounds` times while a writer thread sets and removes keys in the upper
+// half and beyond. Keys returned by `scan` shall be seen at most once, and
+// all the stable keys, in the lower half, shall be seen.
+pub(crate) fn with_writer<H, F>(
+    seed: u128,
+    n_keys: u32,
+    map: &mut Map<u32, u64, H>,
+    rounds: usize,
+    mut scan: F,
+) where
+    H: 'static + Send + BuildHasher + Clone,
+    F: FnMut(&mut Map<u32, u64, H>) -> Vec<u32>,
+{
+    for key in 0..n_keys {
+        map.set(key, key as u64);
+    }
+
+    let mut writer = map.clone();
+    let handle = thread::spawn(move || {
+        let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+        for _i in 0..n_keys {
+            let key = rng.gen_range(n_keys / 2, n_keys * 2);
+            match rng.gen::<u8>() % 2 {
+                0 => writer.set(key, (key as u64) + 1),
+                _ => writer.remove(&key),
+            };
+        }
+        writer
+    });
+
+    for _i in 0..rounds {
+        let mut keys = scan(map);
+        keys.sort_unstable();
+
+        let n = keys.len();
+        keys.dedup();
+        assert_eq!(n, keys.len(), "duplicate keys");
+
+        let stable = keys.into_iter().filter(|k| *k < (n_keys / 2)).count();
+        assert_eq!(stable, (n_keys / 2) as usize);
+    }
+
+    mem::drop(handle.join().unwrap());
+}
+
+#[test]
+fn test_iter_chunks() {
+    let (_, mut rng, n_keys) = seed_keys("test_iter_chunks");
+    let chunk_size = [0, 1, 7, 100, 1024][rng.gen::<usize>() % 5];
+    println!("test_iter_chunks chunk_size:{}", chunk_size);
+
+    let mut map: Map<u32, u64> = Map::new(1, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let chunks: Vec<Vec<(u32, u64)>> = map.iter_chunks(chunk_size).collect();
+    if let Some((_, chunks)) = chunks.split_last() {
+        // only the last chunk can be short.
+        for chunk in chunks.iter() {
+            assert!(chunk.len() >= usize::max(chunk_size, 1), "{}", chunk.len());
+        }
+    }
+
+    let mut items: Vec<(u32, u64)> = chunks.into_iter().flatten().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_partitions() {
+    let (_, mut rng, n_keys) = seed_keys("test_partitions");
+    let n = [0, 1, 3, 16, 100][rng.gen::<usize>() % 5];
+    println!("test_partitions n:{}", n);
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+    let hashes: BTreeMap<u32, u32> = btmap.keys().map(|k| (*k, map.to_hash(k))).collect();
+
+    let partitions = map.partitions(n);
+    assert_eq!(partitions.len(), n);
+
+    type Part = (ops::Range<u64>, Vec<(u32, u64)>);
+    let mut parts: Vec<Part> = thread::scope(|s| {
+        let handles: Vec<_> = partitions
+            .into_iter()
+            .map(|part| {
+                s.spawn(move || (part.to_range(), part.collect::<Vec<(u32, u64)>>()))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut items = vec![];
+    let mut till = 0;
+    for (range, part) in parts.drain(..) {
+        assert_eq!(range.start, till);
+        till = range.end;
+        for (key, _) in part.iter() {
+            let order = to_walk_order(hashes[key]) as u64;
+            assert!(range.contains(&order), "{:?} {}", range, order);
+        }
+        items.extend(part);
+    }
+    if n > 0 {
+        assert_eq!(till, 1 << 32);
+        items.sort_unstable();
+        let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+        assert_eq!(items, refs);
+    }
+
+    // all partitions are dropped, map can be mutated again.
+    map.set(0, 0);
+    assert_eq!(map.get(&0), Some(0));
+}
+
+#[test]
+fn test_for_each() {
+    let (seed, _, n_keys) = seed_keys("test_for_each");
+
+    with_hashers!(for_each_with, seed, n_keys);
+}
+
+fn for_each_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::new(2, hash_builder);
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut items = vec![];
+    map.for_each(|k, v| items.push((*k, *v)));
+
+    // for_each shall follow the walk order of iter().
+    let refs: Vec<(u32, u64)> = map.iter().collect();
+    assert_eq!(items.len(), refs.len());
+    for ((key, _), (k, _)) in items.iter().zip(refs.iter()) {
+        assert_eq!(map.to_hash(key), map.to_hash(k));
+    }
+
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_for_each_concurrent() {
+    let seed: u128 = random();
+    let n_keys = 100_000;
+    println!("test_for_each_concurrent seed:{}", seed);
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    with_writer(seed, n_keys, &mut map, 4, |map| {
+        let mut keys = vec![];
+        map.for_each(|k, v| {
+            if *k < n_keys / 2 {
+                assert_eq!(*k as u64, *v);
+            }
+            keys.push(*k)
+        });
+        keys
+    });
+}
+
+#[test]
+fn test_for_each_mut() {
+    let (seed, _, n_keys) = seed_keys("test_for_each_mut");
+
+    for_each_mut_with(seed, n_keys, DefaultHasher::new(), HashWidth::Bits32);
+    // force collisions, colliding keys are in lists for Bits32, and in
+    // subtries below the 8th level for wider hashes.
+    for width in [
+        HashWidth::Bits32,
+        HashWidth::Bits64,
+        HashWidth::Bits128,
+        HashWidth::Rehash,
+    ] {
+        for_each_mut_with(seed, n_keys / 10, CollisionHasher::default(), width);
+    }
+}
+
+fn for_each_mut_with<H>(seed: u128, n_keys: usize, hash_builder: H, width: HashWidth)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::with_hash_width(2, hash_builder, width);
+    let mut btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut n_calls = 0;
+    map.for_each_mut(|k, v| {
+        assert_eq!(btmap.get(k), Some(&*v));
+        *v = (*v / 10) * 9;
+        n_calls += 1;
+    });
+    assert_eq!(n_calls, btmap.len());
+    btmap.values_mut().for_each(|v| *v = (*v / 10) * 9);
+
+    let mut items: Vec<(u32, u64)> = map.iter().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+
+    map.validate();
+}
+
+#[test]
+fn test_for_each_mut_concurrent() {
+    let seed: u128 = random();
+    let n_keys = 100_000;
+    println!("test_for_each_mut_concurrent seed:{}", seed);
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    with_writer(seed, n_keys, &mut map, 1, |map| {
+        // callback is called again for a run that failed its CAS.
+        let mut keys = BTreeSet::new();
+        map.for_each_mut(|k, v| {
+            *v += 1;
+            keys.insert(*k);
+        });
+        keys.into_iter().collect()
+    });
+
+    // stable keys shall be updated exactly once.
+    for key in 0..(n_keys / 2) {
+        assert_eq!(map.get(&key), Some((key as u64) + 1), "key:{}", key);
+    }
+}
+
+#[test]
+fn test_extract_if() {
+    let (seed, _, n_keys) = seed_keys("test_extract_if");
+
+    with_hashers!(extract_if_with, seed, n_keys);
+}
+
+fn extract_if_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::new(2, hash_builder);
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut items: Vec<(u32, u64)> = map.extract_if(|_, v| v % 3 == 0).collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap
+        .iter()
+        .filter(|(_, v)| *v % 3 == 0)
+        .map(|(k, v)| (*k, *v))
+        .collect();
+    assert_eq!(items, refs);
+
+    let mut items: Vec<(u32, u64)> = map.iter().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().filter(|(_, v)| v % 3 != 0).collect();
+    assert_eq!(items, refs);
+
+    // dropping the iterator early shall leave the rest untouched.
+    let n = map.extract_if(|_, _| true).take(10).count();
+    assert_eq!(n, usize::min(refs.len(), 10));
+    assert_eq!(map.len(), refs.len() - n);
+
+    map.validate();
+}
+
+#[test]
+fn test_extract_if_concurrent() {
+    let seed: u128 = random();
+    let n_keys = 100_000;
+    println!("test_extract_if_concurrent seed:{}", seed);
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    for key in 0..n_keys {
+        map.set(key, key as u64);
+    }
+
+    // writer only sets odd values, in the upper half.
+    let mut writer = map.clone();
+    let handle = thread::spawn(move || {
+        let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+        for _i in 0..n_keys {
+            let key = rng.gen_range(n_keys / 2, n_keys);
+            writer.set(key, (key as u64) * 2 + 1);
+        }
+        writer
+    });
+
+    let items: Vec<(u32, u64)> = map.extract_if(|_, v| v % 2 == 0).collect();
+    mem::drop(handle.join().unwrap());
+
+    assert!(items.iter().all(|(_, v)| v % 2 == 0));
+    let n_lower = items.iter().filter(|(k, _)| *k < n_keys / 2).count();
+    assert_eq!(n_lower, (n_keys / 4) as usize);
+    // even values are never left behind, nor odd values removed.
+    map.for_each(|k, v| assert!(v % 2 == 1, "key:{} value:{}", k, v));
+    let extracted: BTreeMap<u32, u64> = items.into_iter().collect();
+    for key in 0..n_keys {
+        assert!(
+            map.get(&key).is_some() || extracted.contains_key(&key),
+            "key:{}",
+            key
+        );
+    }
+}
+
+#[test]
+fn test_fold() {
+    let (_, mut rng, n_keys) = seed_keys("test_fold");
+
+    let mut map: Map<u32, u64> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>(), rng.gen::<u64>() % 1000);
+        map.set(key, value);
+        btmap.insert(key, value);
+    }
+
+    let (n, sum) = map.fold((0, 0), |(n, sum), _, v| (n + 1, sum + v));
+    assert_eq!(n, btmap.len());
+    assert_eq!(sum, btmap.values().sum::<u64>());
+
+    let max = map.fold(None, |max: Option<u32>, k, _| max.max(Some(*k)));
+    assert_eq!(max, btmap.keys().last().copied());
+}
+
+#[test]
+fn test_scan_prefix() {
+    let (seed, _, n_keys) = seed_keys("test_scan_prefix");
+
+    with_hashers!(scan_prefix_with, seed, n_keys);
+}
+
+fn scan_prefix_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut map: Map<u32, u64, H> = Map::new(2, hash_builder);
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    assert!(map.scan_prefix(0, 33).is_err());
+
+    for _i in 0..20 {
+        let (prefix, n_bits) = (rng.gen::<u32>(), rng.gen::<usize>() % 33);
+        let mask = ((1_u64 << n_bits) - 1) as u32;
+
+        let mut items: Vec<(u32, u64)> =
+            map.scan_prefix(prefix, n_bits).unwrap().collect();
+        items.sort_unstable();
+        let refs: Vec<(u32, u64)> = btmap
+            .iter()
+            .filter(|(k, _)| (map.to_hash(*k) & mask) == (prefix & mask))
+            .map(|(k, v)| (*k, *v))
+            .collect();
+        assert_eq!(items, refs, "prefix:{:x} n_bits:{}", prefix, n_bits);
+
+        // prefix from existing keys, to hit the deeper levels.
+        if let Some((key, _)) = btmap.iter().nth(rng.gen::<usize>() % btmap.len().max(1))
+        {
+            let prefix = map.to_hash(key);
+            let mut items: Vec<(u32, u64)> =
+                map.scan_prefix(prefix, n_bits).unwrap().collect();
+            items.sort_unstable();
+            let refs: Vec<(u32, u64)> = btmap
+                .iter()
+                .filter(|(k, _)| (map.to_hash(*k) & mask) == (prefix & mask))
+                .map(|(k, v)| (*k, *v))
+                .collect();
+            assert!(!items.is_empty());
+            assert_eq!(items, refs, "prefix:{:x} n_bits:{}", prefix, n_bits);
+        }
+    }
+}
+
+#[test]
+fn test_guard() {
+    let (_, mut rng, n_keys) = seed_keys("test_guard");
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut writer = map.clone();
+    {
+        let guard = map.guard();
+        let mut values: Vec<&u64> = btmap.keys().map(|k| guard.get(k).unwrap()).collect();
+        assert_eq!(guard.get(&0).is_some(), btmap.contains_key(&0));
+
+        // concurrent writer shall not reclaim the referenced values.
+        let keys: Vec<u32> = btmap.keys().copied().collect();
+        let handle = thread::spawn(move || {
+            for key in keys.into_iter() {
+                writer.remove(&key);
+            }
+            writer
+        });
+        writer = handle.join().unwrap();
+
+        let refs: Vec<&u64> = btmap.values().collect();
+        assert_eq!(values, refs);
+        values.clear();
+
+        // reads via the guard observe the removal, and nest with map reads.
+        assert!(guard.iter().next().is_none());
+        let key = btmap.keys().next().copied().unwrap_or(0);
+        assert_eq!(guard.get(&key), None);
+        assert_eq!(map.get(&key), None);
+        assert!(guard.scan_prefix(0, 33).is_err());
+        assert!(guard.scan_prefix(0, 0).unwrap().next().is_none());
+    }
+
+    for (key, value) in btmap.iter() {
+        writer.set(*key, *value);
+    }
+    let guard = map.guard();
+    let mut items: Vec<(u32, u64)> = guard.iter().map(|(k, v)| (*k, *v)).collect();
+    items.sort_unstable();
+    for (key, value) in btmap.iter() {
+        let hash = map.hash_key(key);
+        assert_eq!(guard.get_hashed(hash, key), Some(value));
+        assert_eq!(guard.get_key_value(key), Some((key, value)));
+    }
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}
+
+#[test]
+fn test_merge_iter() {
+    let (seed, _, n_keys) = seed_keys("test_merge_iter");
+
+    with_hashers!(merge_iter_with, seed, n_keys);
+}
+
+fn merge_iter_with<H>(seed: u128, n_keys: usize, hash_builder: H)
+where
+    H: BuildHasher + Clone,
+{
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    // keys are drawn from a small space, so that the maps overlap.
+    let space = (n_keys as u32).saturating_mul(2).max(1);
+    let mut lmap: Map<u32, u64, H> = Map::new(1, hash_builder.clone());
+    let mut rmap: Map<u32, u64, H> = Map::new(1, hash_builder);
+    let (mut lbt, mut rbt) = (BTreeMap::new(), BTreeMap::new());
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>() % space, rng.gen::<u64>());
+        match rng.gen::<u8>() % 3 {
+            0 => {
+                lmap.set(key, value);
+                lbt.insert(key, value);
+            }
+            1 => {
+                rmap.set(key, value);
+                rbt.insert(key, value);
+            }
+            _ => {
+                lmap.set(key, value);
+                lbt.insert(key, value);
+                rmap.set(key, value + 1);
+                rbt.insert(key, value + 1);
+            }
+        }
+    }
+
+    let mut items = vec![];
+    let mut last = 0;
+    for item in lmap.merge_iter(&rmap) {
+        let key = match &item {
+            Merge::Left(k, _) | Merge::Right(k, _) | Merge::Both(k, _, _) => *k,
+        };
+        let order = to_walk_order(lmap.to_hash(&key));
+        assert!(order >= last, "{} {}", order, last);
+        last = order;
+        items.push((key, item));
+    }
+
+    let mut refs = vec![];
+    for (key, lvalue) in lbt.iter() {
+        match rbt.get(key) {
+            Some(rvalue) => refs.push((*key, Merge::Both(*key, *lvalue, *rvalue))),
+            None => refs.push((*key, Merge::Left(*key, *lvalue))),
+        }
+    }
+    for (key, rvalue) in rbt.iter() {
+        if !lbt.contains_key(key) {
+            refs.push((*key, Merge::Right(*key, *rvalue)))
+        }
+    }
+
+    items.sort_unstable_by_key(|(k, _)| *k);
+    refs.sort_unstable_by_key(|(k, _)| *k);
+    assert_eq!(items, refs);
+
+    // merging with itself.
+    assert!(lmap
+        .merge_iter(&lmap)
+        .all(|item| matches!(item, Merge::Both(_, l, r) if l == r)));
+}
+
+#[test]
+fn test_to_walk_order() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_to_walk_order seed:{}", seed);
+
+    assert_eq!(to_walk_order(0x1), 0x1000_0000);
+    assert_eq!(to_walk_order(0x1234_5678), 0x8765_4321);
+    for _i in 0..1000 {
+        let hash: u32 = rng.gen();
+        assert_eq!(to_walk_order(to_walk_order(hash)), hash);
+    }
+}
@@ -0,0 +1,285 @@
+//! Module implement encoders for composite keys.
+//!
+//! Composite keys are encoded into a fixed width representation, without
+//! allocation, so that they can be hashed in one go, say with
+//! [KeyHasher][crate::KeyHasher].
+//!
+//! * [Pack], for tuples of integers packed into a single ``u64`` or
+//!   ``u128``. Packed integers compare in the same order as their tuples.
+//! * [IntStr], for an integer along with a short string, held inline.
+//! * [SmallStr], for string keys held inline when short, and on the heap
+//!   otherwise. Lookup using `&str`.
+//!
+//! ```
+//! use cmap::key::{IntStr, Pack, SmallStr};
+//! use cmap::{DefaultHasher, Map};
+//!
+//! let key = (10_u32, 20_u32).pack();
+//! assert_eq!(<(u32, u32)>::unpack(key), (10, 20));
+//!
+//! let key: IntStr<15> = IntStr::new(10, "ten").unwrap();
+//! assert_eq!((key.to_int(), key.as_str()), (10, "ten"));
+//!
+//! let mut map: Map<SmallStr<22>, u64> = Map::new(1, DefaultHasher::new());
+//! map.set(SmallStr::new("/index.html"), 10);
+//! assert_eq!(map.get("/index.html"), Some(10));
+//! ```
+
+use std::{
+    borrow::Borrow,
+    cmp,
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str,
+};
+
+use crate::{Error, Result};
+
+/// Trait to pack a tuple of integers into a single integer, and back.
+/// First field of the tuple is held in the most significant bits.
+pub trait Pack: Sized {
+    /// Integer type holding the packed tuple.
+    type Packed;
+
+    /// Pack this tuple into an integer.
+    fn pack(self) -> Self::Packed;
+
+    /// Unpack the tuple from `packed`.
+    fn unpack(packed: Self::Packed) -> Self;
+}
+
+macro_rules! impl_pack {
+    ($packed:ty; $($t:ident: $ty:ty),+) => {
+        impl Pack for ($($ty,)+) {
+            type Packed = $packed;
+
+            fn pack(self) -> $packed {
+                let ($($t,)+) = self;
+                let mut packed: $packed = 0;
+                $(
+                    packed = (packed << <$ty>::BITS) | ($t as $packed);
+                )+
+                packed
+            }
+
+            #[allow(unused_assignments)]
+            fn unpack(packed: $packed) -> Self {
+                let mut shift = <$packed>::BITS;
+                $(
+                    shift -= <$ty>::BITS;
+                    let $t = (packed >> shift) as $ty;
+                )+
+                ($($t,)+)
+            }
+        }
+    };
+}
+
+impl_pack!(u32; a: u16, b: u16);
+impl_pack!(u64; a: u32, b: u32);
+impl_pack!(u64; a: u16, b: u16, c: u16, d: u16);
+impl_pack!(u64; a: u16, b: u16, c: u32);
+impl_pack!(u64; a: u32, b: u16, c: u16);
+impl_pack!(u128; a: u64, b: u64);
+impl_pack!(u128; a: u32, b: u32, c: u32, d: u32);
+impl_pack!(u128; a: u32, b: u32, c: u64);
+impl_pack!(u128; a: u64, b: u32, c: u32);
+
+/// Composite key of an integer and a string of upto `N` bytes, held inline.
+/// Keys are ordered by the integer and then by the string.
+#[derive(Clone, Copy)]
+pub struct IntStr<const N: usize> {
+    int: u64,
+    len: u8,
+    bytes: [u8; N],
+}
+
+impl<const N: usize> IntStr<N> {
+    /// Create a new key, fail if `s` is longer than `N` bytes, or longer
+    /// than 255 bytes.
+    pub fn new(int: u64, s: &str) -> Result<IntStr<N>> {
+        let n = s.len();
+        if n > N || n > (u8::MAX as usize) {
+            return err_at!(InvalidInput, msg: "string len:{} is more than {}", n, N);
+        }
+        let mut bytes = [0_u8; N];
+        bytes[..n].copy_from_slice(s.as_bytes());
+        Ok(IntStr {
+            int,
+            len: n as u8,
+            bytes,
+        })
+    }
+
+    /// Return the integer part of the key.
+    pub fn to_int(&self) -> u64 {
+        self.int
+    }
+
+    /// Return the string part of the key.
+    pub fn as_str(&self) -> &str {
+        // bytes are copied from a str in new().
+        unsafe { str::from_utf8_unchecked(&self.bytes[..(self.len as usize)]) }
+    }
+}
+
+impl<const N: usize> Hash for IntStr<N> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        // unused bytes are zero, hash the fixed width key in one go.
+        state.write_u64(self.int);
+        state.write_u8(self.len);
+        state.write(&self.bytes);
+    }
+}
+
+impl<const N: usize> PartialEq for IntStr<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.int == other.int && self.len == other.len && self.bytes == other.bytes
+    }
+}
+
+impl<const N: usize> Eq for IntStr<N> {}
+
+impl<const N: usize> PartialOrd for IntStr<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for IntStr<N> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (self.int, self.as_str()).cmp(&(other.int, other.as_str()))
+    }
+}
+
+impl<const N: usize> Debug for IntStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IntStr({}, {:?})", self.int, self.as_str())
+    }
+}
+
+/// String key held inline, without allocation, if it is upto `N` bytes,
+/// and upto 255 bytes, longer strings are held on the heap. Hashes and
+/// compares same as `str`, hence maps keyed by `SmallStr` can be looked
+/// up using `&str`. With `N` as 22, inline keys take the same space as
+/// a `String`.
+#[derive(Clone)]
+pub struct SmallStr<const N: usize>(Repr<N>);
+
+#[derive(Clone)]
+enum Repr<const N: usize> {
+    Inline { len: u8, bytes: [u8; N] },
+    Heap(Box<str>),
+}
+
+impl<const N: usize> SmallStr<N> {
+    /// Create a new key from `s`.
+    pub fn new(s: &str) -> SmallStr<N> {
+        let n = s.len();
+        if n > N || n > (u8::MAX as usize) {
+            return SmallStr(Repr::Heap(s.into()));
+        }
+        let mut bytes = [0_u8; N];
+        bytes[..n].copy_from_slice(s.as_bytes());
+        SmallStr(Repr::Inline {
+            len: n as u8,
+            bytes,
+        })
+    }
+
+    /// Return the key as string.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // bytes are copied from a str in new().
+            Repr::Inline { len, bytes } => unsafe {
+                str::from_utf8_unchecked(&bytes[..(*len as usize)])
+            },
+            Repr::Heap(s) => s,
+        }
+    }
+
+    /// Return whether the key is held inline.
+    pub fn is_inline(&self) -> bool {
+        matches!(&self.0, Repr::Inline { .. })
+    }
+}
+
+impl<const N: usize> From<&str> for SmallStr<N> {
+    fn from(s: &str) -> SmallStr<N> {
+        SmallStr::new(s)
+    }
+}
+
+impl<const N: usize> From<String> for SmallStr<N> {
+    fn from(s: String) -> SmallStr<N> {
+        if s.len() > N || s.len() > (u8::MAX as usize) {
+            SmallStr(Repr::Heap(s.into_boxed_str()))
+        } else {
+            SmallStr::new(&s)
+        }
+    }
+}
+
+impl<const N: usize> Deref for SmallStr<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for SmallStr<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> Borrow<str> for SmallStr<N> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> Hash for SmallStr<N> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        // same as str, required for Borrow<str>.
+        self.as_str().hash(state)
+    }
+}
+
+impl<const N: usize> PartialEq for SmallStr<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> Eq for SmallStr<N> {}
+
+impl<const N: usize> PartialOrd for SmallStr<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for SmallStr<N> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> Debug for SmallStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> Display for SmallStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+#[path = "key_test.rs"]
+mod key_test;
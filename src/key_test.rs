@@ -0,0 +1,121 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, mem};
+
+use super::*;
+use crate::{DefaultHasher, KeyHasher, Map};
+
+#[test]
+fn test_pack() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_pack seed:{}", seed);
+
+    for _i in 0..1000 {
+        let (a, b) = (rng.gen::<u32>(), rng.gen::<u32>());
+        assert_eq!((a, b).pack(), ((a as u64) << 32) | (b as u64));
+        assert_eq!(<(u32, u32)>::unpack((a, b).pack()), (a, b));
+
+        let t = (
+            rng.gen::<u16>(),
+            rng.gen::<u16>(),
+            rng.gen::<u16>(),
+            rng.gen(),
+        );
+        assert_eq!(<(u16, u16, u16, u16)>::unpack(t.pack()), t);
+        let t = (rng.gen::<u16>(), rng.gen::<u16>(), rng.gen::<u32>());
+        assert_eq!(<(u16, u16, u32)>::unpack(t.pack()), t);
+        let t = (rng.gen::<u32>(), rng.gen::<u16>(), rng.gen::<u16>());
+        assert_eq!(<(u32, u16, u16)>::unpack(t.pack()), t);
+        let t = (rng.gen::<u16>(), rng.gen::<u16>());
+        assert_eq!(<(u16, u16)>::unpack(t.pack()), t);
+
+        let t = (rng.gen::<u64>(), rng.gen::<u64>());
+        assert_eq!(t.pack(), ((t.0 as u128) << 64) | (t.1 as u128));
+        assert_eq!(<(u64, u64)>::unpack(t.pack()), t);
+        let t = (
+            rng.gen::<u32>(),
+            rng.gen::<u32>(),
+            rng.gen::<u32>(),
+            rng.gen(),
+        );
+        assert_eq!(<(u32, u32, u32, u32)>::unpack(t.pack()), t);
+        let t = (rng.gen::<u32>(), rng.gen::<u32>(), rng.gen::<u64>());
+        assert_eq!(<(u32, u32, u64)>::unpack(t.pack()), t);
+        let t = (rng.gen::<u64>(), rng.gen::<u32>(), rng.gen::<u32>());
+        assert_eq!(<(u64, u32, u32)>::unpack(t.pack()), t);
+
+        // packed integers compare in the same order as their tuples.
+        let (x, y) = ((a, b), (rng.gen::<u32>(), rng.gen::<u32>()));
+        assert_eq!(x.cmp(&y), x.pack().cmp(&y.pack()));
+    }
+}
+
+#[test]
+fn test_int_str() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_int_str seed:{}", seed);
+
+    assert!(IntStr::<4>::new(1, "hello").is_err());
+    assert!(IntStr::<300>::new(1, &"a".repeat(256)).is_err());
+    let key = IntStr::<5>::new(1, "hello").unwrap();
+    assert_eq!((key.to_int(), key.as_str()), (1, "hello"));
+    assert_eq!(IntStr::<8>::new(2, "").unwrap().as_str(), "");
+
+    let mut map: Map<IntStr<15>, u64, KeyHasher> = Map::new(1, KeyHasher::default());
+    let mut btmap: BTreeMap<(u64, String), u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let int = rng.gen::<u64>() % 100;
+        let s = "x".repeat(rng.gen::<usize>() % 16);
+        let (key, value) = (IntStr::new(int, &s).unwrap(), rng.gen::<u64>());
+        assert_eq!((key.to_int(), key.as_str()), (int, s.as_str()));
+        assert_eq!(map.set(key, value), btmap.insert((int, s), value));
+    }
+
+    let mut items: Vec<(IntStr<15>, u64)> = map.iter().collect();
+    items.sort();
+    let items: Vec<((u64, String), u64)> = items
+        .into_iter()
+        .map(|(k, v)| ((k.to_int(), k.as_str().to_string()), v))
+        .collect();
+    assert_eq!(
+        items,
+        btmap.into_iter().collect::<Vec<((u64, String), u64)>>()
+    );
+}
+
+#[test]
+fn test_small_str() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_small_str seed:{}", seed);
+
+    assert_eq!(mem::size_of::<SmallStr<22>>(), mem::size_of::<String>());
+    assert!(SmallStr::<4>::new("").is_inline());
+    assert!(SmallStr::<4>::new("four").is_inline());
+    assert!(!SmallStr::<4>::new("fives").is_inline());
+    assert!(!SmallStr::<300>::new(&"a".repeat(256)).is_inline());
+    assert!(!SmallStr::<4>::from("fives".to_string()).is_inline());
+    assert_eq!(SmallStr::<4>::from("fives".to_string()).as_str(), "fives");
+    assert_eq!(
+        SmallStr::<4>::new("four"),
+        SmallStr::<4>::from("four".to_string())
+    );
+
+    let mut map: Map<SmallStr<8>, u64> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<String, u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let s = "x".repeat(rng.gen::<usize>() % 16);
+        let (key, value) = (SmallStr::new(&s), rng.gen::<u64>());
+        assert_eq!(key.is_inline(), s.len() <= 8);
+        assert_eq!(map.set(key, value), btmap.insert(s.clone(), value));
+        assert_eq!(map.get(s.as_str()), Some(value));
+    }
+
+    let mut items: Vec<(SmallStr<8>, u64)> = map.iter().collect();
+    items.sort();
+    let items: Vec<(String, u64)> =
+        items.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    assert_eq!(items, btmap.into_iter().collect::<Vec<(String, u64)>>());
+}
@@ -13,7 +13,8 @@
 //! This implementation of hash map cannot be strictly classified into either
 //! of the above definition. It supports concurrent writes, using atomic
 //! ``Load``, ``Store`` and ``Cas`` operations under the hood, and _does not_
-//! provide point in time snapshot for transactional operations or iterative operations.
+//! provide point in time snapshot for transactional operations. Iterative
+//! operations are weakly consistent, except for [Map::snapshot_iter].
 //!
 //! If point in time snapshots are needed refer to [ppom] package, that
 //! implement ordered map with multi-reader concurrency and serialised writes.
@@ -21,9 +22,58 @@
 //! - Each entry in [Map] instance correspond to a {Key, Value} pair.
 //! - Parametrised over `key-type` and `value-type`.
 //! - API - set(), get(), remove() using key.
+//! - Composite keys - [key] module to encode multi-part keys into a fixed
+//!   width representation.
+//! - Short string keys held inline, [SmallStr][key::SmallStr], without a
+//!   heap allocation per key.
+//! - Key normalization - [Normalized][normalize::Normalized] keys, say for
+//!   case-insensitive lookups.
+//! - Key interning - [Interner] to store identical large keys once.
+//! - Guarded reads - guard() pins the epoch once for a batch of reads.
+//! - Bulk load - [Map::bulk_load] to build the trie bottom up, for loading
+//!   large data sets into an empty map.
+//! - Write batches - [Map::batch] to stage mutations, and apply them grouped
+//!   by their path in the trie.
+//! - Pre-hashed API - set_hashed(), get_hashed(), remove_hashed() using
+//!   key and its hash, computed by hash_key().
+//! - Multi-map API - append(), get_all(), remove_value() for list of values.
+//! - Iteration API - iter(), keys(), values(), weakly consistent with
+//!   concurrent writes.
+//! - Structural checks - check_invariants() to verify the trie, refer to
+//!   [check] module.
+//! - Read-only view - freeze() the map into an immutable [FrozenMap][frozen::FrozenMap],
+//!   a full copy of the entries, and freeze_compact() to index the view for
+//!   faster lookups.
 //! - Uses ownership model and borrow semantics to ensure safety.
 //! - Implement a custom epoch-based-garbage-collection to handle write
 //!   concurrency and memory optimization.
+//! - footprint() to account for the bytes held by the map.
+//! - gc_stats() with [Histogram] of gc latency and reclaim age, to diagnose
+//!   readers stalling garbage collection.
+//! - Optional destructor thread, spawn_destructor(), to drop reclaimed
+//!   entries off the write path.
+//! - Bounded reclaim backlog, set_backlog(), with writer backpressure.
+//! - Amortized epoch advancement, set_epoch_period(), for write heavy
+//!   workloads.
+//! - Pluggable [Backoff] between retries of conflicting writes, set_backoff().
+//! - [Backoff::Lock], writes contending on a subtree beyond a retry limit
+//!   take turns on a stripe lock, bounding retries on hot keys.
+//! - Optional quiescent-state-based reclamation, enable_qsbr(), where
+//!   reads do not enter the epoch.
+//! - Optional background garbage collector, spawn_gc(), to keep write
+//!   latency flat.
+//! - flush_gc() to collect all eligible garbage, say before a memory
+//!   sensitive phase.
+//! - Dropping a map instance does not wait for concurrent readers, garbage
+//!   that is not yet safe to collect is handed over to the other instances.
+//! - Configurable memory pools, set_pool_size() with a [Spill] policy, and
+//!   trim() to release them.
+//! - Optional `paranoid` feature, to keep invariant checks active in
+//!   release builds.
+//! - Optional `leak-check` feature, to track retired allocations till they
+//!   are reclaimed, refer to outstanding_allocations().
+//! - Optional `cache-padded` feature, to align in-nodes to their own cache
+//!   line, so that writers on sibling sub-tries do not false-share.
 //! - No Durability guarantee.
 //! - Thread safe for both concurrent writes and concurrent reads.
 //!
@@ -51,26 +101,64 @@
 //! This allows interesting and efficient hash-generation for application
 //! specific key-set.
 //!
-//! This package define two off-the-self types implementing BuildHasher.
+//! This package define following off-the-self types implementing BuildHasher.
 //!
 //! * [U32Hasher], for applications that are going to use u32 as key type
 //!   and can guarantee unique keys (that is no collision guarantee).
-//! * [DefaultHasher], as default hasher that internally uses google's
-//!   city-hash via [fasthash][fasthash] package, this might change in future
-//!   releases.
+//! * [U64Hasher] and [U128Hasher], same as U32Hasher, for u64 and u128 keys
+//!   like random identifiers, whose bits are consumed by the trie as is.
+//! * [DefaultHasher], as default hasher that internally uses keyed
+//!   SipHash-1-3, with random keys, resisting hash-flooding attacks from
+//!   untrusted keys. Use [DefaultHasher::with_seed] for deterministic
+//!   hashing. This might change in future releases.
+//! * [KeyHasher], for fixed width keys, like u32 and u64, hashed with a
+//!   single multiply-shift, refer to [KeyHash].
+//! * [CityHasher], faster hasher that internally uses google's city-hash
+//!   via [fasthash][fasthash] package, use this only with trusted keys.
+//!
+//! With `ahash` and `fxhash` features, [ahash][ahash] and [fxhash][fxhash]
+//! are available as `AHasher` and `FxHasher`, say
+//! `Map::new(concurrency, AHasher::new())`.
+//!
+//! Any other BuildHasher can be used as well, say
+//! [std::collections::hash_map::RandomState] for keyed hashing, or a
+//! hasher with a fixed seed for hashing that is deterministic across
+//! processes. By default the trie is indexed on 32 bits of the hash, the
+//! 64-bit output of the hasher is folded into 32 bits. For maps holding
+//! billions of keys, use [Map::with_hash_width] with [HashWidth::Bits64]
+//! to index on all the 64 bits, or [HashWidth::Bits128] where full-hash
+//! collisions must be avoided altogether. [HashWidth::Rehash] sticks to 32
+//! bits and re-hashes only the colliding keys. Keys that are already
+//! random identifiers need not be hashed at all, use [U64Hasher] with
+//! [HashWidth::Bits64] or [U128Hasher] with [HashWidth::Bits128], for the
+//! trie to consume all the bits of the key.
+//!
+//! To detect hash-flooding against internet facing services, watch the
+//! counters returned by [Map::collision_stats], or register a callback via
+//! [Map::on_collision_pressure], to be notified of long collision lists.
 //!
 //! [pds]: https://en.wikipedia.org/wiki/Persistent_data_structure
 //! [ppom]: https://github.com/bnclabs/cmap
 //! [fasthash]: https://github.com/flier/rust-fasthash
+//! [ahash]: https://github.com/tkaitchuck/ahash
+//! [fxhash]: https://github.com/cbreeden/fxhash
 
 #![feature(unboxed_closures)]
-#![feature(fn_traits)]
-#![feature(destructuring_assignment)]
 
 #[allow(unused_imports)]
 use std::hash::BuildHasher;
 use std::{error, fmt, result};
 
+// Same as debug_assert!(), and with `paranoid` feature the check is active
+// in release builds as well.
+macro_rules! paranoid_assert {
+    ($($arg:tt)+) => {
+        if cfg!(any(debug_assertions, feature = "paranoid")) {
+            assert!($($arg)+)
+        }
+    };
+}
+
 /// Short form to compose Error values.
 ///
 /// Here are few possible ways:
@@ -118,12 +206,42 @@ macro_rules! err_at {
 }
 
 // mod entry;
+pub mod batch;
+pub mod check;
+mod convert;
+pub mod equivalent;
+pub mod frozen;
 mod gc;
 mod hasher;
+pub mod intern;
+pub mod iter;
+pub mod key;
 mod map;
+pub mod normalize;
+#[cfg(feature = "rayon")]
+pub mod par;
+#[cfg(feature = "raw")]
+pub mod raw;
+#[cfg(feature = "rand")]
+pub mod sample;
+#[cfg(feature = "async")]
+pub mod stream;
 
-pub use hasher::{DefaultHasher, U32Hasher};
-pub use map::Map;
+pub use batch::WriteBatch;
+pub use equivalent::Equivalent;
+pub use gc::{Histogram, Spill};
+#[cfg(feature = "ahash")]
+pub use hasher::AHasher;
+#[cfg(feature = "fxhash")]
+pub use hasher::FxHasher;
+pub use hasher::{
+    CityHasher, DefaultHasher, KeyHash, KeyHasher, SipHasher13, U128Hasher, U32Hasher,
+    U64Hasher,
+};
+pub use intern::{Interned, Interner};
+pub use map::{
+    Backoff, CollisionStats, Footprint, GcStats, HashWidth, Map, Pinned, Subtree,
+};
 
 /// Error variants that can be returned by this package's API.
 ///
@@ -132,6 +250,8 @@ pub use map::Map;
 pub enum Error {
     Fatal(String, String),
     GcFail(String, String),
+    InvalidInput(String, String),
+    Backpressure(String, String),
 }
 
 impl fmt::Display for Error {
@@ -141,6 +261,8 @@ impl fmt::Display for Error {
         match self {
             Fatal(p, msg) => write!(f, "{} Fatal: {}", p, msg),
             GcFail(p, msg) => write!(f, "{} GcFail: {}", p, msg),
+            InvalidInput(p, msg) => write!(f, "{} InvalidInput: {}", p, msg),
+            Backpressure(p, msg) => write!(f, "{} Backpressure: {}", p, msg),
         }
     }
 }
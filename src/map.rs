@@ -1,25 +1,40 @@
 use std::{
-    borrow::Borrow,
     fmt::Debug,
     hash::{BuildHasher, Hash, Hasher},
-    mem,
-    ops::{Add, Deref},
+    hint, mem,
+    ops::{Add, ControlFlow, Deref},
     sync::{
-        atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering::SeqCst},
-        Arc, Mutex,
+        atomic::{
+            AtomicPtr, AtomicU64, AtomicUsize,
+            Ordering::{Relaxed, SeqCst},
+        },
+        mpsc, Arc, Mutex, OnceLock,
     },
     thread, time,
 };
 
 use crate::{
-    gc::{self, Cas},
-    DefaultHasher,
+    gc::{self, Cas, Histogram, Spill},
+    iter::{self, to_walk_order},
+    DefaultHasher, Equivalent, Error, Result,
 };
 
 const SLOT_MASK: u32 = 0xF;
+// Epoch is incremented once for every write, 63 bits of it shall not wrap
+// around for ~290 years at a billion writes per second, hence epochs are
+// compared as plain integers.
 const ENTER_MASK: u64 = 0x8000000000000000;
 const EPOCH_MASK: u64 = 0x7FFFFFFFFFFFFFFF;
 const GC_PERIOD: usize = 16;
+const MAX_LEVELS: usize = 32;
+const REHASH_DEPTH: usize = 16;
+// levels consumed from the lower 32 bits of the hash.
+const LEVELS32: usize = 8;
+// held in the pin count while entering or exiting the epoch, refer to
+// Map::pin_epoch.
+const PIN_LOCK: usize = 1 << (usize::BITS - 1);
+// stripe locks for Backoff::Lock, indexed by the first two levels of path.
+const STRIPES: usize = 256;
 
 #[allow(unused_macros)]
 macro_rules! format_ws {
@@ -32,6 +47,12 @@ macro_rules! format_ws {
     }};
 }
 
+// Entering the epoch, a store into access_log followed by loading trie
+// pointers, pairs with swinging a trie pointer followed by scanning the
+// access_log in gc_epoch!(). Both sides are a store followed by a load on
+// another location, which acquire/release orderings do not order, hence
+// epoch, access_log and trie pointers stay SeqCst. Statistical counters,
+// aggregated on drop, are Relaxed.
 macro_rules! gc_epoch {
     ($log:expr, $seqno:expr) => {{
         let mut gc_epoch = u64::MAX;
@@ -64,22 +85,232 @@ macro_rules! generate_op {
 /// Map implement concurrent hash-map of key ``K`` and value ``V``.
 pub struct Map<K, V, H = DefaultHasher> {
     id: usize,
-    hash_builder: H,
+    pub(crate) hash_builder: H,
+    width: HashWidth,
     root: Arc<Root<K, V>>,
 
-    epoch: Arc<AtomicU64>,
-    access_log: Arc<Vec<AtomicU64>>,
+    epoch: Arc<Slot>,
+    access_log: Arc<Vec<Slot>>,
     map_pool: Arc<Mutex<Vec<Map<K, V, H>>>>,
+    // number of clones taken from map_pool by par_extend(), till it is done.
+    pub(crate) n_loaned: Arc<AtomicUsize>,
+    // number of handles in use, excluding the clones in map_pool.
+    n_handles: Arc<AtomicUsize>,
+    pools: Arc<Mutex<gc::Pools<K, V>>>,
+    collector: Arc<gc::Collector<K, V>>,
+    gc_thread: Option<thread::JoinHandle<()>>,
+    qsbr: bool,
+    backlog: Option<usize>,
+    backoff: Backoff,
+    // shared by all the clones, allocated on the first set_backoff() with
+    // Backoff::Lock, on any of them.
+    stripes: Arc<OnceLock<Vec<Slot>>>,
     cas: gc::Cas<K, V>,
     gc_period: usize,
     gc_count: usize,
+    epoch_period: usize,
+    epoch_count: usize,
+    pub(crate) repin_period: usize,
+    n_pins: AtomicUsize,
     n_pools: Arc<AtomicUsize>,
     n_allocs: Arc<AtomicUsize>,
     n_frees: Arc<AtomicUsize>,
+    n_retries: Arc<AtomicUsize>,
+    n_compacts: Arc<AtomicUsize>,
+    n_set_retries: Arc<AtomicUsize>,
+    n_remove_retries: Arc<AtomicUsize>,
+    n_compact_retries: Arc<AtomicUsize>,
+    latency: Arc<Mutex<gc::Latency>>,
+    pressure: Arc<Pressure>,
 }
 
+/// Number of hash bits consumed by the trie, refer to
+/// [Map::with_hash_width].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HashWidth {
+    /// 32-bit hash, the trie is 8 levels deep and keys colliding on all the
+    /// 32 bits are held in a collision list.
+    #[default]
+    Bits32,
+    /// 64-bit hash, the trie is 16 levels deep. Lower 32 bits are consumed
+    /// first, upper 32 bits extend the trie below the 8th level.
+    Bits64,
+    /// 128-bit hash, the trie is 32 levels deep. Full-hash collisions are
+    /// effectively impossible, suitable for content-addressed keys. Upper
+    /// 64 bits are computed by hashing the key a second time.
+    Bits128,
+    /// 32-bit hash, and keys colliding on all the 32 bits are re-hashed to
+    /// build up to 8 more levels below the 8th level. Only keys colliding
+    /// on both the hashes are held in a collision list. Unlike the wider
+    /// hashes, re-hash is computed only for keys stored below the 8th level.
+    Rehash,
+}
+
+/// Policy to back off between retries, when a write fails to swing the
+/// trie under contention, refer to [Map::set_backoff].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Backoff {
+    /// Retry right away.
+    #[default]
+    None,
+    /// Spin `n` times, via [hint::spin_loop], before every retry.
+    Spin(u32),
+    /// Spin twice as long on every retry, starting from a single spin, for
+    /// upto `n` retries, and yield the thread for the retries beyond. `n`
+    /// is capped to 10 retries, that is, no more than 1024 spins at a time.
+    Exponential(u32),
+    /// Yield the thread, via [thread::yield_now], before every retry.
+    Yield,
+    /// Retry right away for upto `n` retries, and for the retries beyond,
+    /// lock the subtree of the key, two levels below the root, so that
+    /// writers contending on it take turns. Readers, and writers that are
+    /// not past `n` retries, are not blocked, and locked writes still swing
+    /// the trie via CAS, hence this trades lock-freedom of contended writes
+    /// for bounded retries under adversarial access patterns.
+    Lock(u32),
+}
+
+impl Backoff {
+    // called before every attempt, attempt 0 is the first try.
+    fn snooze(&self, attempt: usize) {
+        match self {
+            _ if attempt == 0 => (),
+            Backoff::None | Backoff::Lock(_) => (),
+            Backoff::Spin(n) => (0..*n).for_each(|_| hint::spin_loop()),
+            Backoff::Exponential(n) if attempt <= (*n as usize).min(10) => {
+                (0..(1_u64 << (attempt - 1))).for_each(|_| hint::spin_loop())
+            }
+            Backoff::Exponential(_) | Backoff::Yield => thread::yield_now(),
+        }
+    }
+}
+
+// Spinlock on a stripe of the trie, held by a write till it is done,
+// refer to Backoff::Lock.
+struct Stripe {
+    stripes: Arc<OnceLock<Vec<Slot>>>,
+    off: usize,
+}
+
+impl Stripe {
+    fn lock(stripes: &Arc<OnceLock<Vec<Slot>>>, hash: u128) -> Stripe {
+        let off = (hash as usize) % STRIPES;
+        let mut spins = 0;
+        while stripes.get().expect("stripes not allocated")[off]
+            .compare_exchange_weak(0, 1, SeqCst, Relaxed)
+            .is_err()
+        {
+            spins += 1;
+            match spins {
+                1..=64 => hint::spin_loop(),
+                _ => thread::yield_now(),
+            }
+        }
+        Stripe {
+            stripes: Arc::clone(stripes),
+            off,
+        }
+    }
+}
+
+impl Drop for Stripe {
+    fn drop(&mut self) {
+        if let Some(stripes) = self.stripes.get() {
+            stripes[self.off].store(0, SeqCst)
+        }
+    }
+}
+
+impl HashWidth {
+    // number of levels consumed from the hash computed upfront.
+    fn to_levels(self) -> usize {
+        match self {
+            HashWidth::Bits32 | HashWidth::Rehash => 8,
+            HashWidth::Bits64 => 16,
+            HashWidth::Bits128 => 32,
+        }
+    }
+
+    // depth of the trie, below which colliding keys are held in a list.
+    pub(crate) fn to_depth(self) -> usize {
+        match self {
+            HashWidth::Rehash => REHASH_DEPTH,
+            width => width.to_levels(),
+        }
+    }
+}
+
+/// Collision counters, shared by all the clones of a map, refer to
+/// [Map::collision_stats]. Counters are only updated by writes.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct CollisionStats {
+    /// Length of the longest collision list seen so far. This is a high
+    /// watermark, removing keys from the list does not lower it.
+    pub max_list_len: usize,
+    /// Number of writes on keys stored below the 8th level of the trie,
+    /// that is, keys sharing all the 32 bits of their hash with other keys.
+    pub n_deep_writes: u64,
+}
+
+type PressureCallback = Box<dyn Fn(CollisionStats) + Send + Sync>;
+
+#[derive(Default)]
+struct Pressure {
+    max_list_len: AtomicUsize,
+    n_deep_writes: AtomicU64,
+    threshold: AtomicUsize,
+    callback: Mutex<Option<PressureCallback>>,
+}
+
+impl Pressure {
+    fn to_stats(&self) -> CollisionStats {
+        CollisionStats {
+            max_list_len: self.max_list_len.load(Relaxed),
+            n_deep_writes: self.n_deep_writes.load(Relaxed),
+        }
+    }
+
+    fn deep_write(&self) {
+        self.n_deep_writes.fetch_add(1, Relaxed);
+    }
+
+    fn list_len(&self, n: usize) {
+        let prev = self.max_list_len.fetch_max(n, Relaxed);
+        let threshold = self.threshold.load(Relaxed);
+        // lock is taken only when the high watermark grows.
+        if n > prev && n > threshold {
+            let callback = self.callback.lock().expect("callback lock poisoned");
+            if let Some(callb) = callback.as_ref() {
+                callb(self.to_stats())
+            }
+        }
+    }
+}
+
+// Epoch slot of an instance, padded to its own cache line, so that an
+// instance entering and exiting the epoch does not invalidate the slots of
+// other instances, refer to Map::enable_qsbr for reads without any stores.
+// Global epoch is padded as well, it is advanced by every write.
+#[derive(Debug)]
+#[repr(align(128))]
+struct Slot(AtomicU64);
+
+impl Deref for Slot {
+    type Target = AtomicU64;
+
+    fn deref(&self) -> &AtomicU64 {
+        &self.0
+    }
+}
+
+// In-node pointer is the only location that is written after a node is
+// published, trie nodes and child slots are copy-on-write. With the
+// `cache-padded` feature in-nodes are aligned to their own cache line, so
+// that writers swinging sibling in-nodes do not false-share, at the cost
+// of 64 bytes for every child slot.
+#[cfg_attr(feature = "cache-padded", repr(align(64)))]
 pub struct In<K, V> {
-    node: AtomicPtr<Node<K, V>>,
+    pub(crate) node: AtomicPtr<Node<K, V>>,
 }
 
 pub enum Node<K, V> {
@@ -95,22 +326,18 @@ pub enum Node<K, V> {
     },
 }
 
+#[derive(Default)]
 pub enum Child<K, V> {
     Deep(In<K, V>),
     Leaf(Item<K, V>),
+    #[default]
     None,
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Item<K, V> {
-    key: K,
-    value: V,
-}
-
-impl<K, V> Default for Child<K, V> {
-    fn default() -> Self {
-        Child::None
-    }
+    pub(crate) key: K,
+    pub(crate) value: V,
 }
 
 pub struct Root<K, V> {
@@ -164,8 +391,11 @@ where
         node.print(prefix)
     }
 
-    fn validate(&self, depth: usize) -> Stats {
-        unsafe { self.node.load(SeqCst).as_ref().unwrap().validate(depth + 1) }
+    fn validate(&self, depth: usize, levels: usize) -> Stats {
+        unsafe {
+            let node = self.node.load(SeqCst).as_ref().unwrap();
+            node.validate(depth + 1, levels)
+        }
     }
 
     #[cfg(test)]
@@ -181,31 +411,78 @@ where
 impl<K, V, H> Drop for Map<K, V, H> {
     fn drop(&mut self) {
         self.access_log[self.id].store(0, SeqCst);
+        // garbage of the last writes is collectable only after the epoch
+        // advances, refer to set_epoch_period().
+        self.epoch.fetch_add(1, SeqCst);
 
-        while self.cas.has_reclaims() {
-            let seqno = self.epoch.load(SeqCst);
-            let seqno = gc_epoch!(self.access_log, seqno);
-            if seqno == u64::MAX {
-                // force collect, either all clones have been dropped or there was none.
-                self.cas.garbage_collect(u64::MAX)
-            } else {
-                self.cas.garbage_collect(seqno)
-            }
-            thread::sleep(time::Duration::from_millis(10));
+        if self.gc_thread.is_some() {
+            self.stop_gc();
+        } else {
+            // garbage is collected by the background collector, if running.
+            self.collector.hand_off(&mut self.cas);
+        }
+
+        // other instances may still be reading the garbage of this instance,
+        // leave them the garbage that is not yet safe to collect, instead of
+        // waiting for their epochs to advance.
+        self.collector.take_back(&mut self.cas);
+        let seqno = gc_epoch!(self.access_log, self.epoch.load(SeqCst));
+        // u64::MAX implies that all the clones have been dropped.
+        self.cas.garbage_collect(seqno);
+        self.collector.give_back(&mut self.cas);
+
+        let last = self.n_handles.fetch_sub(1, SeqCst) == 1;
+        if last {
+            // garbage left over by the instances dropped before this one.
+            self.collector.take_back(&mut self.cas);
+            self.cas.garbage_collect(u64::MAX);
+        }
+
+        self.n_pools.fetch_add(self.cas.to_pools_len(), Relaxed);
+        self.n_allocs.fetch_add(self.cas.to_alloc_count(), Relaxed);
+        self.n_frees.fetch_add(self.cas.to_free_count(), Relaxed);
+        self.n_retries.fetch_add(self.cas.to_retry_count(), Relaxed);
+        self.n_compacts
+            .fetch_add(self.cas.to_compact_count(), Relaxed);
+        let (a, b, c) = self.cas.to_op_retry_counts();
+        self.n_set_retries.fetch_add(a, Relaxed);
+        self.n_remove_retries.fetch_add(b, Relaxed);
+        self.n_compact_retries.fetch_add(c, Relaxed);
+        self.latency
+            .lock()
+            .expect("latency lock poisoned")
+            .merge(&self.cas.to_latency());
+
+        if last {
+            // last handle, drop the clones that are not yet taken, they
+            // refer to the trie, which is freed along with its last reference.
+            // Keep the count above zero while the clones are dropped.
+            let maps = mem::take(&mut *self.map_pool.lock().expect("map lock poisoned"));
+            self.n_handles.fetch_add(maps.len() + 1, SeqCst);
+            mem::drop(maps);
+
+            #[cfg(feature = "leak-check")]
+            self.leak_report();
         }
-        self.n_pools.fetch_add(self.cas.to_pools_len(), SeqCst);
-        self.n_allocs.fetch_add(self.cas.to_alloc_count(), SeqCst);
-        self.n_frees.fetch_add(self.cas.to_free_count(), SeqCst);
     }
 }
 
 impl<K, V, H> Clone for Map<K, V, H> {
     fn clone(&self) -> Map<K, V, H> {
-        self.map_pool
-            .lock()
-            .expect("map lock poisoned")
-            .pop()
-            .unwrap()
+        let map = loop {
+            let map = self.map_pool.lock().expect("map lock poisoned").pop();
+            match map {
+                Some(map) => break map,
+                // clones on loan to par_extend() are returned once it is done.
+                None if self.n_loaned.load(SeqCst) > 0 => thread::yield_now(),
+                None => panic!("map cloned beyond its concurrency"),
+            }
+        };
+        map.n_handles.fetch_add(1, SeqCst);
+        if map.qsbr {
+            map.enter_epoch(map.epoch.load(SeqCst))
+        }
+        map
     }
 }
 
@@ -216,11 +493,34 @@ impl<K, V, H> Map<K, V, H> {
     /// If application intent to use this map in single threaded mode, supply
     /// `concurrency` as 1. Otherwise supplied level of concurrency must be equal
     /// to or greater than the number of times this intance is going to be cloned.
+    /// In single threaded mode there can be no concurrent readers, and the
+    /// memory replaced by writes is collected right away, irrespective of
+    /// [Map::set_gc_period].
     pub fn new(concurrency: usize, hash_builder: H) -> Map<K, V, H>
     where
         H: Clone,
     {
-        let mut cas = gc::Cas::new();
+        Map::with_hash_width(concurrency, hash_builder, HashWidth::default())
+    }
+
+    /// Create a new instance of map, whose trie consumes `width` bits of the
+    /// key's hash. With [HashWidth::Bits32], full-hash collisions become
+    /// common beyond a few hundred million keys, and colliding keys are kept
+    /// in linear lists. [HashWidth::Bits64] and [HashWidth::Bits128] keep
+    /// such keys in a deeper trie, at the cost of more nodes for them.
+    ///
+    /// Iteration order, scan tokens and hash prefixes are always based on
+    /// the lower 32 bits of the hash, which is the same for either width.
+    pub fn with_hash_width(
+        concurrency: usize,
+        hash_builder: H,
+        width: HashWidth,
+    ) -> Map<K, V, H>
+    where
+        H: Clone,
+    {
+        let pools = Arc::new(Mutex::new(gc::Pools::default()));
+        let mut cas = gc::Cas::new(Arc::clone(&pools));
         let root = {
             let node = cas.alloc_node('t');
             let inode = Box::new(In {
@@ -232,22 +532,43 @@ impl<K, V, H> Map<K, V, H> {
         };
 
         let mut access_log = vec![];
-        (0..concurrency).for_each(|_| access_log.push(AtomicU64::new(1)));
+        (0..concurrency).for_each(|_| access_log.push(Slot(AtomicU64::new(1))));
 
         let map = Map {
             id: 0,
             hash_builder,
+            width,
             root,
 
-            epoch: Arc::new(AtomicU64::new(1)),
+            epoch: Arc::new(Slot(AtomicU64::new(1))),
             access_log: Arc::new(access_log),
             map_pool: Arc::new(Mutex::new(vec![])),
+            n_loaned: Arc::new(AtomicUsize::new(0)),
+            n_handles: Arc::new(AtomicUsize::new(1)),
+            pools,
+            collector: Arc::new(gc::Collector::default()),
+            gc_thread: None,
+            qsbr: false,
+            backlog: None,
+            backoff: Backoff::default(),
+            stripes: Arc::new(OnceLock::new()),
             cas,
             gc_period: GC_PERIOD,
             gc_count: GC_PERIOD,
+            epoch_period: 1,
+            epoch_count: 0,
+            repin_period: iter::REPIN_PERIOD,
+            n_pins: AtomicUsize::new(0),
             n_pools: Arc::new(AtomicUsize::new(0)),
             n_allocs: Arc::new(AtomicUsize::new(0)),
             n_frees: Arc::new(AtomicUsize::new(0)),
+            n_retries: Arc::new(AtomicUsize::new(0)),
+            n_compacts: Arc::new(AtomicUsize::new(0)),
+            n_set_retries: Arc::new(AtomicUsize::new(0)),
+            n_remove_retries: Arc::new(AtomicUsize::new(0)),
+            n_compact_retries: Arc::new(AtomicUsize::new(0)),
+            latency: Arc::new(Mutex::new(gc::Latency::default())),
+            pressure: Arc::new(Pressure::default()),
         };
 
         map.clones((1..concurrency).collect());
@@ -263,22 +584,67 @@ impl<K, V, H> Map<K, V, H> {
             let map = Map {
                 id,
                 hash_builder: self.hash_builder.clone(),
+                width: self.width,
                 root: Arc::clone(&self.root),
 
                 epoch: Arc::clone(&self.epoch),
                 access_log: Arc::clone(&self.access_log),
                 map_pool: Arc::clone(&self.map_pool),
-                cas: gc::Cas::new(),
+                n_loaned: Arc::clone(&self.n_loaned),
+                n_handles: Arc::clone(&self.n_handles),
+                pools: Arc::clone(&self.pools),
+                collector: Arc::clone(&self.collector),
+                gc_thread: None,
+                qsbr: self.qsbr,
+                backlog: self.backlog,
+                backoff: self.backoff,
+                stripes: Arc::clone(&self.stripes),
+                cas: gc::Cas::new(Arc::clone(&self.pools)),
                 gc_period: self.gc_period,
                 gc_count: self.gc_count,
+                epoch_period: self.epoch_period,
+                epoch_count: 0,
+                repin_period: self.repin_period,
+                n_pins: AtomicUsize::new(0),
                 n_pools: Arc::clone(&self.n_pools),
                 n_allocs: Arc::clone(&self.n_allocs),
                 n_frees: Arc::clone(&self.n_frees),
+                n_retries: Arc::clone(&self.n_retries),
+                n_compacts: Arc::clone(&self.n_compacts),
+                n_set_retries: Arc::clone(&self.n_set_retries),
+                n_remove_retries: Arc::clone(&self.n_remove_retries),
+                n_compact_retries: Arc::clone(&self.n_compact_retries),
+                latency: Arc::clone(&self.latency),
+                pressure: Arc::clone(&self.pressure),
             };
             self.map_pool.lock().expect("map lock poisoned").push(map);
         }
     }
 
+    // Take upto `n` clones from the pool, caller shall return them back via
+    // put_clones() once done.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn take_clones(&self, n: usize) -> Vec<Map<K, V, H>> {
+        let mut pool = self.map_pool.lock().expect("map lock poisoned");
+        let n = pool.len() - usize::min(n, pool.len());
+        self.n_loaned.fetch_add(pool.len() - n, SeqCst);
+        pool.drain(n..).collect()
+    }
+
+    #[cfg(feature = "rayon")]
+    pub(crate) fn put_clones(&self, maps: Vec<Map<K, V, H>>) {
+        // clones in the pool shall not hold back garbage collection.
+        for map in maps.iter().filter(|map| map.qsbr) {
+            map.access_log[map.id].store(map.epoch.load(SeqCst), SeqCst);
+        }
+        let n = maps.len();
+        self.map_pool
+            .lock()
+            .expect("map lock poisoned")
+            .extend(maps);
+        self.n_loaned.fetch_sub(n, SeqCst);
+    }
+
     /// Data structure internally uses epoch based garbage collection for safety
     /// and optimization. Garbage collection is per map-clone/thread and for
     /// each thread gc will be triggered for every mutation operation. This can
@@ -290,6 +656,343 @@ impl<K, V, H> Map<K, V, H> {
         self
     }
 
+    /// Global epoch is advanced after every mutation, by all the clones,
+    /// making it a contended cache line under concurrent writes. By setting
+    /// this to N, this instance shall advance the epoch once for every N
+    /// mutations. Garbage is collectable only after the epoch advances,
+    /// hence a larger N delays garbage collection, holding more memory.
+    /// Default is 1.
+    pub fn set_epoch_period(&mut self, period: usize) -> &mut Self {
+        self.epoch_period = usize::max(period, 1);
+        self.epoch_count = 0;
+        self
+    }
+
+    /// Cap this instance's memory pools to `size` allocations of each kind,
+    /// default is 1024. Memory released into a full pool is handled as per
+    /// `spill`, either released to the allocator or pushed into a pool
+    /// shared by all the clones. Clones allocate from the shared pool when
+    /// their own pool is empty.
+    ///
+    /// Pooled memory is bounded by `size` for each clone, along with `n`
+    /// for the shared pool, per kind of allocation. To bound the pooled
+    /// memory map-wide, irrespective of the number of clones, use zero
+    /// sized pools with `Spill::Shared(n)`, so that idle clones do not hold
+    /// on to memory. Applies to this instance and to clones that are not
+    /// yet taken, via clone(), from this map.
+    pub fn set_pool_size(&mut self, size: usize, spill: Spill) -> &mut Self {
+        self.cas.set_pool_size(size, spill);
+        for map in self.map_pool.lock().expect("map lock poisoned").iter_mut() {
+            map.cas.set_pool_size(size, spill);
+        }
+        self
+    }
+
+    /// Switch this map to quiescent-state-based reclamation, QSBR. Reads
+    /// no longer enter and exit the epoch, instead, each instance of the
+    /// map shall periodically call [Map::quiescent], say at its event loop
+    /// boundary, and garbage is reclaimed only after all the instances in
+    /// use have passed through a quiescent state. An instance that does
+    /// not call quiescent(), or does not write, stalls garbage collection
+    /// for all the instances of the map. Call this right after creating
+    /// the map, fail if any of its clones are already taken.
+    ///
+    /// In QSBR mode, get() and other reads perform no stores to shared
+    /// memory, and quiescent() stores only to this instance's epoch slot,
+    /// that is padded to its own cache line, suitable for read dominated
+    /// workloads scaling across cores.
+    pub fn enable_qsbr(&mut self) -> Result<&mut Self> {
+        let mut pool = self.map_pool.lock().expect("map lock poisoned");
+        if pool.len() + 1 < self.access_log.len() {
+            let n = self.access_log.len() - pool.len() - 1;
+            return err_at!(InvalidInput, msg: "{} clones are already taken", n);
+        }
+        pool.iter_mut().for_each(|map| map.qsbr = true);
+        mem::drop(pool);
+
+        self.qsbr = true;
+        self.quiescent();
+        Ok(self)
+    }
+
+    /// Declare a quiescent state for this instance, in QSBR mode, refer to
+    /// [Map::enable_qsbr]. Garbage, that is no longer reachable from the
+    /// map, prior to this call, could be reclaimed once all the other
+    /// instances have passed through a quiescent state. No-op otherwise.
+    pub fn quiescent(&mut self) {
+        if self.qsbr {
+            self.enter_epoch(self.epoch.load(SeqCst))
+        }
+    }
+
+    /// Spawn a background thread that collects garbage, on behalf of this
+    /// map and all its clones, every `interval`. While the collector is
+    /// running, mutations hand over their garbage to the collector instead
+    /// of collecting it inline, keeping the write latency flat. Memory
+    /// collected is handled as per this instance's spill policy, refer to
+    /// [Map::set_pool_size]. Collector runs until [Map::stop_gc] is called
+    /// on this instance, or this instance is dropped. Ignored if a collector
+    /// is already running for this map.
+    pub fn spawn_gc(&mut self, interval: time::Duration) -> &mut Self
+    where
+        K: 'static + Send,
+        V: 'static + Send,
+    {
+        if !self.collector.start() {
+            return self;
+        }
+
+        let collector = Arc::clone(&self.collector);
+        let access_log = Arc::clone(&self.access_log);
+        let epoch = Arc::clone(&self.epoch);
+        let n_frees = Arc::clone(&self.n_frees);
+        let latency = Arc::clone(&self.latency);
+        let mut cas = gc::Cas::new(Arc::clone(&self.pools));
+        cas.set_pool_size(0, self.cas.to_spill());
+        cas.set_dropper(self.cas.to_dropper());
+
+        let handle = thread::spawn(move || {
+            let mut n_published = 0;
+            loop {
+                let running = collector.is_running();
+
+                epoch.fetch_add(1, SeqCst);
+                let seqno = gc_epoch!(access_log, epoch.load(SeqCst));
+                collector.take_back(&mut cas);
+                // u64::MAX implies that all the clones have been dropped.
+                cas.garbage_collect(seqno);
+                collector.give_back(&mut cas);
+
+                let n = cas.to_free_count();
+                n_frees.fetch_add(n - n_published, Relaxed);
+                n_published = n;
+                let val = cas.take_latency();
+                latency.lock().expect("latency lock poisoned").merge(&val);
+
+                if !running {
+                    break;
+                }
+                thread::park_timeout(interval);
+            }
+        });
+        self.gc_thread = Some(handle);
+        self
+    }
+
+    /// Spawn a destructor thread, to drop the items reclaimed by garbage
+    /// collection, like removed and replaced entries, so that writes do not
+    /// pay for expensive destructors of keys and values. Applies to this
+    /// instance, to clones that are not yet taken, via clone(), from this
+    /// map, and to the background collector spawned after this call.
+    pub fn spawn_destructor(&mut self) -> &mut Self
+    where
+        K: 'static + Send,
+        V: 'static + Send,
+    {
+        if self.cas.to_dropper().is_some() {
+            return self;
+        }
+
+        let (tx, rx) = mpsc::channel::<Vec<Item<K, V>>>();
+        thread::spawn(move || {
+            for items in rx.iter() {
+                mem::drop(items)
+            }
+        });
+        self.cas.set_dropper(Some(tx.clone()));
+        for map in self.map_pool.lock().expect("map lock poisoned").iter_mut() {
+            map.cas.set_dropper(Some(tx.clone()));
+        }
+        self
+    }
+
+    /// Stop the background collector spawned by this instance, if any, and
+    /// wait for it to exit. Subsequently, garbage is collected inline by
+    /// each clone of the map.
+    pub fn stop_gc(&mut self) -> &mut Self {
+        if let Some(handle) = self.gc_thread.take() {
+            self.collector.stop();
+            handle.thread().unpark();
+            handle.join().expect("gc thread panicked");
+            // garbage left over by the collector is collected inline.
+            self.collector.take_back(&mut self.cas);
+        }
+        self
+    }
+
+    /// Limit the number of writes pending garbage collection, held by this
+    /// instance along with the ones handed over to the background
+    /// collector, if running. A reader pinning an old epoch for long would otherwise
+    /// grow the backlog without bound. Once the limit is reached, writes
+    /// help collecting the garbage and wait until the backlog is below the
+    /// limit, while [Map::try_set] and [Map::try_remove] fail with
+    /// [Error::Backpressure]. Default is no limit. A `limit` of 0 is
+    /// treated as 1, a write is pending collection till its epoch is over.
+    pub fn set_backlog(&mut self, limit: usize) -> &mut Self {
+        self.backlog = Some(limit.max(1));
+        self
+    }
+
+    /// Set the policy to back off between retries, when writes conflict on
+    /// the same subtree, refer to [Backoff]. Applies to this instance and to
+    /// clones that are not yet taken, via clone(), from this map. Default is
+    /// to retry right away.
+    pub fn set_backoff(&mut self, backoff: Backoff) -> &mut Self {
+        if matches!(backoff, Backoff::Lock(_)) {
+            self.stripes
+                .get_or_init(|| (0..STRIPES).map(|_| Slot(AtomicU64::new(0))).collect());
+        }
+        self.backoff = backoff;
+        for map in self.map_pool.lock().expect("map lock poisoned").iter_mut() {
+            map.backoff = backoff;
+        }
+        self
+    }
+
+    // called before every attempt, attempt 0 is the first try. With
+    // Backoff::Lock, attempts beyond the limit hold the key's stripe till
+    // `lock` is dropped.
+    fn snooze(&self, attempt: usize, hash: u128, lock: &mut Option<Stripe>) {
+        match self.backoff {
+            Backoff::Lock(n) if attempt > (n as usize) => {
+                if lock.is_none() {
+                    *lock = Some(Stripe::lock(&self.stripes, hash))
+                }
+            }
+            backoff => backoff.snooze(attempt),
+        }
+    }
+
+    /// Pre-populate this instance's memory pools, so that a burst of `n`
+    /// mutations need not pay allocation latency. Pools are maintained for
+    /// each clone of the map, call this method on each of the clones that
+    /// is going to handle the burst. Note that pools are capped to a maximum
+    /// size, so `n` beyond that will be ignored.
+    pub fn reserve(&mut self, n: usize) -> &mut Self {
+        self.cas.reserve(n);
+        self
+    }
+
+    // Consume this instance and return all its items. If this is the only
+    // instance of the map in use, that is all its clones are dropped, items
+    // are moved out of the trie, otherwise they are cloned. Clones in the
+    // pool hold the root but are not in use.
+    pub(crate) fn into_items(mut self) -> Vec<Item<K, V>>
+    where
+        K: Clone + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        let exclusive = {
+            let pool = self.map_pool.lock().expect("map lock poisoned");
+            Arc::strong_count(&self.root) == pool.len() + 1
+        };
+        if !exclusive {
+            return self.iter().map(Item::from).collect();
+        }
+
+        let inode: &In<K, V> = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+        let node = Box::leak(self.cas.alloc_node('t'));
+        let mut items = vec![];
+        let n = Node::dismantle(inode.node.swap(node, SeqCst), &mut items);
+        self.cas.count_frees(n);
+        items
+    }
+
+    /// Return a reference to the map's [BuildHasher].
+    pub fn hasher(&self) -> &H {
+        &self.hash_builder
+    }
+
+    /// Return the number of hash bits consumed by the trie.
+    pub fn hash_width(&self) -> HashWidth {
+        self.width
+    }
+
+    /// Return garbage collection and operation statistics. Counters are
+    /// accumulated by this instance along with all its dropped clones,
+    /// while pool occupancy and pending reclaims are of this instance, and
+    /// the shared pool and background collector, if any.
+    pub fn gc_stats(&self) -> GcStats {
+        let (n_reclaims, n_garbage) = {
+            let (a, b) = self.cas.to_reclaim_counts();
+            let (x, y) = self.collector.to_reclaim_counts();
+            (a + x, b + y)
+        };
+        let latency = {
+            let mut latency = *self.latency.lock().expect("latency lock poisoned");
+            latency.merge(&self.cas.to_latency());
+            latency
+        };
+        let (a, b, c) = self.cas.to_op_retry_counts();
+        GcStats {
+            n_retries: self.n_retries.load(Relaxed) + self.cas.to_retry_count(),
+            n_compacts: self.n_compacts.load(Relaxed) + self.cas.to_compact_count(),
+            n_set_retries: self.n_set_retries.load(Relaxed) + a,
+            n_remove_retries: self.n_remove_retries.load(Relaxed) + b,
+            n_compact_retries: self.n_compact_retries.load(Relaxed) + c,
+            n_allocs: self.n_allocs.load(Relaxed) + self.cas.to_alloc_count(),
+            n_frees: self.n_frees.load(Relaxed) + self.cas.to_free_count(),
+            n_pooled: self.cas.to_pooled_count() + self.cas.to_shared_pooled_count(),
+            n_reclaims,
+            n_garbage,
+            gc_latency: latency.gc_pass,
+            reclaim_age: latency.reclaim_age,
+        }
+    }
+
+    /// Return the bytes held by this map, as computed from the size of its
+    /// trie nodes, child slots and items. `size_of` shall return the bytes
+    /// held on the heap by an entry's key and value, if any, say the
+    /// capacity of a `String` key, return 0 otherwise. Pools and pending
+    /// reclaims are of this instance, the shared pool and the background
+    /// collector, if any. Note that this walks the entire trie.
+    pub fn footprint<F>(&self, size_of: F) -> Footprint
+    where
+        F: Fn(&K, &V) -> usize,
+    {
+        let mut fp = Footprint {
+            trie_bytes: mem::size_of::<In<K, V>>(),
+            pool_bytes: self.cas.to_pool_bytes(),
+            reclaim_bytes: self.cas.to_reclaim_bytes()
+                + self.collector.to_reclaim_bytes(),
+            ..Footprint::default()
+        };
+
+        let seqno = self.pin_epoch();
+        self.as_root_node().footprint(&mut fp, &size_of);
+        self.unpin_epoch(seqno);
+
+        fp
+    }
+
+    /// Return the collision counters for this map. Long collision lists, or
+    /// a steady rise in deep writes, for a keyed hasher like
+    /// [DefaultHasher], could be a sign of hash-flooding.
+    pub fn collision_stats(&self) -> CollisionStats {
+        self.pressure.to_stats()
+    }
+
+    /// Call `callb` every time the longest collision list grows beyond
+    /// `threshold` keys. Callback is shared by all the clones of this map
+    /// and replaces the previous callback, if any. It is called while
+    /// holding a lock, from the thread inserting the key, hence it shall
+    /// return quickly and shall not write into this map.
+    pub fn on_collision_pressure<F>(&mut self, threshold: usize, callb: F) -> &mut Self
+    where
+        F: 'static + Fn(CollisionStats) + Send + Sync,
+    {
+        let mut callback = self
+            .pressure
+            .callback
+            .lock()
+            .expect("callback lock poisoned");
+        *callback = Some(Box::new(callb));
+        self.pressure.threshold.store(threshold, Relaxed);
+        mem::drop(callback);
+        self
+    }
+
     /// Return the number of items indexed in the map. This may not be accurate due
     /// to concurrent writes. Note that this is a costly operation walking through
     /// the entire map.
@@ -299,9 +1002,15 @@ impl<K, V, H> Map<K, V, H> {
         node.count()
     }
 
-    /// Return whether map is empty
+    /// Return whether map is empty. Unlike len(), this is a cheap operation
+    /// that stops at the first entry. Tomb nodes and empty children, left in
+    /// the trie by removes till they are compacted, are treated as empty.
+    /// Like len(), this may not be accurate due to concurrent writes.
     pub fn is_empty(&self) -> bool {
-        self.len() == 0
+        let seqno = self.pin_epoch();
+        let res = iter::Walker::new(self.as_root_node()).next().is_none();
+        self.unpin_epoch(seqno);
+        res
     }
 }
 
@@ -312,25 +1021,33 @@ where
     H: Clone,
 {
     /// Call this method after all other concurrnet instances have been
-    /// dropped.
+    /// dropped, and the background collector, if any, is stopped.
     ///
     /// * There shall be no tomb-nodes.
     /// * There shall be no empty trie-nodes that is not root.
     /// * There shall be no trie-nodes with childs.len() > 16.
     /// * There shall be no list-node with items.len() < 2
-    /// * All list-nodes must be at 9th level.
+    /// * All list-nodes must be at the last level, 9th level for 32-bit hash,
+    ///   17th level for 64-bit hash and re-hash, 33rd level for 128-bit hash.
     pub fn validate(&self) -> Stats {
-        let mut stats = unsafe { self.root.load(SeqCst).as_ref().unwrap().validate(0) };
-        stats.n_pools = self.n_pools.load(SeqCst) + self.cas.to_pools_len();
-        stats.n_allocs = self.n_allocs.load(SeqCst) + self.cas.to_alloc_count();
-        stats.n_frees = self.n_frees.load(SeqCst) + self.cas.to_free_count();
+        let levels = self.width.to_depth();
+        let root = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+        let mut stats = root.validate(0, levels);
+        let (n_reclaims, n_items) = self.collector.to_reclaim_counts();
+        stats.n_pools = self.n_pools.load(Relaxed)
+            + self.cas.to_pools_len()
+            + self.cas.to_shared_pooled_count()
+            + n_reclaims
+            + n_items;
+        stats.n_allocs = self.n_allocs.load(Relaxed) + self.cas.to_alloc_count();
+        stats.n_frees = self.n_frees.load(Relaxed) + self.cas.to_free_count();
 
         self.cas.validate();
 
         {
             let mem_count = stats.n_allocs - stats.n_frees;
             let alg_count = stats.n_nodes + stats.n_childs + stats.n_pools;
-            debug_assert!(
+            paranoid_assert!(
                 mem_count == alg_count,
                 "mem_count:{} alg_count:{}",
                 mem_count,
@@ -338,7 +1055,7 @@ where
             );
         }
 
-        debug_assert!(
+        paranoid_assert!(
             stats.n_tombs == 0,
             "unexpected tomb nodes {}",
             stats.n_tombs
@@ -476,17 +1193,16 @@ impl<K, V> Node<K, V> {
         }
     }
 
-    fn as_value<'a, Q>(&'a self, key: &Q) -> Option<&'a V>
+    fn as_item<'a, Q>(&'a self, key: &Q) -> Option<&'a Item<K, V>>
     where
-        K: Borrow<Q>,
-        Q: PartialEq + Hash + ?Sized,
+        Q: Equivalent<K> + ?Sized,
     {
         match self {
-            Node::List { items } => get_from_list(key, items),
+            Node::List { items } => items.iter().find(|x| key.equivalent(&x.key)),
             Node::Tomb { item } => match item {
                 Some(item) => {
-                    if item.key.borrow() == key {
-                        Some(&item.value)
+                    if key.equivalent(&item.key) {
+                        Some(item)
                     } else {
                         None
                     }
@@ -519,6 +1235,118 @@ impl<K, V> Node<K, V> {
     }
 }
 
+impl<K, V> Child<K, V> {
+    fn dismantle(child: *mut Child<K, V>, items: &mut Vec<Item<K, V>>) -> usize {
+        let child = unsafe { Box::from_raw(child) };
+        match *child {
+            Child::Leaf(item) => items.push(item),
+            Child::Deep(inode) => {
+                return 1 + Node::dismantle(inode.node.into_inner(), items)
+            }
+            Child::None => unreachable!(),
+        }
+        1
+    }
+}
+
+impl<K, V> Node<K, V> {
+    // Move out all the items under `node`, freeing the nodes, return the
+    // number of allocations freed.
+    fn dismantle(node: *mut Node<K, V>, items: &mut Vec<Item<K, V>>) -> usize {
+        let node = unsafe { Box::from_raw(node) };
+        match *node {
+            Node::Trie { childs, .. } => {
+                let n: usize = childs
+                    .into_iter()
+                    .map(|child| Child::dismantle(child.into_inner(), items))
+                    .sum();
+                n + 1
+            }
+            Node::Tomb { item } => {
+                items.extend(item);
+                1
+            }
+            Node::List { items: list } => {
+                items.extend(list);
+                1
+            }
+        }
+    }
+}
+
+impl<K, V> Node<K, V> {
+    // Number of allocations held by this node, its child slots and the
+    // nodes below them.
+    #[cfg(feature = "leak-check")]
+    fn to_alloc_count(&self) -> usize {
+        match self {
+            Node::Trie { childs, .. } => {
+                let mut n = 1 + childs.len();
+                for child in childs {
+                    if let Child::Deep(inode) =
+                        unsafe { child.load(SeqCst).as_ref().unwrap() }
+                    {
+                        n += unsafe { inode.node.load(SeqCst).as_ref().unwrap() }
+                            .to_alloc_count()
+                    }
+                }
+                n
+            }
+            Node::Tomb { .. } | Node::List { .. } => 1,
+        }
+    }
+
+    // Bytes held by this node, excluding its child slots and the memory
+    // held by keys and values on the heap.
+    pub(crate) fn to_node_bytes(&self) -> usize {
+        let n = match self {
+            Node::Trie { childs, .. } => {
+                childs.capacity() * mem::size_of::<AtomicPtr<Child<K, V>>>()
+            }
+            Node::Tomb { .. } => 0,
+            Node::List { items } => items.capacity() * mem::size_of::<Item<K, V>>(),
+        };
+        mem::size_of::<Node<K, V>>() + n
+    }
+
+    fn footprint<F>(&self, fp: &mut Footprint, size_of: &F)
+    where
+        F: Fn(&K, &V) -> usize,
+    {
+        match self {
+            Node::Trie { childs, .. } => {
+                fp.trie_bytes += self.to_node_bytes();
+                fp.child_bytes += childs.len() * mem::size_of::<Child<K, V>>();
+                for child in childs {
+                    match unsafe { child.load(SeqCst).as_ref().unwrap() } {
+                        Child::Leaf(item) => {
+                            fp.heap_bytes += size_of(&item.key, &item.value)
+                        }
+                        Child::Deep(inode) => {
+                            let node =
+                                unsafe { inode.node.load(SeqCst).as_ref().unwrap() };
+                            node.footprint(fp, size_of)
+                        }
+                        Child::None => (),
+                    }
+                }
+            }
+            Node::Tomb { item } => {
+                fp.trie_bytes += self.to_node_bytes();
+                if let Some(item) = item {
+                    fp.heap_bytes += size_of(&item.key, &item.value)
+                }
+            }
+            Node::List { items } => {
+                fp.list_bytes += self.to_node_bytes();
+                for item in items.iter() {
+                    fp.heap_bytes += size_of(&item.key, &item.value)
+                }
+            }
+        }
+    }
+}
+
 impl<K, V> Node<K, V> {
     fn dropped(node: *mut Node<K, V>) {
         let node = unsafe { Box::from_raw(node) };
@@ -580,7 +1408,7 @@ where
     }
 
     // make sure that Node::List are only at the last level.
-    fn validate(&self, depth: usize) -> Stats {
+    fn validate(&self, depth: usize, levels: usize) -> Stats {
         let mut stats = Stats::default();
         stats.n_nodes += 1;
 
@@ -590,9 +1418,9 @@ where
             Node::Trie { childs, .. } => {
                 let nc = childs.len();
                 if depth > 1 {
-                    debug_assert!(nc > 0, "unexpected node.trie n:{}", nc);
+                    paranoid_assert!(nc > 0, "unexpected node.trie n:{}", nc);
                 } else {
-                    debug_assert!(nc <= 16, "unexpected node.trie n:{}", nc);
+                    paranoid_assert!(nc <= 16, "unexpected node.trie n:{}", nc);
                 }
                 stats.n_childs += childs.len();
 
@@ -604,14 +1432,24 @@ where
                 for child in childs {
                     match unsafe { child.load(SeqCst).as_ref().unwrap() } {
                         Child::Leaf(_) => stats.n_items += 1,
-                        Child::Deep(inode) => stats = stats + inode.validate(depth),
+                        Child::Deep(inode) => {
+                            stats = stats + inode.validate(depth, levels)
+                        }
                         Child::None => unreachable!(),
                     }
                 }
             }
             Node::List { items } => {
-                debug_assert!(items.len() > 1, "unexpected node.list n:{}", items.len());
-                debug_assert!(depth == 9, "unexpected node.list depth:{}", depth);
+                paranoid_assert!(
+                    items.len() > 1,
+                    "unexpected node.list n:{}",
+                    items.len()
+                );
+                paranoid_assert!(
+                    depth == levels + 1,
+                    "unexpected node.list depth:{}",
+                    depth
+                );
                 stats.n_lists += 1;
                 stats.n_items += items.len();
                 stats.n_mem += items.capacity() * mem::size_of::<Item<K, V>>();
@@ -666,7 +1504,7 @@ where
                 unsafe { items.set_len(2) }; // **IMPORTANT**
                 items[0] = item;
                 items[1].clone_from(leaf);
-                #[cfg(freature = "compact")]
+                #[cfg(feature = "compact")]
                 items.shrink_to_fit();
             }
             _ => unreachable!(),
@@ -782,8 +1620,8 @@ where
                 match node.as_mut() {
                     Node::Trie { bmp, childs } => {
                         let n = match hamming_distance(*w2, *bmp) {
-                            Distance::Insert(n) => n,
-                            Distance::Set(_) => unreachable!(),
+                            (false, n) => n,
+                            (true, _) => unreachable!(),
                         };
                         let leaf = leaf.clone();
                         childs.insert(n, AtomicPtr::new(Child::new_leaf(leaf, op.cas)));
@@ -822,13 +1660,13 @@ where
         }
     }
 
-    fn update_list(key: &K, value: &V, op: CasOp<K, V>) -> CasRc<Option<V>>
+    fn update_list(key: &K, value: V, op: CasOp<K, V>) -> CasRc<Option<V>>
     where
         K: PartialEq,
     {
         match unsafe { op.old.as_ref().unwrap() } {
             Node::List { items: olds } => {
-                let item: Item<K, V> = (key.clone(), value.clone()).into();
+                let item: Item<K, V> = (key.clone(), value).into();
                 let (new, old_value) = Node::new_list_with(olds, item, op.cas);
 
                 op.cas.free_on_pass(gc::Mem::Node(op.old));
@@ -898,24 +1736,104 @@ where
         }
     }
 
-    fn leaf_to_list(key: K, value: &V, n: usize, op: CasOp<K, V>) -> CasRc<()> {
-        // convert a child node holding a leaf, into a interm-node pointing to node-list
-        let old_child_ptr = match unsafe { op.old.as_ref().unwrap() } {
-            Node::Trie { childs, .. } => childs[n].load(SeqCst),
-            _ => unreachable!(),
-        };
-        let old_child = unsafe { old_child_ptr.as_ref().unwrap() };
+    // Replace the leaves at `n` with their new items, in a single copy of
+    // the trie node, refer to Map::for_each_mut.
+    fn set_leaves(
+        leaves: Vec<(usize, Item<K, V>)>,
+        op: CasOp<K, V>,
+    ) -> CasRc<*mut Node<K, V>> {
+        let old = unsafe { op.old.as_ref().unwrap() };
 
         let mut node = op.cas.alloc_node('t');
-        node.trie_copy_from(unsafe { op.old.as_ref().unwrap() });
+        node.trie_copy_from(old);
 
-        match node.as_mut() {
-            Node::Trie { childs, .. } => {
-                let new_child_ptr = match old_child {
-                    Child::Leaf(leaf) => {
-                        let item: Item<K, V> = (key, value.clone()).into();
-                        let node = Node::new_bi_list(item, leaf, op.cas);
-                        Child::new_deep(node, op.cas)
+        for (n, leaf) in leaves.into_iter() {
+            let old_child_ptr = match old {
+                Node::Trie { childs, .. } => childs[n].load(SeqCst),
+                _ => unreachable!(),
+            };
+            let new_child_ptr = Child::new_leaf(leaf, op.cas);
+            match node.as_mut() {
+                Node::Trie { childs, .. } => childs[n] = AtomicPtr::new(new_child_ptr),
+                _ => unreachable!(),
+            }
+            op.cas.free_on_pass(gc::Mem::Child(old_child_ptr));
+        }
+
+        let new = Box::leak(node);
+
+        op.cas.free_on_fail(gc::Mem::Node(new));
+        op.cas.free_on_pass(gc::Mem::Node(op.old));
+        if op.cas.swing(op.epoch, &op.inode.node, op.old, new) {
+            CasRc::Ok(new)
+        } else {
+            CasRc::Retry
+        }
+    }
+
+    // Collect the keys of all the entries under this node.
+    fn collect_keys(&self, keys: &mut Vec<K>) {
+        match self {
+            Node::Trie { childs, .. } => {
+                for child in childs.iter() {
+                    match unsafe { child.load(SeqCst).as_ref().unwrap() } {
+                        Child::Leaf(item) => keys.push(item.key.clone()),
+                        Child::Deep(inode) => {
+                            let node =
+                                unsafe { inode.node.load(SeqCst).as_ref().unwrap() };
+                            node.collect_keys(keys)
+                        }
+                        Child::None => unreachable!(),
+                    }
+                }
+            }
+            Node::List { items } => {
+                keys.extend(items.iter().map(|item| item.key.clone()))
+            }
+            Node::Tomb { item } => keys.extend(item.iter().map(|item| item.key.clone())),
+        }
+    }
+
+    // Replace the collision list with `items`, refer to Map::for_each_mut.
+    fn set_list(items: Vec<Item<K, V>>, op: CasOp<K, V>) -> CasRc<()> {
+        let mut node = op.cas.alloc_node('l');
+        match node.as_mut() {
+            Node::List { items: new_items } => {
+                new_items.clear();
+                new_items.extend(items);
+            }
+            _ => unreachable!(),
+        }
+
+        let new = Box::leak(node);
+
+        op.cas.free_on_fail(gc::Mem::Node(new));
+        op.cas.free_on_pass(gc::Mem::Node(op.old));
+        if op.cas.swing(op.epoch, &op.inode.node, op.old, new) {
+            CasRc::Ok(())
+        } else {
+            CasRc::Retry
+        }
+    }
+
+    fn leaf_to_list(key: K, value: V, n: usize, op: CasOp<K, V>) -> CasRc<()> {
+        // convert a child node holding a leaf, into a interm-node pointing to node-list
+        let old_child_ptr = match unsafe { op.old.as_ref().unwrap() } {
+            Node::Trie { childs, .. } => childs[n].load(SeqCst),
+            _ => unreachable!(),
+        };
+        let old_child = unsafe { old_child_ptr.as_ref().unwrap() };
+
+        let mut node = op.cas.alloc_node('t');
+        node.trie_copy_from(unsafe { op.old.as_ref().unwrap() });
+
+        match node.as_mut() {
+            Node::Trie { childs, .. } => {
+                let new_child_ptr = match old_child {
+                    Child::Leaf(leaf) => {
+                        let item: Item<K, V> = (key, value).into();
+                        let node = Node::new_bi_list(item, leaf, op.cas);
+                        Child::new_deep(node, op.cas)
                     }
                     Child::Deep(_) => unreachable!(),
                     Child::None => unreachable!(),
@@ -1146,94 +2064,352 @@ where
             (compact, CasRc::Retry)
         }
     }
+
+    // replace trie-node or list-node, holding more memory than it needs,
+    // with a compact copy.
+    fn shrink_node(op: CasOp<K, V>) -> CasRc<()> {
+        let mut node = match unsafe { op.old.as_ref().unwrap() } {
+            Node::Trie { childs, .. } if childs.capacity() > childs.len() => {
+                let mut node = op.cas.alloc_node('t');
+                node.trie_copy_from(unsafe { op.old.as_ref().unwrap() });
+                node
+            }
+            Node::List { items: olds } if olds.capacity() > olds.len() => {
+                let mut node = op.cas.alloc_node('l');
+                match node.as_mut() {
+                    Node::List { items } => {
+                        items.clear();
+                        items.extend_from_slice(olds);
+                    }
+                    _ => unreachable!(),
+                }
+                node
+            }
+            Node::Trie { .. } | Node::List { .. } | Node::Tomb { .. } => {
+                return CasRc::Ok(());
+            }
+        };
+        match node.as_mut() {
+            Node::Trie { childs, .. } => childs.shrink_to_fit(),
+            Node::List { items } => items.shrink_to_fit(),
+            Node::Tomb { .. } => unreachable!(),
+        }
+
+        let new = Box::leak(node);
+
+        op.cas.free_on_fail(gc::Mem::Node(new));
+        op.cas.free_on_pass(gc::Mem::Node(op.old));
+        if op.cas.swing(op.epoch, &op.inode.node, op.old, new) {
+            CasRc::Ok(())
+        } else {
+            CasRc::Retry
+        }
+    }
 }
 
 impl<K, V, H> Map<K, V, H> {
+    pub(crate) fn to_hash<Q>(&self, key: &Q) -> u32
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        key_to_hash32(key, self.hash_builder.build_hasher())
+    }
+
+    /// Return the hash for `key`, as computed by this map's [BuildHasher].
+    /// Same as `self.hasher().hash_one(key)`, use this with the
+    /// `*_hashed()` methods.
+    pub fn hash_key<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.hash_builder.hash_one(key)
+    }
+
+    // Return the hash consumed by the trie, whose lower 32 bits are same as
+    // that of to_hash().
+    pub(crate) fn to_trie_hash<Q>(&self, key: &Q) -> u128
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.to_trie_hash_from(self.hash_key(key), key)
+    }
+
+    // Same as to_trie_hash(), from the hash already computed for `key`.
+    pub(crate) fn to_trie_hash_from<Q>(&self, code: u64, key: &Q) -> u128
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        match self.width {
+            HashWidth::Bits32 | HashWidth::Rehash => fold_hash32(code) as u128,
+            HashWidth::Bits64 => fold_hash64(code) as u128,
+            HashWidth::Bits128 => {
+                let upper = key_to_upper64(key, self.hash_builder.build_hasher());
+                ((upper as u128) << 64) | (fold_hash64(code) as u128)
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn to_slots(&self, hash: u128) -> ([u8; MAX_LEVELS], usize) {
+        (slots128(hash), self.width.to_levels())
+    }
+
+    // Whether a key can be re-hashed on exhausting the hash, `deep` shall be
+    // true if the key was already re-hashed for this walk.
+    #[inline]
+    fn can_rehash(&self, deep: bool) -> bool {
+        self.width == HashWidth::Rehash && !deep
+    }
+
+    // With HashWidth::Rehash, return the slots below the 8th level, by
+    // re-hashing `key`, only the first time for an operation.
+    pub(crate) fn rehash_slots<'a, Q>(
+        &self,
+        key: &Q,
+        ws: &'a mut [u8; MAX_LEVELS],
+        rehashed: &mut bool,
+    ) -> &'a [u8]
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        paranoid_assert!(self.width == HashWidth::Rehash, "{:?}", self.width);
+        let levels = self.width.to_levels();
+        if !*rehashed {
+            let hash = key_to_rehash32(key, self.hash_builder.build_hasher());
+            ws[levels..REHASH_DEPTH].copy_from_slice(&slots(hash));
+            *rehashed = true;
+        }
+        &ws[levels..REHASH_DEPTH]
+    }
+
     pub fn get<Q>(&self, key: &Q) -> Option<V>
     where
-        K: Borrow<Q>,
         V: Clone,
-        Q: PartialEq + Hash + ?Sized,
+        Q: Equivalent<K> + Hash + ?Sized,
         H: BuildHasher,
     {
-        let seqno = self.epoch.load(SeqCst);
-        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+        self.get_hashed(self.hash_key(key), key)
+    }
 
-        let ws = {
-            let hasher = self.hash_builder.build_hasher();
-            slots(key_to_hash32(key, hasher))
-        };
-        let mut inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
-        let mut wss = &ws[..];
-        // println!("{}", format_ws!("get outer ws:{:?}", wss));
+    /// Same as [Map::get], with `hash` already computed for `key` by
+    /// [Map::hash_key], say for routing the key across shards. Supplying
+    /// a hash that is not computed from `key` would fail to locate it.
+    /// With [HashWidth::Bits128] upper 64 bits of the hash, and with
+    /// [HashWidth::Rehash] the re-hash, are still computed from `key`.
+    pub fn get_hashed<Q>(&self, hash: u64, key: &Q) -> Option<V>
+    where
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let seqno = self.pin_epoch();
+        let res = self.lookup(key, self.to_trie_hash_from(hash, key)).cloned();
+        self.unpin_epoch(seqno);
+        res
+    }
 
-        let res = loop {
-            let old = inode.node.load(SeqCst);
-            let node = unsafe { old.as_ref().unwrap() };
+    /// Same as [Map::get], return the key as stored in the map along with
+    /// its value. Useful when lookups are done with an equivalent key,
+    /// refer to [Equivalent].
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let seqno = self.pin_epoch();
+        let res = self
+            .lookup_item(key, self.to_trie_hash(key))
+            .map(|item| (item.key.clone(), item.value.clone()));
+        self.unpin_epoch(seqno);
+        res
+    }
 
-            let w = match wss.first() {
-                Some(w) => *w,
-                None => break node.as_value(key).cloned(),
-            };
-            wss = &wss[1..];
-            // println!("get loop w:{:x}", w);
+    pub fn get_with<Q, F, T>(&self, key: &Q, callb: F) -> Option<T>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+        F: Fn(&V) -> T,
+    {
+        self.do_get_with(key, self.to_trie_hash(key), callb)
+    }
 
-            inode = match node {
-                Node::Trie { bmp, childs } => {
-                    let hd = hamming_distance(w, *bmp);
-                    // println!("get loop bmp:{:x} {:?}", bmp, hd);
-                    match hd {
-                        Distance::Insert(_) => break None,
-                        Distance::Set(n) => {
-                            let ptr = childs[n].load(SeqCst);
-                            match unsafe { ptr.as_ref().unwrap() } {
-                                Child::Deep(next_inode) => next_inode,
-                                Child::Leaf(item) if item.key.borrow() == key => {
-                                    break Some(item.value.clone());
-                                }
-                                Child::Leaf(_) => break None,
-                                Child::None => unreachable!(),
-                            }
-                        }
+    /// Get the value for `key` without cloning it. The returned guard keep
+    /// this instance pinned to the current epoch until it is dropped, so
+    /// that the value is not reclaimed while being used. Writers are not
+    /// blocked, but garbage collection is held back for all instances of
+    /// this map, hence hold on to the guard only as long as necessary.
+    pub fn get_and_pin<Q>(&self, key: &Q) -> Option<Pinned<'_, K, V, H>>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let since = time::Instant::now();
+        let seqno = self.pin_epoch();
+        match self.lookup(key, self.to_trie_hash(key)) {
+            Some(value) => Some(Pinned {
+                map: self,
+                value,
+                seqno,
+                since,
+            }),
+            None => {
+                self.unpin_epoch(seqno);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn do_get_with<Q, F, T>(&self, key: &Q, hash: u128, callb: F) -> Option<T>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+        F: Fn(&V) -> T,
+    {
+        let seqno = self.pin_epoch();
+        let res = self.lookup(key, hash).map(callb);
+        self.unpin_epoch(seqno);
+        res
+    }
+
+    // Enter epoch for read-only access. Pins can nest, say a get() while a
+    // [Pinned] guard is alive, and this instance shall exit the epoch only
+    // when the outer most pin is unpinned. Map is Sync, and shared readers,
+    // like par_iter(), pin the same instance from several threads, hence
+    // the first pin and the last unpin hold PIN_LOCK in the pin count while
+    // they store into the epoch slot, and other pins wait for them.
+    #[inline]
+    pub(crate) fn pin_epoch(&self) -> u64 {
+        if self.qsbr {
+            return 0;
+        }
+        let seqno = self.epoch.load(SeqCst);
+        loop {
+            match self.n_pins.load(SeqCst) {
+                0 => {
+                    if self.swap_pins(0, PIN_LOCK) {
+                        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+                        self.n_pins.store(1, SeqCst);
+                        break seqno;
                     }
                 }
-                Node::List { .. } => unreachable!(),
-                Node::Tomb { item } => match item {
-                    Some(m) if m.key.borrow() == key => break Some(m.value.clone()),
-                    _ => break None,
-                },
+                n if n & PIN_LOCK == 0 => {
+                    if self.swap_pins(n, n + 1) {
+                        break seqno;
+                    }
+                }
+                _ => hint::spin_loop(),
             }
-        };
+        }
+    }
+
+    #[inline]
+    pub(crate) fn unpin_epoch(&self, seqno: u64) {
+        if self.qsbr {
+            return;
+        }
+        loop {
+            match self.n_pins.load(SeqCst) {
+                1 => {
+                    if self.swap_pins(1, PIN_LOCK) {
+                        self.access_log[self.id].store(seqno, SeqCst);
+                        self.n_pins.store(0, SeqCst);
+                        break;
+                    }
+                }
+                n if n & PIN_LOCK == 0 => {
+                    if self.swap_pins(n, n - 1) {
+                        break;
+                    }
+                }
+                _ => hint::spin_loop(),
+            }
+        }
+    }
+
+    #[inline]
+    fn swap_pins(&self, old: usize, new: usize) -> bool {
+        let res = self.n_pins.compare_exchange_weak(old, new, SeqCst, Relaxed);
+        res.is_ok()
+    }
+
+    // Advance the global epoch, once for every `epoch_period` mutations.
+    #[inline]
+    fn advance_epoch(&mut self) {
+        self.epoch_count += 1;
+        if self.epoch_count >= self.epoch_period {
+            self.epoch.fetch_add(1, SeqCst);
+            self.epoch_count = 0;
+        }
+    }
+
+    // Enter epoch for write access, or for a walk that does not nest.
+    #[inline]
+    fn enter_epoch(&self, seqno: u64) {
+        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+    }
 
+    // Exit epoch entered via enter_epoch(). In QSBR mode this instance stays
+    // in the epoch, until its next write or quiescent state, so that reads
+    // need not enter the epoch.
+    #[inline]
+    fn exit_epoch(&self, seqno: u64) {
+        let seqno = if self.qsbr { seqno | ENTER_MASK } else { seqno };
         self.access_log[self.id].store(seqno, SeqCst);
-        res
     }
 
-    pub fn get_with<Q, F, T>(&self, key: &Q, callb: F) -> Option<T>
+    // Caller should make sure that this instance is pinned to an epoch, and
+    // the returned reference shall not outlive that epoch.
+    pub(crate) fn as_root_node(&self) -> &Node<K, V> {
+        let inode: &In<K, V> = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+        unsafe { inode.node.load(SeqCst).as_ref().unwrap() }
+    }
+
+    // Lookup without entering the epoch, callers pin the instance on their
+    // own, and the value reference shall not outlive that pin.
+    pub(crate) fn lookup<'a, Q>(&'a self, key: &Q, hash: u128) -> Option<&'a V>
     where
-        K: Borrow<Q>,
-        Q: PartialEq + Hash + ?Sized,
+        Q: Equivalent<K> + Hash + ?Sized,
         H: BuildHasher,
-        F: Fn(&V) -> T,
     {
-        let seqno = self.epoch.load(SeqCst);
-        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+        self.lookup_item(key, hash).map(|item| &item.value)
+    }
 
-        let ws = {
-            let hasher = self.hash_builder.build_hasher();
-            slots(key_to_hash32(key, hasher))
-        };
+    // Same as lookup(), return the stored key along with its value.
+    pub(crate) fn lookup_item<'a, Q>(
+        &'a self,
+        key: &Q,
+        hash: u128,
+    ) -> Option<&'a Item<K, V>>
+    where
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let (mut ws, levels) = self.to_slots(hash);
+        let mut rehashed = false;
         let mut inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
-        let mut wss = &ws[..];
+        let mut wss = &ws[..levels];
         // println!("{}", format_ws!("get outer ws:{:?}", wss));
 
-        let res = loop {
+        loop {
             let old = inode.node.load(SeqCst);
             let node = unsafe { old.as_ref().unwrap() };
 
             let w = match wss.first() {
                 Some(w) => *w,
-                None => break node.as_value(key).map(|v| callb(v)),
+                None => match node {
+                    Node::Trie { .. } if !rehashed => {
+                        wss = self.rehash_slots(key, &mut ws, &mut rehashed);
+                        continue;
+                    }
+                    node => break node.as_item(key),
+                },
             };
             wss = &wss[1..];
             // println!("get loop w:{:x}", w);
@@ -1243,13 +2419,13 @@ impl<K, V, H> Map<K, V, H> {
                     let hd = hamming_distance(w, *bmp);
                     // println!("get loop bmp:{:x} {:?}", bmp, hd);
                     match hd {
-                        Distance::Insert(_) => break None,
-                        Distance::Set(n) => {
+                        (false, _) => break None,
+                        (true, n) => {
                             let ptr = childs[n].load(SeqCst);
                             match unsafe { ptr.as_ref().unwrap() } {
                                 Child::Deep(next_inode) => next_inode,
-                                Child::Leaf(item) if item.key.borrow() == key => {
-                                    break Some(callb(&item.value))
+                                Child::Leaf(item) if key.equivalent(&item.key) => {
+                                    break Some(item);
                                 }
                                 Child::Leaf(_) => break None,
                                 Child::None => unreachable!(),
@@ -1259,14 +2435,11 @@ impl<K, V, H> Map<K, V, H> {
                 }
                 Node::List { .. } => unreachable!(),
                 Node::Tomb { item } => match item {
-                    Some(m) if m.key.borrow() == key => break Some(callb(&m.value)),
+                    Some(m) if key.equivalent(&m.key) => break Some(m),
                     _ => break None,
                 },
             }
-        };
-
-        self.access_log[self.id].store(seqno, SeqCst);
-        res
+        }
     }
 
     pub fn set(&mut self, key: K, value: V) -> Option<V>
@@ -1275,36 +2448,72 @@ impl<K, V, H> Map<K, V, H> {
         V: Clone,
         H: BuildHasher,
     {
-        let (seqno, res) = self.do_set(key, value);
-        if self.gc_count == 0 {
-            let seqno = gc_epoch!(self.access_log, seqno);
-            if seqno < u64::MAX {
-                self.cas.garbage_collect(seqno)
-            }
-            self.gc_count = self.gc_period; // reload
-        }
-        self.gc_count = self.gc_count.saturating_sub(1);
+        self.set_hashed(self.hash_key(&key), key, value)
+    }
 
-        res
+    /// Same as [Map::set], but fail with [Error::Backpressure] instead of
+    /// waiting on a full backlog, refer to [Map::set_backlog].
+    pub fn try_set(&mut self, key: K, value: V) -> Result<Option<V>>
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        self.check_backlog()?;
+        Ok(self.set(key, value))
     }
 
-    fn do_set(&mut self, key: K, value: V) -> (u64, Option<V>)
+    /// Same as [Map::set], with `hash` already computed for `key` by
+    /// [Map::hash_key]. Supplying a hash that is not computed from `key`
+    /// would index the key under a wrong position, where lookups shall not
+    /// find it.
+    pub fn set_hashed(&mut self, hash: u64, key: K, value: V) -> Option<V>
     where
         K: Clone + PartialEq + Hash,
         V: Clone,
         H: BuildHasher,
     {
-        let seqno = self.epoch.load(SeqCst);
-        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+        let hash = self.to_trie_hash_from(hash, &key);
+        let (seqno, res) = self.do_update(key, hash, |_| Some(value.clone()));
+        self.do_gc(seqno);
 
-        let ws = {
-            let hasher = self.hash_builder.build_hasher();
-            slots(key_to_hash32(&key, hasher))
-        };
-        let res = 'retry: loop {
-            let mut inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
-            let mut wss = &ws[..];
-            // println!("set try key:{:?} {}", key, format_ws!("{:?}", ws));
+        res
+    }
+
+    // Update the value for key, through `callb`. Callback is called with the
+    // current value for key, if any, and it shall return the new value. If
+    // callback returns None, map is left untouched. Since the callback might
+    // be called again on CAS failure, it should be idempotent.
+    pub(crate) fn do_update<F>(
+        &mut self,
+        key: K,
+        hash: u128,
+        mut callb: F,
+    ) -> (u64, Option<V>)
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+        F: FnMut(Option<&V>) -> Option<V>,
+    {
+        self.wait_backlog();
+
+        let seqno = self.epoch.load(SeqCst);
+        self.enter_epoch(seqno);
+
+        let (mut ws, levels) = self.to_slots(hash);
+        let mut rehashed = false;
+        let mut depth; // of the key in the trie, for collision counters.
+        let mut attempt = 0;
+        let mut lock = None;
+        let res = 'retry: loop {
+            self.snooze(attempt, hash, &mut lock);
+            attempt += 1;
+            let mut inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+            let mut wss = &ws[..levels];
+            let mut deep = false;
+            depth = 0;
+            // println!("set try key:{:?} {}", key, format_ws!("{:?}", ws));
 
             loop {
                 let old: *mut Node<K, V> = inode.node.load(SeqCst);
@@ -1314,31 +2523,50 @@ impl<K, V, H> Map<K, V, H> {
                     Some(w) => *w,
                     None => match node {
                         Node::Tomb { .. } => continue 'retry,
-                        Node::List { .. } => {
+                        Node::List { items } => {
+                            let value = match callb(get_from_list(&key, items)) {
+                                Some(value) => value,
+                                None => break 'retry None,
+                            };
+                            depth += 1;
                             let op = generate_op!(self, inode, old);
-                            match Node::update_list(&key, &value, op) {
+                            match Node::update_list(&key, value, op) {
+                                CasRc::Ok(None) => {
+                                    self.pressure.list_len(items.len() + 1);
+                                    break 'retry None;
+                                }
                                 CasRc::Ok(old_value) => break 'retry old_value,
                                 CasRc::Retry => continue 'retry,
                             }
                         }
+                        Node::Trie { .. } if !deep => {
+                            wss = self.rehash_slots(&key, &mut ws, &mut rehashed);
+                            deep = true;
+                            continue;
+                        }
                         Node::Trie { .. } => unreachable!(),
                     },
                 };
                 wss = &wss[1..];
+                depth += 1;
                 // println!("set loop w:{:x} wss:{:?}", w, wss);
 
                 let n = match node {
                     Node::Trie { bmp, .. } => match hamming_distance(w, *bmp) {
-                        Distance::Insert(n) => {
+                        (false, n) => {
                             // println!("set loop insert bmp:{:x} {}", bmp, n);
+                            let value = match callb(None) {
+                                Some(value) => value,
+                                None => break 'retry None,
+                            };
                             let op = generate_op!(self, inode, old);
-                            let item = (key.clone(), value.clone()).into();
+                            let item = (key.clone(), value).into();
                             match Node::ins_child(item, w, n, op) {
                                 CasRc::Ok(_) => break 'retry None,
                                 CasRc::Retry => continue 'retry,
                             }
                         }
-                        Distance::Set(n) => n,
+                        (true, n) => n,
                     },
                     Node::Tomb { .. } => continue 'retry,
                     Node::List { .. } => unreachable!(),
@@ -1351,39 +2579,78 @@ impl<K, V, H> Map<K, V, H> {
                         next_node
                     }
                     Child::Leaf(ot) if ot.key == key => {
+                        let value = match callb(Some(&ot.value)) {
+                            Some(value) => value,
+                            None => break 'retry None,
+                        };
                         let op = generate_op!(self, inode, old);
                         // println!("set loop 1");
 
-                        let item = (key.clone(), value.clone()).into();
+                        let item = (key.clone(), value).into();
                         match Node::set_child(item, n, op) {
                             CasRc::Ok(_) => break 'retry Some(ot.value.clone()),
                             CasRc::Retry => continue 'retry,
                         }
                     }
-                    Child::Leaf(_) if wss.is_empty() => {
+                    Child::Leaf(_) if wss.is_empty() && !self.can_rehash(deep) => {
+                        let value = match callb(None) {
+                            Some(value) => value,
+                            None => break 'retry None,
+                        };
+                        depth += 1;
                         let op = generate_op!(self, inode, old);
                         // println!("set loop 2");
 
-                        match Node::leaf_to_list(key.clone(), &value, n, op) {
-                            CasRc::Ok(_) => break 'retry None,
+                        match Node::leaf_to_list(key.clone(), value, n, op) {
+                            CasRc::Ok(_) => {
+                                self.pressure.list_len(2);
+                                break 'retry None;
+                            }
                             CasRc::Retry => continue 'retry,
                         }
                     }
                     Child::Leaf(leaf) => {
-                        let hash_lk = {
-                            let hasher = self.hash_builder.build_hasher();
-                            key_to_hash32(&leaf.key, hasher)
+                        let value = match callb(None) {
+                            Some(value) => value,
+                            None => break 'retry None,
                         };
+                        let (mut lws, _) = self.to_slots(self.to_trie_hash(&leaf.key));
+                        if deep {
+                            self.rehash_slots(&leaf.key, &mut lws, &mut false);
+                        }
+                        let till = if deep { REHASH_DEPTH } else { levels };
+                        let from = till - wss.len();
+                        let till = match ws[from..till] == lws[from..till] {
+                            true if self.can_rehash(deep) => {
+                                // keys collide on the rest of the hash, split them
+                                // on re-hash.
+                                self.rehash_slots(&key, &mut ws, &mut rehashed);
+                                self.rehash_slots(&leaf.key, &mut lws, &mut false);
+                                REHASH_DEPTH
+                            }
+                            _ => till,
+                        };
+
+                        if ws[..LEVELS32] == lws[..LEVELS32] {
+                            depth = LEVELS32 + 1;
+                        }
+                        // keys collide on the entire hash, ends up in a list.
+                        let list = ws[from..till] == lws[from..till];
 
                         let mut op = generate_op!(self, inode, old);
-                        let mut scratch = [(0_u8, 0_u8); 8];
-                        let xs = subtrie_zip(hash_lk, wss, &mut scratch);
+                        let mut scratch = [(0_u8, 0_u8); MAX_LEVELS];
+                        let xs =
+                            subtrie_zip(&ws[from..till], &lws[from..till], &mut scratch);
                         // println!("set loop 3");
 
-                        let item: Item<K, V> = (key.clone(), value.clone()).into();
+                        let item: Item<K, V> = (key.clone(), value).into();
                         let node_ptr = Node::new_subtrie(item, leaf, xs, &mut op);
 
                         match Node::set_trie_child(node_ptr, n, op) {
+                            CasRc::Ok(_) if list => {
+                                self.pressure.list_len(2);
+                                break 'retry None;
+                            }
                             CasRc::Ok(_) => break 'retry None,
                             CasRc::Retry => continue 'retry,
                         }
@@ -1393,52 +2660,292 @@ impl<K, V, H> Map<K, V, H> {
             }
         };
 
-        self.access_log[self.id].store(seqno, SeqCst);
-        self.epoch.fetch_add(1, SeqCst);
+        self.exit_epoch(seqno);
+        self.advance_epoch();
+        self.cas.count_set_retries(attempt - 1);
+
+        if depth > LEVELS32 {
+            self.pressure.deep_write()
+        }
 
         (seqno, res)
     }
 
-    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    // Rewrite values in place, node by node, refer to Map::for_each_mut.
+    // Each pass enters the epoch and rewrites about FOR_EACH_PERIOD entries,
+    // in walk order, from `from`, a walk order widened to u64 so that the
+    // end of walk, 1 << 32, can be represented.
+    pub(crate) fn do_rewrite<F>(&mut self, callb: &mut F)
     where
-        K: Clone + Borrow<Q>,
+        K: Clone + PartialEq + Hash,
         V: Clone,
-        Q: PartialEq + Hash + ?Sized,
         H: BuildHasher,
+        F: FnMut(&K, &mut V),
     {
-        let (seqno, compact, res) = self.do_remove(key);
-        if compact {
-            self.do_compact(key)
+        let mut from = 0_u64;
+        while from <= (u32::MAX as u64) {
+            self.wait_backlog();
+
+            let seqno = self.epoch.load(SeqCst);
+            self.enter_epoch(seqno);
+            let res = {
+                let inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+                let mut budget = iter::FOR_EACH_PERIOD;
+                let mut cursor = from;
+                self.rewrite_node(inode, 0, 0, &mut cursor, &mut budget, callb)
+            };
+            self.exit_epoch(seqno);
+            self.advance_epoch();
+            // a pass swings upto FOR_EACH_PERIOD nodes, irrespective of gc
+            // period.
+            self.force_gc();
+
+            from = match res {
+                ControlFlow::Continue(()) => break,
+                ControlFlow::Break(Rewrite::At(order)) => order,
+                ControlFlow::Break(Rewrite::Group(order, keys)) => {
+                    for key in keys.into_iter() {
+                        let hash = self.to_trie_hash(&key);
+                        let (seqno, _) = self.do_update(key.clone(), hash, |value| {
+                            let mut value = value?.clone();
+                            callb(&key, &mut value);
+                            Some(value)
+                        });
+                        self.do_gc(seqno);
+                    }
+                    (order as u64) + 1
+                }
+            };
         }
-        if self.gc_count == 0 {
-            let seqno = gc_epoch!(self.access_log, seqno);
-            if seqno < u64::MAX {
-                self.cas.garbage_collect(seqno)
+    }
+
+    // Rewrite entries under `inode`, whose walk order is at or beyond
+    // `cursor`, and move the cursor past them. Leaves of a trie node are
+    // rewritten in runs, each run is a single copy of the node swung into
+    // `inode`, a run ends before descending into a deeper node. On CAS
+    // failure the node is reloaded and the run is rewritten again from the
+    // cursor, calling `callb` again on the latest values.
+    fn rewrite_node<F>(
+        &mut self,
+        inode: &In<K, V>,
+        depth: usize,
+        prefix: u32,
+        cursor: &mut u64,
+        budget: &mut usize,
+        callb: &mut F,
+    ) -> ControlFlow<Rewrite<K>>
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+        F: FnMut(&K, &mut V),
+    {
+        'retry: loop {
+            let mut old: *mut Node<K, V> = inode.node.load(SeqCst);
+            // rewritten leaves are swung as copies of this node, positions
+            // of its childs stay the same in the copies.
+            let node: &Node<K, V> = unsafe { old.as_ref().unwrap() };
+
+            let bmp = match node {
+                // in-node is being compacted, restart from the root.
+                Node::Tomb { .. } => break ControlFlow::Break(Rewrite::At(*cursor)),
+                Node::List { .. } if (prefix as u64) < *cursor => {
+                    break ControlFlow::Continue(());
+                }
+                Node::List { items } => {
+                    let items: Vec<Item<K, V>> = items
+                        .iter()
+                        .map(|item| {
+                            let mut value = item.value.clone();
+                            callb(&item.key, &mut value);
+                            (item.key.clone(), value).into()
+                        })
+                        .collect();
+                    let n = items.len();
+                    let op = generate_op!(self, inode, old);
+                    match Node::set_list(items, op) {
+                        CasRc::Ok(_) => {
+                            *budget = budget.saturating_sub(n);
+                            *cursor = (prefix as u64) + 1;
+                            break ControlFlow::Continue(());
+                        }
+                        CasRc::Retry => continue 'retry,
+                    }
+                }
+                // below the 32-bit walk order, subtrie is a collision group,
+                // rewritten per key.
+                Node::Trie { .. } if depth >= LEVELS32 && (prefix as u64) < *cursor => {
+                    break ControlFlow::Continue(());
+                }
+                Node::Trie { .. } if depth >= LEVELS32 => {
+                    let mut keys = vec![];
+                    node.collect_keys(&mut keys);
+                    break ControlFlow::Break(Rewrite::Group(prefix, keys));
+                }
+                Node::Trie { bmp, .. } => *bmp,
+            };
+
+            let shift = 28 - (depth * 4);
+            let span = 1_u64 << shift;
+            let ws = (0..16_u32).filter(|w| (bmp & (1 << w)) != 0);
+
+            let mut leaves: Vec<(usize, Item<K, V>)> = vec![];
+            let mut last = 0;
+            for (n, w) in ws.enumerate() {
+                let lo = (prefix | (w << shift)) as u64;
+                if lo + span <= *cursor {
+                    continue;
+                }
+                if *budget == 0
+                    || matches!(
+                        unsafe { node.get_child(n).as_ref() },
+                        Some(Child::Deep(_))
+                    )
+                {
+                    match self.rewrite_leaves(inode, &mut old, leaves, last, cursor) {
+                        true => leaves = vec![],
+                        false => continue 'retry,
+                    }
+                }
+                if *budget == 0 {
+                    break 'retry ControlFlow::Break(Rewrite::At(u64::max(lo, *cursor)));
+                }
+                match unsafe { node.get_child(n).as_ref().unwrap() } {
+                    Child::Leaf(item) => {
+                        let order = to_walk_order(self.to_hash(&item.key)) as u64;
+                        if order >= *cursor {
+                            let mut value = item.value.clone();
+                            callb(&item.key, &mut value);
+                            leaves.push((n, (item.key.clone(), value).into()));
+                            *budget = budget.saturating_sub(1);
+                            last = order;
+                        }
+                    }
+                    Child::Deep(next) => {
+                        *cursor = u64::max(lo, *cursor);
+                        let res = self.rewrite_node(
+                            next,
+                            depth + 1,
+                            lo as u32,
+                            cursor,
+                            budget,
+                            callb,
+                        );
+                        if let ControlFlow::Break(_) = res {
+                            break 'retry res;
+                        }
+                        *cursor = lo + span;
+                    }
+                    Child::None => unreachable!(),
+                }
+            }
+            match self.rewrite_leaves(inode, &mut old, leaves, last, cursor) {
+                true => break ControlFlow::Continue(()),
+                false => continue 'retry,
             }
-            self.gc_count = self.gc_period; // reload
         }
-        self.gc_count = self.gc_count.saturating_sub(1);
+    }
+
+    // Swing a copy of `old` with its `leaves` rewritten, and point `old` to
+    // the copy, return false on CAS failure. `last` is the walk order of the
+    // last leaf.
+    fn rewrite_leaves(
+        &mut self,
+        inode: &In<K, V>,
+        old: &mut *mut Node<K, V>,
+        leaves: Vec<(usize, Item<K, V>)>,
+        last: u64,
+        cursor: &mut u64,
+    ) -> bool
+    where
+        K: Clone,
+        V: Clone,
+    {
+        if leaves.is_empty() {
+            return true;
+        }
+        let op = generate_op!(self, inode, *old);
+        match Node::set_leaves(leaves, op) {
+            CasRc::Ok(new) => {
+                *old = new;
+                *cursor = last + 1;
+                true
+            }
+            CasRc::Retry => false,
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Clone,
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.remove_hashed(self.hash_key(key), key)
+    }
+
+    /// Same as [Map::remove], but fail with [Error::Backpressure] instead of
+    /// waiting on a full backlog, refer to [Map::set_backlog].
+    pub fn try_remove<Q>(&mut self, key: &Q) -> Result<Option<V>>
+    where
+        K: Clone,
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.check_backlog()?;
+        Ok(self.remove(key))
+    }
+
+    /// Same as [Map::remove], with `hash` already computed for `key` by
+    /// [Map::hash_key].
+    pub fn remove_hashed<Q>(&mut self, hash: u64, key: &Q) -> Option<V>
+    where
+        K: Clone,
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let hash = self.to_trie_hash_from(hash, key);
+        let (seqno, compact, res) = self.do_remove(key, hash, |_| true);
+        if compact {
+            self.do_compact(key, hash)
+        }
+        self.do_gc(seqno);
 
         res
     }
 
-    fn do_remove<Q>(&mut self, key: &Q) -> (u64, bool, Option<V>)
+    // Remove the entry for key, only if `pred` returns true for its value.
+    pub(crate) fn do_remove<Q, F>(
+        &mut self,
+        key: &Q,
+        hash: u128,
+        mut pred: F,
+    ) -> (u64, bool, Option<V>)
     where
-        K: Clone + Borrow<Q>,
+        K: Clone,
         V: Clone,
-        Q: PartialEq + Hash + ?Sized,
+        Q: Equivalent<K> + Hash + ?Sized,
         H: BuildHasher,
+        F: FnMut(&V) -> bool,
     {
+        self.wait_backlog();
+
         let seqno = self.epoch.load(SeqCst);
-        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+        self.enter_epoch(seqno);
 
-        let ws = {
-            let hasher = self.hash_builder.build_hasher();
-            slots(key_to_hash32(key, hasher))
-        };
+        let (mut ws, levels) = self.to_slots(hash);
+        let mut rehashed = false;
+        let mut attempt = 0;
+        let mut lock = None;
         let (compact, res) = 'retry: loop {
+            self.snooze(attempt, hash, &mut lock);
+            attempt += 1;
             let mut inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
-            let mut wss = &ws[..];
+            let mut wss = &ws[..levels];
+            let mut deep = false;
             // println!("remove try key:{:?} {}", key, format_ws!("{:?}", ws));
 
             let mut depth = 0;
@@ -1452,6 +2959,9 @@ impl<K, V, H> Map<K, V, H> {
                     None => match node {
                         Node::List { items } if items.len() < 2 => unreachable!(),
                         Node::List { items } => match has_key(items, key) {
+                            Some(n) if !pred(&items[n].value) => {
+                                break 'retry (false, None)
+                            }
                             Some(n) => {
                                 let op = generate_op!(self, inode, old);
                                 match Node::remove_from_list(n, op) {
@@ -1462,9 +2972,15 @@ impl<K, V, H> Map<K, V, H> {
                             None => break 'retry (false, None),
                         },
                         Node::Tomb { item } => match item {
-                            Some(m) if m.key.borrow() == key => continue 'retry,
+                            Some(m) if key.equivalent(&m.key) => continue 'retry,
                             _ => break 'retry (false, None),
                         },
+                        Node::Trie { .. } if !deep => {
+                            wss = self.rehash_slots(key, &mut ws, &mut rehashed);
+                            deep = true;
+                            depth -= 1;
+                            continue;
+                        }
                         Node::Trie { .. } => unreachable!(),
                     },
                 };
@@ -1476,12 +2992,12 @@ impl<K, V, H> Map<K, V, H> {
                     Node::Trie { bmp, childs } => match childs.len() {
                         0 => break 'retry (false, None),
                         _ => match hamming_distance(w, *bmp) {
-                            Distance::Insert(_) => break 'retry (false, None),
-                            Distance::Set(n) => (n, bmp, childs),
+                            (false, _) => break 'retry (false, None),
+                            (true, n) => (n, bmp, childs),
                         },
                     },
                     Node::Tomb { item } => match item {
-                        Some(item) if item.key.borrow() == key => continue 'retry,
+                        Some(item) if key.equivalent(&item.key) => continue 'retry,
                         _ => break 'retry (false, None),
                     },
                     Node::List { .. } => unreachable!(),
@@ -1491,7 +3007,12 @@ impl<K, V, H> Map<K, V, H> {
                 let ocp = childs[n].load(SeqCst);
                 inode = match unsafe { ocp.as_ref().unwrap() } {
                     Child::Deep(next_inode) => next_inode,
-                    Child::Leaf(item) if item.key.borrow() == key && depth == 1 => {
+                    Child::Leaf(item)
+                        if key.equivalent(&item.key) && !pred(&item.value) =>
+                    {
+                        break 'retry (false, None)
+                    }
+                    Child::Leaf(item) if key.equivalent(&item.key) && depth == 1 => {
                         // println!("remove1 old value {:?}", ov);
 
                         // avoid tombification of node, root node should always be
@@ -1503,7 +3024,7 @@ impl<K, V, H> Map<K, V, H> {
                             CasRc::Retry => continue 'retry,
                         }
                     }
-                    Child::Leaf(item) if item.key.borrow() == key => {
+                    Child::Leaf(item) if key.equivalent(&item.key) => {
                         let ov = item.value.clone();
                         let (compact, res) = match childs.len() {
                             0 => unreachable!(),
@@ -1511,7 +3032,7 @@ impl<K, V, H> Map<K, V, H> {
                                 // println!("remove2 old value {:?}", ov);
 
                                 let op = generate_op!(self, inode, old);
-                                debug_assert!(n == 0, "unexpected {}", n);
+                                paranoid_assert!(n == 0, "unexpected {}", n);
                                 (true, Node::remove_child1(n, op))
                             }
                             2 => {
@@ -1553,29 +3074,35 @@ impl<K, V, H> Map<K, V, H> {
             }
         };
 
-        self.access_log[self.id].store(seqno, SeqCst);
-        self.epoch.fetch_add(1, SeqCst);
+        self.exit_epoch(seqno);
+        self.advance_epoch();
+        self.cas.count_remove_retries(attempt - 1);
 
         (seqno, compact, res)
     }
 
-    fn do_compact<Q>(&mut self, key: &Q)
+    pub(crate) fn do_compact<Q>(&mut self, key: &Q, hash: u128)
     where
-        K: Clone + Borrow<Q>,
+        K: Clone,
         V: Clone,
         Q: Hash + ?Sized,
         H: BuildHasher,
     {
+        self.cas.count_compact();
+
         let seqno = self.epoch.load(SeqCst);
-        self.access_log[self.id].store(seqno | ENTER_MASK, SeqCst);
+        self.enter_epoch(seqno);
 
-        let ws = {
-            let hasher = self.hash_builder.build_hasher();
-            slots(key_to_hash32(key, hasher))
-        };
+        let (mut ws, levels) = self.to_slots(hash);
+        let mut rehashed = false;
+        let mut attempt = 0;
+        let mut lock = None;
         'retry: loop {
+            self.snooze(attempt, hash, &mut lock);
+            attempt += 1;
             let mut inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
-            let mut wss = &ws[..];
+            let mut wss = &ws[..levels];
+            let mut deep = false;
             //println!(
             //    "do_compact key:{:?} {} retry:{}",
             //    key,
@@ -1595,6 +3122,12 @@ impl<K, V, H> Map<K, V, H> {
                         Node::Tomb { .. } => continue 'retry,
                         Node::List { items } if items.len() < 2 => unreachable!(),
                         Node::List { .. } => break 'retry,
+                        Node::Trie { .. } if !deep => {
+                            wss = self.rehash_slots(key, &mut ws, &mut rehashed);
+                            deep = true;
+                            depth -= 1;
+                            continue;
+                        }
                         Node::Trie { .. } => unreachable!(),
                     },
                 };
@@ -1606,8 +3139,8 @@ impl<K, V, H> Map<K, V, H> {
                     Node::Trie { bmp, childs } => {
                         let hd = hamming_distance(w, *bmp);
                         let n = match hd {
-                            Distance::Insert(_) => break 'retry,
-                            Distance::Set(n) => n,
+                            (false, _) => break 'retry,
+                            (true, n) => n,
                         };
                         (n, unsafe { childs[n].load(SeqCst).as_ref().unwrap() })
                     }
@@ -1618,7 +3151,12 @@ impl<K, V, H> Map<K, V, H> {
                     Node::Trie { .. } if child.is_tomb_node() => {
                         let op = generate_op!(self, inode, old);
                         match Node::compact_trie_from(w, n, depth, op) {
-                            (true, CasRc::Ok(_)) => continue 'retry,
+                            (true, CasRc::Ok(_)) => {
+                                // compact the parent, this is not a retry.
+                                self.cas.count_compact_retries(attempt - 1);
+                                attempt = 0;
+                                continue 'retry;
+                            }
                             (false, CasRc::Ok(_)) => break 'retry,
                             (_, CasRc::Retry) => continue 'retry,
                         }
@@ -1633,8 +3171,726 @@ impl<K, V, H> Map<K, V, H> {
             }
         }
 
-        self.access_log[self.id].store(seqno, SeqCst);
+        self.exit_epoch(seqno);
+        self.advance_epoch();
+        self.cas.count_compact_retries(attempt - 1);
+    }
+
+    /// Release memory held by this instance, typically after a large burst
+    /// of deletes. Trie nodes that are holding more memory than they need
+    /// are replaced with compact copies, garbage that is safe to collect is
+    /// collected and this instance's memory pools are released to the
+    /// allocator. Pools are maintained for each clone of the map, call this
+    /// method on each of the clones to release all pooled memory.
+    pub fn shrink_to_fit(&mut self)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let seqno = self.epoch.load(SeqCst);
+        self.enter_epoch(seqno);
+
+        let inode = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+        self.do_shrink(inode);
+
+        self.exit_epoch(seqno);
         self.epoch.fetch_add(1, SeqCst);
+
+        // use the latest epoch, so that nodes replaced above can be collected.
+        let seqno = gc_epoch!(self.access_log, self.epoch.load(SeqCst));
+        if seqno < u64::MAX {
+            self.cas.garbage_collect(seqno)
+        }
+        self.cas.trim();
+    }
+
+    /// Release this instance's memory pools, along with the pool shared by
+    /// all the clones, back to the allocator, and return the number of
+    /// allocations released. Unlike [Map::shrink_to_fit], this does not
+    /// walk the trie, and is cheap enough to be called when a thread goes
+    /// idle after a burst of writes.
+    pub fn trim(&mut self) -> usize {
+        self.cas.trim()
+    }
+
+    /// Return the number of allocations, retired by all the clones via
+    /// copy-on-write, that are not yet reclaimed. Every retired allocation
+    /// is tracked from the time it is handed to the garbage collector, till
+    /// it is freed or pooled, a double free shall panic. Available with
+    /// `leak-check` feature.
+    #[cfg(feature = "leak-check")]
+    pub fn outstanding_allocations(&self) -> usize {
+        self.cas.to_retired_count()
+    }
+
+    // called while dropping the last handle, report retired allocations
+    // that were never reclaimed, and check that allocations, less frees,
+    // are held either by the trie or by the pools.
+    #[cfg(feature = "leak-check")]
+    fn leak_report(&self) {
+        let n = self.outstanding_allocations();
+        if n > 0 {
+            eprintln!("leak-check: {} retired allocations are not reclaimed", n);
+        }
+
+        let (n_reclaims, n_items) = self.collector.to_reclaim_counts();
+        let n_allocs = self.n_allocs.load(Relaxed);
+        let n_frees = self.n_frees.load(Relaxed);
+        let n_held = self.as_root_node().to_alloc_count()
+            + self.n_pools.load(Relaxed)
+            + self.cas.to_shared_pooled_count()
+            + n_reclaims
+            + n_items;
+        if n_allocs != n_frees + n_held {
+            eprintln!(
+                "leak-check: allocs:{} frees:{} held:{}",
+                n_allocs, n_frees, n_held
+            );
+            if !thread::panicking() {
+                panic!("leak-check: allocations do not balance frees")
+            }
+        }
+    }
+
+    /// Advance the epoch and collect all the garbage that is safe to
+    /// collect, irrespective of gc period, including the garbage handed
+    /// over to the background collector. Return the number of allocations
+    /// collected. Garbage that might still be referred by concurrent
+    /// readers, pinned to an older epoch, is left for a later call.
+    /// Collected memory is held in pools, use [Map::shrink_to_fit] to
+    /// release them to the allocator.
+    pub fn flush_gc(&mut self) -> usize {
+        self.epoch.fetch_add(1, SeqCst);
+        self.quiescent();
+        self.collector.take_back(&mut self.cas);
+
+        let (n_reclaims, n_items) = self.cas.to_reclaim_counts();
+        let seqno = gc_epoch!(self.access_log, self.epoch.load(SeqCst));
+        if seqno < u64::MAX {
+            self.cas.garbage_collect(seqno)
+        }
+        let (m_reclaims, m_items) = self.cas.to_reclaim_counts();
+        (n_reclaims + n_items) - (m_reclaims + m_items)
+    }
+
+    fn do_shrink(&mut self, inode: &In<K, V>)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let old = inode.node.load(SeqCst);
+        let node = unsafe { old.as_ref().unwrap() };
+
+        let op = generate_op!(self, inode, old);
+        // compaction is best effort, CAS failure means a fresh node is in place.
+        let _ = Node::shrink_node(op);
+
+        if let Node::Trie { childs, .. } = node {
+            for child in childs.iter() {
+                if let Some(Child::Deep(next_inode)) =
+                    unsafe { child.load(SeqCst).as_ref() }
+                {
+                    self.do_shrink(next_inode)
+                }
+            }
+        }
+    }
+
+    pub(crate) fn do_gc(&mut self, seqno: u64) {
+        if self.access_log.len() == 1 && self.cas.has_reclaims() {
+            // single handle, there can be no concurrent readers, and reads
+            // via this handle borrow the map, collect the garbage right away.
+            self.cas.garbage_collect(u64::MAX);
+            return;
+        }
+        if self.gc_count == 0 {
+            if !self.collector.hand_off(&mut self.cas) {
+                // collect the garbage left by dropped instances.
+                self.collector.take_back(&mut self.cas);
+                let seqno = gc_epoch!(self.access_log, seqno);
+                if seqno < u64::MAX {
+                    self.cas.garbage_collect(seqno)
+                }
+            }
+            self.gc_count = self.gc_period; // reload
+        }
+        self.gc_count = self.gc_count.saturating_sub(1);
+    }
+
+    // Number of writes pending garbage collection, held by this instance and
+    // the background collector.
+    fn to_backlog(&self) -> usize {
+        self.cas.to_reclaim_counts().0 + self.collector.to_reclaim_counts().0
+    }
+
+    // Fail if the backlog is full even after collecting the garbage.
+    fn check_backlog(&mut self) -> Result<()> {
+        match self.backlog {
+            Some(limit) if self.to_backlog() >= limit => {
+                self.flush_gc();
+                let n = self.to_backlog();
+                if n >= limit {
+                    err_at!(Backpressure, msg: "backlog {} is full, limit {}", n, limit)
+                } else {
+                    Ok(())
+                }
+            }
+            Some(_) | None => Ok(()),
+        }
+    }
+
+    // Help collecting the garbage until the backlog is below its limit.
+    #[inline]
+    fn wait_backlog(&mut self) {
+        if self.backlog.is_some() {
+            while self.check_backlog().is_err() {
+                thread::yield_now()
+            }
+        }
+    }
+
+    // Collect garbage from this instance, irrespective of gc period.
+    pub(crate) fn force_gc(&mut self) {
+        if self.collector.hand_off(&mut self.cas) {
+            return;
+        }
+        let seqno = gc_epoch!(self.access_log, self.epoch.load(SeqCst));
+        if seqno < u64::MAX {
+            self.cas.garbage_collect(seqno)
+        }
+    }
+}
+
+/// Multi-map API, where each key can index a list of values.
+impl<K, T, H> Map<K, Vec<T>, H> {
+    /// Append `value` to the list of values indexed by `key`, creating the
+    /// entry if it is not already present. Unlike a get() and set() pair, this
+    /// operation is atomic, concurrent appends on the same key are not lost.
+    ///
+    /// Values are held as a plain `Vec<T>`, which is copied on write like
+    /// any other value. Each append clones the current list into a new one,
+    /// O(n) for n values under `key`, and repeats the copy when a concurrent
+    /// write forces a retry. Building a list of n values by appending one at
+    /// a time is hence O(n^2), prefer set() with the full list for bulk
+    /// inserts. Refer to TODO.md on sharing the list across versions.
+    pub fn append(&mut self, key: K, value: T)
+    where
+        K: Clone + PartialEq + Hash,
+        T: Clone,
+        H: BuildHasher,
+    {
+        let hash = self.to_trie_hash(&key);
+        let (seqno, _) = self.do_update(key, hash, |old| {
+            let mut values = Vec::with_capacity(old.map(|vs| vs.len()).unwrap_or(0) + 1);
+            if let Some(vs) = old {
+                values.extend_from_slice(vs)
+            }
+            values.push(value.clone());
+            Some(values)
+        });
+        self.do_gc(seqno);
+    }
+
+    /// Return all the values indexed by `key`, in the order they were appended.
+    /// Return an empty list if key is not present.
+    pub fn get_all<Q>(&self, key: &Q) -> Vec<T>
+    where
+        T: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.get(key).unwrap_or_default()
+    }
+
+    /// Remove the first occurrence of `value` from the list of values indexed
+    /// by `key`. If that was the only value left, the entry itself is removed.
+    /// Return whether a value was removed.
+    pub fn remove_value(&mut self, key: &K, value: &T) -> bool
+    where
+        K: Clone + PartialEq + Hash,
+        T: Clone + PartialEq,
+        H: BuildHasher,
+    {
+        let hash = self.to_trie_hash(key);
+        loop {
+            let (seqno, compact, res) =
+                self.do_remove(key, hash, |vs| vs.len() == 1 && vs[0] == *value);
+            if compact {
+                self.do_compact(key, hash)
+            }
+            self.do_gc(seqno);
+            if res.is_some() {
+                break true;
+            }
+
+            // `removed` is left as None, if value is the only one left, in which
+            // case retry removing the entry.
+            let mut removed = Some(false);
+            let (seqno, _) = self.do_update(key.clone(), hash, |old| {
+                let vs = match old {
+                    Some(vs) => vs,
+                    None => {
+                        removed = Some(false);
+                        return None;
+                    }
+                };
+                match vs.iter().position(|v| v == value) {
+                    Some(_) if vs.len() == 1 => {
+                        removed = None;
+                        None
+                    }
+                    Some(i) => {
+                        removed = Some(true);
+                        let mut values = Vec::with_capacity(vs.len() - 1);
+                        values.extend_from_slice(&vs[..i]);
+                        values.extend_from_slice(&vs[i + 1..]);
+                        Some(values)
+                    }
+                    None => {
+                        removed = Some(false);
+                        None
+                    }
+                }
+            });
+            self.do_gc(seqno);
+
+            if let Some(removed) = removed {
+                break removed;
+            }
+        }
+    }
+}
+
+/// Bulk load API.
+impl<K, V, H> Map<K, V, H> {
+    /// Load entries from `iter` into this map, which must be empty. Entries
+    /// are sorted by their path in the trie, and the trie is built bottom
+    /// up, without compare-and-swap or garbage collection, and published
+    /// in one go. For keys repeated in `iter` the last value is loaded.
+    /// Return the number of entries loaded. Fail if the map is not empty,
+    /// including when a concurrent write lands before the trie is published.
+    pub fn bulk_load<I>(&mut self, iter: I) -> Result<usize>
+    where
+        K: PartialEq + Hash,
+        H: BuildHasher,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        if !self.is_empty() {
+            return err_at!(InvalidInput, msg: "bulk_load on a non-empty map");
+        }
+
+        let levels = self.width.to_levels();
+        let mut entries: Vec<(u128, Option<Item<K, V>>)> = iter
+            .into_iter()
+            .map(|(key, value)| {
+                let (ws, _) = self.to_slots(self.to_trie_hash(&key));
+                (to_path(&ws[..levels]), Some((key, value).into()))
+            })
+            .collect();
+        // stable sort, repeated keys stay in the order of `iter`.
+        entries.sort_by_key(|(path, _)| *path);
+        let mut entries = dedup_last(entries);
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        let n = entries.len();
+        let node = self.bulk_node(&mut entries, 0, levels, false);
+
+        let seqno = self.epoch.load(SeqCst);
+        self.enter_epoch(seqno);
+        let inode: &In<K, V> = unsafe { self.root.load(SeqCst).as_ref().unwrap() };
+        let old = inode.node.load(SeqCst);
+        let ok = match unsafe { old.as_ref().unwrap() } {
+            Node::Trie { childs, .. } if childs.is_empty() => {
+                self.cas.free_on_pass(gc::Mem::Node(old));
+                self.cas.swing(&self.epoch, &inode.node, old, node)
+            }
+            _ => false,
+        };
+        self.exit_epoch(seqno);
+
+        if ok {
+            self.advance_epoch();
+            self.do_gc(seqno);
+            Ok(n)
+        } else {
+            let n = Node::dismantle(node, &mut vec![]);
+            self.cas.count_frees(n);
+            err_at!(InvalidInput, msg: "bulk_load raced with a concurrent write")
+        }
+    }
+
+    // Build the trie node for `entries` sharing the path till `depth`.
+    // Paths are `levels` slots long, and with `deep` the paths are of the
+    // re-hash, refer to HashWidth::Rehash.
+    fn bulk_node(
+        &mut self,
+        entries: &mut [(u128, Option<Item<K, V>>)],
+        depth: usize,
+        levels: usize,
+        deep: bool,
+    ) -> *mut Node<K, V>
+    where
+        H: BuildHasher,
+        K: Hash,
+    {
+        if depth == levels && self.can_rehash(deep) {
+            for (path, item) in entries.iter_mut() {
+                let key = &item.as_ref().unwrap().key;
+                let hash = key_to_rehash32(key, self.hash_builder.build_hasher());
+                *path = to_path(&slots(hash));
+            }
+            entries.sort_by_key(|(path, _)| *path);
+            return self.bulk_node(entries, 0, REHASH_DEPTH - levels, true);
+        } else if depth == levels {
+            // keys colliding on the entire hash.
+            self.pressure.list_len(entries.len());
+            let mut node = self.cas.alloc_node('l');
+            match node.as_mut() {
+                Node::List { items } => {
+                    items.clear();
+                    for (_, item) in entries.iter_mut() {
+                        self.pressure.deep_write();
+                        items.push(item.take().unwrap());
+                    }
+                    #[cfg(feature = "compact")]
+                    items.shrink_to_fit();
+                }
+                _ => unreachable!(),
+            }
+            return Box::leak(node);
+        }
+
+        let shift = (levels - depth - 1) * 4;
+        let to_slot = |path: u128| ((path >> shift) & (SLOT_MASK as u128)) as u8;
+        // re-hashed keys already collide on the lower 32 bits of the hash.
+        let deep_write = deep || depth >= LEVELS32;
+
+        let mut node = self.cas.alloc_node('t');
+        match node.as_mut() {
+            Node::Trie { bmp, childs } => {
+                *bmp = 0;
+                childs.clear();
+                let mut entries = entries;
+                while let Some((path, _)) = entries.first() {
+                    let w = to_slot(*path);
+                    let m = entries.iter().take_while(|(p, _)| to_slot(*p) == w).count();
+                    let (group, rest) = entries.split_at_mut(m);
+                    let child = match group {
+                        [(_, item)] => {
+                            if deep_write {
+                                self.pressure.deep_write();
+                            }
+                            Child::Leaf(item.take().unwrap())
+                        }
+                        group => {
+                            let node = self.bulk_node(group, depth + 1, levels, deep);
+                            Child::Deep(In {
+                                node: AtomicPtr::new(node),
+                            })
+                        }
+                    };
+                    let mut child_box = self.cas.alloc_child();
+                    *child_box = child;
+                    childs.push(AtomicPtr::new(Box::leak(child_box)));
+                    *bmp |= 1 << w;
+                    entries = rest;
+                }
+                #[cfg(feature = "compact")]
+                childs.shrink_to_fit();
+            }
+            _ => unreachable!(),
+        }
+        Box::leak(node)
+    }
+}
+
+// Pack the slots of a path into an integer, that orders paths the same way
+// as they are laid out in the trie.
+fn to_path(ws: &[u8]) -> u128 {
+    ws.iter().fold(0, |path, w| (path << 4) | (*w as u128))
+}
+
+// Keep only the last entry for each key, entries for the same key are
+// adjacent, sharing the same path, once sorted by path.
+fn dedup_last<K, V>(
+    entries: Vec<(u128, Option<Item<K, V>>)>,
+) -> Vec<(u128, Option<Item<K, V>>)>
+where
+    K: PartialEq,
+{
+    let mut deduped: Vec<(u128, Option<Item<K, V>>)> = Vec::with_capacity(entries.len());
+    let mut from = 0; // start of entries sharing the last path.
+    for (path, item) in entries.into_iter() {
+        match deduped.last() {
+            Some((last, _)) if *last == path => (),
+            _ => from = deduped.len(),
+        }
+        let key = &item.as_ref().unwrap().key;
+        let off = deduped[from..]
+            .iter()
+            .position(|(_, i)| &i.as_ref().unwrap().key == key);
+        match off {
+            Some(off) => deduped[from + off] = (path, item),
+            None => deduped.push((path, item)),
+        }
+    }
+    deduped
+}
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return a handle to the subtree of keys whose hash share the same
+    /// prefix. `prefix` is matched against the lower `n_bits` of the 32-bit
+    /// hash computed for the key, which is the order in which the trie
+    /// consumes the hash, 4 bits for each level. For example, `n_bits` as 4
+    /// will confine the handle to one child of the root node.
+    ///
+    /// Handles confined to disjoint prefixes, obtained from different clones
+    /// of the map, can operate without interfering with each other.
+    pub fn subtree(
+        &mut self,
+        prefix: u32,
+        n_bits: usize,
+    ) -> Result<Subtree<'_, K, V, H>> {
+        if n_bits > 32 {
+            err_at!(InvalidInput, msg: "n_bits:{} is more than 32", n_bits)
+        } else {
+            let mask = ((1_u64 << n_bits) - 1) as u32;
+            Ok(Subtree {
+                map: self,
+                prefix: prefix & mask,
+                n_bits,
+            })
+        }
+    }
+}
+
+/// Subtree is a handle, into [Map], confined to keys whose hash share
+/// a common prefix. Refer to [Map::subtree] for details.
+pub struct Subtree<'a, K, V, H = DefaultHasher> {
+    map: &'a mut Map<K, V, H>,
+    prefix: u32,
+    n_bits: usize,
+}
+
+impl<'a, K, V, H> Subtree<'a, K, V, H> {
+    /// Return the hash prefix and its number of bits, for this subtree.
+    pub fn to_prefix(&self) -> (u32, usize) {
+        (self.prefix, self.n_bits)
+    }
+
+    /// Return whether `key`'s hash fall under this subtree.
+    pub fn covers<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let hash = self.map.to_hash(key);
+        self.is_prefix_of(hash)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Result<Option<V>>
+    where
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.check_key(key)?;
+        Ok(self.map.get(key))
+    }
+
+    pub fn set(&mut self, key: K, value: V) -> Result<Option<V>>
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+    {
+        self.check_key(&key)?;
+        Ok(self.map.set(key, value))
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Result<Option<V>>
+    where
+        K: Clone,
+        V: Clone,
+        Q: Equivalent<K> + Hash + ?Sized,
+        H: BuildHasher,
+    {
+        self.check_key(key)?;
+        Ok(self.map.remove(key))
+    }
+
+    /// Return the number of items indexed under this subtree. This may not be
+    /// accurate due to concurrent writes. Note that this is a costly operation
+    /// walking through the entire subtree.
+    pub fn len(&self) -> usize
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let seqno = self.map.epoch.load(SeqCst);
+        self.map.enter_epoch(seqno);
+
+        let ws = slots(self.prefix);
+        let (levels, rem) = (self.n_bits / 4, self.n_bits % 4);
+
+        let mut inode = unsafe { self.map.root.load(SeqCst).as_ref().unwrap() };
+        let mut wss = &ws[..levels];
+        let len = loop {
+            let node = unsafe { inode.node.load(SeqCst).as_ref().unwrap() };
+            let (w, bmp, childs) = match (wss.first(), node) {
+                (None, Node::Trie { bmp, childs }) if rem > 0 => {
+                    // partial slot, count all the childs sharing the lower bits.
+                    let (mask, w) = ((1_u8 << rem) - 1, ws[levels]);
+                    let mut len = 0;
+                    for (i, x) in (0..16).filter(|x| (bmp & (1 << x)) != 0).enumerate() {
+                        if (x & mask) == (w & mask) {
+                            let ptr = childs[i].load(SeqCst);
+                            len += self.count_child(unsafe { ptr.as_ref().unwrap() });
+                        }
+                    }
+                    break len;
+                }
+                (None, Node::Tomb { item: Some(item) }) => break self.count_item(item),
+                (None, node) => break node.count(),
+                (Some(w), Node::Trie { bmp, childs }) => (*w, *bmp, childs),
+                (Some(_), Node::Tomb { item: Some(item) }) => {
+                    break self.count_item(item);
+                }
+                (Some(_), Node::Tomb { item: None }) => break 0,
+                (Some(_), Node::List { .. }) => unreachable!(),
+            };
+            wss = &wss[1..];
+
+            inode = match hamming_distance(w, bmp) {
+                (false, _) => break 0,
+                (true, n) => match unsafe { childs[n].load(SeqCst).as_ref() } {
+                    Some(Child::Deep(next_inode)) => next_inode,
+                    Some(Child::Leaf(item)) => break self.count_item(item),
+                    Some(Child::None) | None => unreachable!(),
+                },
+            };
+        };
+
+        self.map.exit_epoch(seqno);
+        len
+    }
+
+    /// Return whether this subtree is empty. Note that this is a costly
+    /// operation walking through the entire subtree.
+    pub fn is_empty(&self) -> bool
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        self.len() == 0
+    }
+
+    /// Return an iterator over entries under this subtree, same as
+    /// [Map::scan_prefix] with this subtree's prefix. Only the subtree is
+    /// walked, and the map instance is pinned to an epoch until the iterator
+    /// is dropped.
+    pub fn iter(&self) -> iter::PrefixIter<'_, K, V, H>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        self.map.prefix_iter(self.prefix, self.n_bits)
+    }
+
+    /// Call `callb` for each {key, value} entry under this subtree, without
+    /// cloning them. Map instance is pinned to an epoch for the entire walk,
+    /// hence `callb` shall not block.
+    pub fn for_each<F>(&self, mut callb: F)
+    where
+        K: Hash,
+        H: BuildHasher,
+        F: FnMut(&K, &V),
+    {
+        let seqno = self.map.pin_epoch();
+        for item in self.map.prefix_walker(self.prefix, self.n_bits) {
+            callb(&item.key, &item.value)
+        }
+        self.map.unpin_epoch(seqno);
+    }
+
+    fn count_child(&self, child: &Child<K, V>) -> usize
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        match child {
+            Child::Leaf(item) => self.count_item(item),
+            Child::Deep(inode) => {
+                unsafe { inode.node.load(SeqCst).as_ref().unwrap() }.count()
+            }
+            Child::None => unreachable!(),
+        }
+    }
+
+    // leaf items can sit above the prefix depth, check their full hash.
+    fn count_item(&self, item: &Item<K, V>) -> usize
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        match self.covers(&item.key) {
+            true => 1,
+            false => 0,
+        }
+    }
+
+    fn is_prefix_of(&self, hash: u32) -> bool {
+        let mask = ((1_u64 << self.n_bits) - 1) as u32;
+        (hash & mask) == self.prefix
+    }
+
+    fn check_key<Q>(&self, key: &Q) -> Result<()>
+    where
+        Q: Hash + ?Sized,
+        H: BuildHasher,
+    {
+        let hash = self.map.to_hash(key);
+        if self.is_prefix_of(hash) {
+            Ok(())
+        } else {
+            let (prefix, n_bits) = (self.prefix, self.n_bits);
+            err_at!(InvalidInput, msg: "hash {:x} not under {:x}/{}", hash, prefix, n_bits)
+        }
+    }
+}
+
+/// Pinned is a guard returned by [Map::get_and_pin], it dereferences to the
+/// value and keeps the map instance pinned to an epoch until dropped.
+///
+/// Garbage left over by instances dropped while this guard is held is handed
+/// over to the surviving instances, and collected after the guard is dropped.
+pub struct Pinned<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    value: &'a V,
+    seqno: u64,
+    since: time::Instant,
+}
+
+impl<'a, K, V, H> Drop for Pinned<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> Deref for Pinned<'a, K, V, H> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+    }
+}
+
+impl<'a, K, V, H> Pinned<'a, K, V, H> {
+    /// Return how long this guard has been holding the epoch. Long held
+    /// guards stall garbage collection for all instances of the map.
+    pub fn elapsed(&self) -> time::Duration {
+        self.since.elapsed()
     }
 }
 
@@ -1643,43 +3899,98 @@ enum CasRc<T> {
     Retry,
 }
 
+// Where to resume Map::do_rewrite() from, after exiting the epoch.
+enum Rewrite<K> {
+    // walk order to resume from.
+    At(u64),
+    // collision group at walk order, to be rewritten per key.
+    Group(u32, Vec<K>),
+}
+
 struct CasOp<'a, K, V> {
-    epoch: &'a Arc<AtomicU64>,
+    epoch: &'a AtomicU64,
     inode: &'a In<K, V>,
     old: *mut Node<K, V>,
     cas: &'a mut Cas<K, V>,
 }
 
-#[derive(PartialEq, Debug)]
-enum Distance {
-    Set(usize),    // found
-    Insert(usize), // not found
-}
-
-fn hamming_distance(w: u8, bmp: u16) -> Distance {
+// Executed at every level of the trie, for every operation. Index of the
+// child for slot `w` is the number of bits set below `w`, counted with a
+// single popcount of the masked bitmap, refer to README on enabling the
+// popcnt instruction. Return whether slot `w` is set, along with the index,
+// where the child is found if set, or shall be inserted otherwise. Both are
+// computed without branching, callers match on the pair.
+#[inline]
+pub(crate) fn hamming_distance(w: u8, bmp: u16) -> (bool, usize) {
     let posn = 1_u16 << w;
-    let mask: u16 = !(posn - 1);
+    let dist = (bmp & posn.wrapping_sub(1)).count_ones() as usize;
 
-    let (x, y) = ((bmp & mask), bmp);
-    let dist = (x ^ y).count_ones() as usize;
+    ((bmp & posn) != 0, dist)
+}
 
-    match bmp & posn {
-        0 => Distance::Insert(dist),
-        _ => Distance::Set(dist),
-    }
+pub(crate) fn key_to_hash32<K, H>(key: &K, mut hasher: H) -> u32
+where
+    K: Hash + ?Sized,
+    H: Hasher,
+{
+    key.hash(&mut hasher);
+    fold_hash32(hasher.finish())
 }
 
-fn key_to_hash32<K, H>(key: &K, mut hasher: H) -> u32
+// Hash the key after prefixing it with a marker, for upper 64 bits of the
+// 128-bit hash, so that the two halves differ even for identity like
+// hashers.
+//
+// Marker protocol: with HashWidth::Bits128, and only then, a fresh hasher is
+// fed a single write_u8(0x80) before the key is hashed, and its finish() is
+// taken as the upper 64 bits. Hashers that accept only whole keys in write(),
+// like U32Hasher, U64Hasher and U128Hasher, override write_u8() to consume
+// the marker instead, either ignoring it, when the key fit in the lower half
+// as is, or switching to the upper half of the key.
+fn key_to_upper64<K, H>(key: &K, mut hasher: H) -> u64
 where
     K: Hash + ?Sized,
     H: Hasher,
 {
+    hasher.write_u8(0x80);
     key.hash(&mut hasher);
-    let code: u64 = hasher.finish();
+    hasher.finish()
+}
+
+#[inline]
+fn fold_hash32(code: u64) -> u32 {
     (((code >> 32) ^ code) & 0xFFFFFFFF) as u32
 }
 
-fn slots(key: u32) -> [u8; 8] {
+// Fold the 64-bit hash into the lower 32 bits, same as fold_hash32(), and
+// keep the upper 32 bits as is.
+#[inline]
+fn fold_hash64(code: u64) -> u64 {
+    (code & 0xFFFFFFFF00000000) | (fold_hash32(code) as u64)
+}
+
+// Re-hash the key by hashing it twice, into the same hasher, and fold the
+// 64-bit hash into 32 bits. Unlike key_to_upper64() no marker is written,
+// so hashers that only accept the key type can be used as well.
+pub(crate) fn key_to_rehash32<K, H>(key: &K, mut hasher: H) -> u32
+where
+    K: Hash + ?Sized,
+    H: Hasher,
+{
+    key.hash(&mut hasher);
+    key.hash(&mut hasher);
+    fold_hash32(hasher.finish())
+}
+
+pub(crate) fn slots128(key: u128) -> [u8; MAX_LEVELS] {
+    let mut arr = [0_u8; MAX_LEVELS];
+    for (i, item) in arr.iter_mut().enumerate() {
+        *item = ((key >> (i * 4)) & (SLOT_MASK as u128)) as u8;
+    }
+    arr
+}
+
+pub(crate) fn slots(key: u32) -> [u8; 8] {
     let mut arr = [0_u8; 8];
     for (i, item) in arr.iter_mut().enumerate() {
         *item = ((key >> (i * 4)) & SLOT_MASK) as u8;
@@ -1687,24 +3998,25 @@ fn slots(key: u32) -> [u8; 8] {
     arr
 }
 
-fn subtrie_zip<'a>(hash: u32, wss: &[u8], out: &'a mut [(u8, u8); 8]) -> &'a [(u8, u8)] {
-    let ls = slots(hash);
+fn subtrie_zip<'a>(
+    wss: &[u8],
+    lss: &[u8],
+    out: &'a mut [(u8, u8); MAX_LEVELS],
+) -> &'a [(u8, u8)] {
     let n = wss.len();
-    let off = 8 - n;
     for i in 0..n {
-        out[i] = (wss[i], ls[off + i])
+        out[i] = (wss[i], lss[i])
     }
     &out[..n]
 }
 
 fn get_from_list<'a, K, V, Q>(key: &Q, items: &'a [Item<K, V>]) -> Option<&'a V>
 where
-    K: Borrow<Q>,
-    Q: PartialEq + ?Sized,
+    Q: Equivalent<K> + ?Sized,
 {
     items
         .iter()
-        .find(|x| x.key.borrow() == key)
+        .find(|x| key.equivalent(&x.key))
         .map(|x| &x.value)
 }
 
@@ -1728,16 +4040,81 @@ where
 
 fn has_key<K, V, Q>(items: &[Item<K, V>], key: &Q) -> Option<usize>
 where
-    K: Borrow<Q>,
-    Q: PartialEq + ?Sized,
+    Q: Equivalent<K> + ?Sized,
 {
     items
         .iter()
         .enumerate()
-        .find(|(_, x)| x.key.borrow() == key)
+        .find(|(_, x)| key.equivalent(&x.key))
         .map(|res| res.0)
 }
 
+/// Garbage collection and operation statistics, refer to [Map::gc_stats].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct GcStats {
+    /// Number of CAS operations that failed and had to be retried.
+    pub n_retries: usize,
+    /// Number of compactions, after removing an entry.
+    pub n_compacts: usize,
+    /// Number of times set operations were retried, either due to a CAS
+    /// failure or due to a tomb left behind by a concurrent remove. Note
+    /// that get operations never retry.
+    pub n_set_retries: usize,
+    /// Number of times remove operations were retried, same as
+    /// `n_set_retries`.
+    pub n_remove_retries: usize,
+    /// Number of times compactions were retried, a high count relative to
+    /// `n_compacts` implies contention on the tomb nodes left behind by
+    /// removes, rather than write-write conflicts on entries.
+    pub n_compact_retries: usize,
+    /// Number of allocations, that could not be served from the pools.
+    pub n_allocs: usize,
+    /// Number of allocations released, that could not be held in pools.
+    pub n_frees: usize,
+    /// Number of allocations held in pools, for reuse.
+    pub n_pooled: usize,
+    /// Number of write operations pending garbage collection.
+    pub n_reclaims: usize,
+    /// Number of allocations pending garbage collection.
+    pub n_garbage: usize,
+    /// Time taken by garbage collection passes, that had pending reclaims.
+    pub gc_latency: Histogram,
+    /// Time between logical removal of entries and physical free of their
+    /// memory. A long tail implies readers stalling garbage collection.
+    pub reclaim_age: Histogram,
+}
+
+/// Bytes held by a map, refer to [Map::footprint].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Footprint {
+    /// Bytes held by trie nodes, including their array of child pointers.
+    pub trie_bytes: usize,
+    /// Bytes held by collision lists, including their items.
+    pub list_bytes: usize,
+    /// Bytes held by child slots, including leaf items.
+    pub child_bytes: usize,
+    /// Bytes held on the heap by keys and values, as computed by the
+    /// callback supplied to [Map::footprint].
+    pub heap_bytes: usize,
+    /// Bytes held in pools, for reuse.
+    pub pool_bytes: usize,
+    /// Bytes pending garbage collection, excluding the heap held by keys and
+    /// values.
+    pub reclaim_bytes: usize,
+}
+
+impl Footprint {
+    /// Return the total bytes held by the map.
+    pub fn total(&self) -> usize {
+        self.trie_bytes
+            + self.list_bytes
+            + self.child_bytes
+            + self.heap_bytes
+            + self.pool_bytes
+            + self.reclaim_bytes
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Stats {
     pub n_nodes: usize,
@@ -71,8 +71,8 @@ fn test_hamming_distance() {
         let dist = hamming_distance(w, bmp);
         let o = ((w % 16) / 2) as usize;
         match w % 2 {
-            0 => assert_eq!(dist, Distance::Insert(o)),
-            1 => assert_eq!(dist, Distance::Set(o)),
+            0 => assert_eq!(dist, (false, o)),
+            1 => assert_eq!(dist, (true, o)),
             _ => unreachable!(),
         }
     }
@@ -82,8 +82,8 @@ fn test_hamming_distance() {
         let o = ((w % 16) / 2) as usize;
         let dist = hamming_distance(w, bmp);
         match w % 2 {
-            0 => assert_eq!(dist, Distance::Set(o)),
-            1 => assert_eq!(dist, Distance::Insert(o + 1)),
+            0 => assert_eq!(dist, (true, o)),
+            1 => assert_eq!(dist, (false, o + 1)),
             _ => unreachable!(),
         }
     }
@@ -101,6 +101,17 @@ fn test_print_sizing() {
     println!("test_print_sizing {:p} {:p}", &vs[0], &vs[1]);
 }
 
+#[test]
+fn test_cache_padded() {
+    // epoch slots are written by every instance.
+    assert_eq!(mem::align_of::<Slot>() % 64, 0);
+    #[cfg(feature = "cache-padded")]
+    {
+        assert_eq!(mem::align_of::<In<u32, u64>>(), 64);
+        assert_eq!(mem::size_of::<Child<u32, u64>>() % 64, 0);
+    }
+}
+
 #[test]
 fn test_map() {
     let seed: u128 =
@@ -115,16 +126,49 @@ fn test_map() {
         cmp::min(key_max, n)
     };
     let gc_period = [0, 1, 16, 32, 256, 1024][rng.gen::<usize>() % 6];
-    let modul = key_max / n_threads;
+    println!("test_map seed:{}", seed);
+
+    let width = HashWidth::default();
+    map_with(seed, key_max, n_ops, n_threads, gc_period, width);
+}
+
+#[test]
+fn test_map_hash_width() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let key_max = [Ky::MAX, 256, 1024][rng.gen::<usize>() % 3];
+    let n_threads = cmp::min(key_max, [1, 2, 4, 8][rng.gen::<usize>() % 4]);
+    let gc_period = [0, 1, 16, 256][rng.gen::<usize>() % 4];
+    println!("test_map_hash_width seed:{}", seed);
+
+    for width in [
+        HashWidth::Bits32,
+        HashWidth::Bits64,
+        HashWidth::Bits128,
+        HashWidth::Rehash,
+    ] {
+        map_with(seed, key_max, 20_000, n_threads, gc_period, width);
+    }
+}
 
+fn map_with(
+    seed: u128,
+    key_max: Ky,
+    n_ops: usize,
+    n_threads: Ky,
+    gc_period: usize,
+    width: HashWidth,
+) {
+    let modul = key_max / n_threads;
     println!(
-        "test_map seed:{} key_max:{} ops:{} threads:{} modul:{}",
-        seed, key_max, n_ops, n_threads, modul
+        "map_with key_max:{} ops:{} threads:{} modul:{} width:{:?}",
+        key_max, n_ops, n_threads, modul, width
     );
 
     let mut map: Map<Ky, u64> = {
         let hash_builder = DefaultHasher::new();
-        Map::new(n_threads as usize + 1, hash_builder)
+        Map::with_hash_width(n_threads as usize + 1, hash_builder, width)
     };
     map.set_gc_period(gc_period);
     map.print_sizing();
@@ -150,9 +194,9 @@ fn test_map() {
     }
 
     let ln = map.len();
-    println!("test_map len {}", ln);
+    println!("map_with len {}", ln);
     assert_eq!(ln, btmap.len());
-    println!("test_map Validate .... {:?}", map.validate());
+    println!("map_with Validate .... {:?}", map.validate());
 
     // map.print();
 
@@ -160,6 +204,377 @@ fn test_map() {
     mem::drop(btmap);
 }
 
+#[test]
+fn test_is_empty() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [1, 16, 1000, 100_000][rng.gen::<usize>() % 4];
+    println!("test_is_empty seed:{} n_keys:{}", seed, n_keys);
+
+    let mut map: Map<Ky, u64> = Map::new(1, DefaultHasher::new());
+    assert!(map.is_empty());
+
+    let mut keys: Vec<Ky> = (0..n_keys).map(|_| rng.gen()).collect();
+    for key in keys.iter() {
+        map.set(*key, 0);
+        assert!(!map.is_empty());
+    }
+
+    keys.sort_unstable();
+    keys.dedup();
+    while let Some(key) = keys.pop() {
+        assert!(!map.is_empty());
+        map.remove(&key);
+        assert_eq!(map.is_empty(), keys.is_empty(), "key {}", key);
+    }
+    assert_eq!(map.len(), 0);
+    map.validate();
+}
+
+#[test]
+fn test_is_empty_concurrent() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4, 8][rng.gen::<usize>() % 4];
+    let n_keys = [16, 1000, 100_000][rng.gen::<usize>() % 3];
+    println!(
+        "test_is_empty_concurrent seed:{} threads:{} n_keys:{}",
+        seed, n_threads, n_keys
+    );
+
+    let mut map: Map<Ky, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    for key in 0..n_keys {
+        map.set(key, key as u64);
+    }
+    assert!(!map.is_empty());
+
+    // each thread drains its own share of keys.
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let h = thread::spawn(move || {
+            for key in (0..n_keys).filter(|k| (*k as usize) % n_threads == id) {
+                assert_eq!(map.remove(&key), Some(key as u64), "key {}", key);
+            }
+        });
+        handles.push(h);
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap();
+    }
+
+    assert!(map.is_empty());
+    map.flush_gc();
+    assert!(map.is_empty());
+    map.shrink_to_fit();
+    assert!(map.is_empty());
+    assert_eq!(map.len(), 0);
+    map.validate();
+}
+
+#[test]
+fn test_multimap() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let n_threads = [1, 2, 4, 8][rng.gen::<usize>() % 4];
+    let (n_keys, n_values) = (64_u32, 100_u64);
+    println!("test_multimap seed:{} threads:{}", seed, n_threads);
+
+    let mut map: Map<Ky, Vec<u64>> = Map::new(n_threads + 1, DefaultHasher::new());
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let h = thread::spawn(move || {
+            for value in 0..n_values {
+                for key in 0..n_keys {
+                    map.append(key, (id as u64 * n_values) + value);
+                }
+            }
+        });
+        handles.push(h);
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap();
+    }
+
+    for key in 0..n_keys {
+        let mut values = map.get_all(&key);
+        assert_eq!(values.len(), n_threads * (n_values as usize), "key {}", key);
+        values.sort_unstable();
+        let refs: Vec<u64> = (0..(n_threads as u64 * n_values)).collect();
+        assert_eq!(values, refs, "key {}", key);
+    }
+    assert!(map.get_all(&n_keys).is_empty());
+
+    for key in 0..n_keys {
+        assert!(!map.remove_value(&key, &u64::MAX));
+        let mut values = map.get_all(&key);
+        while let Some(value) = values.pop() {
+            assert!(
+                map.remove_value(&key, &value),
+                "key {} value {}",
+                key,
+                value
+            );
+            assert_eq!(map.get_all(&key).len(), values.len());
+        }
+        assert!(!map.remove_value(&key, &0));
+        assert_eq!(map.get(&key), None);
+    }
+
+    assert_eq!(map.len(), 0);
+    map.validate();
+}
+
+#[test]
+fn test_multimap_concurrent() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    let (n_keys, n_values) = (16_u32, 1000_u64);
+    println!(
+        "test_multimap_concurrent seed:{} threads:{}",
+        seed, n_threads
+    );
+
+    let map: Map<Ky, Vec<u64>> = Map::new((n_threads * 2) + 1, DefaultHasher::new());
+
+    // for every writer appending to a key, a reader removes the same values
+    // from the same key, in the same order, the list is frequently brought
+    // down to its last value and removed while other writers append to it.
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut writer = map.clone();
+        let h = thread::spawn(move || {
+            for value in 0..n_values {
+                for key in 0..n_keys {
+                    writer.append(key, (id as u64 * n_values) + value);
+                }
+            }
+        });
+        handles.push(h);
+
+        let mut reader = map.clone();
+        let h = thread::spawn(move || {
+            for value in 0..n_values {
+                for key in 0..n_keys {
+                    let value = (id as u64 * n_values) + value;
+                    while !reader.remove_value(&key, &value) {
+                        thread::yield_now()
+                    }
+                }
+            }
+        });
+        handles.push(h);
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap();
+    }
+
+    for key in 0..n_keys {
+        assert_eq!(map.get(&key), None, "key {}", key);
+    }
+    assert_eq!(map.len(), 0);
+    map.validate();
+}
+
+#[test]
+fn test_subtree() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let n_keys = [0, 1, 16, 1000, 100_000][rng.gen::<usize>() % 5];
+    println!("test_subtree seed:{} keys:{}", seed, n_keys);
+
+    let mut map: Map<Ky, u64> = Map::new(1, DefaultHasher::new());
+    for _i in 0..n_keys {
+        map.set(rng.gen_range(1000, Ky::MAX), rng.gen());
+    }
+    let len = map.len();
+
+    assert!(map.subtree(0, 33).is_err());
+    assert_eq!(map.subtree(0, 0).unwrap().len(), len);
+
+    for n_bits in [1, 3, 4, 6, 8].iter() {
+        let total: usize = (0..(1_u32 << n_bits))
+            .map(|prefix| map.subtree(prefix, *n_bits).unwrap().len())
+            .sum();
+        assert_eq!(total, len, "n_bits {}", n_bits);
+    }
+
+    let mut keys: Vec<Ky> = vec![];
+    for prefix in 0..(1_u32 << 6) {
+        let subtree = map.subtree(prefix, 6).unwrap();
+        let items: Vec<(Ky, u64)> = subtree.iter().collect();
+        assert_eq!(items.len(), subtree.len(), "prefix {:x}", prefix);
+        let mut n = 0;
+        subtree.for_each(|key, value| {
+            assert_eq!(items[n], (*key, *value), "prefix {:x}", prefix);
+            n += 1;
+        });
+        assert_eq!(n, items.len(), "prefix {:x}", prefix);
+        for (key, value) in items.into_iter() {
+            assert!(subtree.covers(&key), "prefix {:x} key {}", prefix, key);
+            assert_eq!(subtree.get(&key).unwrap(), Some(value));
+            keys.push(key);
+        }
+    }
+    assert_eq!(keys.len(), len);
+
+    let mut subtree = map.subtree(0x5, 4).unwrap();
+    for key in 0..1000 {
+        let value = key as u64;
+        match subtree.covers(&key) {
+            true => {
+                subtree.set(key, value).unwrap();
+                assert_eq!(subtree.get(&key).unwrap(), Some(value));
+                assert_eq!(subtree.remove(&key).unwrap(), Some(value));
+            }
+            false => {
+                assert!(subtree.set(key, value).is_err());
+                assert!(subtree.get(&key).is_err());
+                assert!(subtree.remove(&key).is_err());
+            }
+        }
+    }
+    assert_eq!(map.len(), len);
+}
+
+#[test]
+fn test_reserve() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_reserve seed:{}", seed);
+
+    let mut map: Map<Ky, u64> = Map::new(1, DefaultHasher::new());
+    map.set_gc_period(usize::MAX).reserve(1024);
+
+    let n_allocs = map.validate().n_allocs;
+    for _i in 0..100 {
+        map.set(rng.gen(), rng.gen());
+    }
+    assert_eq!(map.validate().n_allocs, n_allocs);
+}
+
+#[test]
+fn test_shrink_to_fit() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let n_keys = [10, 1000, 100_000][rng.gen::<usize>() % 3];
+    println!("test_shrink_to_fit seed:{} keys:{}", seed, n_keys);
+
+    let mut map: Map<Ky, u64> = Map::new(1, DefaultHasher::new());
+    let keys: Vec<Ky> = (0..n_keys).collect();
+    for key in keys.iter() {
+        map.set(*key, rng.gen());
+    }
+    for key in keys.iter().skip(1) {
+        map.remove(key);
+    }
+    assert!(map.validate().n_pools > 0);
+
+    map.shrink_to_fit();
+    let stats = map.validate();
+    assert_eq!(stats.n_pools, 0);
+    assert_eq!(map.len(), 1);
+    assert!(map.get(&keys[0]).is_some());
+}
+
+#[test]
+fn test_get_and_pin() {
+    let mut map: Map<Ky, u64> = Map::new(2, DefaultHasher::new());
+    map.set_gc_period(0);
+    for key in 0..1000 {
+        map.set(key, key as u64);
+    }
+    let is_pinned =
+        |map: &Map<Ky, u64>| (map.access_log[map.id].load(SeqCst) & ENTER_MASK) > 0;
+
+    {
+        assert!(map.get_and_pin(&1000).is_none());
+        assert!(!is_pinned(&map));
+
+        let a = map.get_and_pin(&10).unwrap();
+        let b = map.get_and_pin(&20).unwrap();
+        assert_eq!(map.get(&30), Some(30));
+        assert!(is_pinned(&map));
+        mem::drop(a);
+        assert!(is_pinned(&map));
+        assert_eq!(*b, 20);
+        mem::drop(b);
+        assert!(!is_pinned(&map));
+    }
+
+    // concurrent writer shall not reclaim the pinned value.
+    let mut writer = map.clone();
+    let value = map.get_and_pin(&10).unwrap();
+    let h = thread::spawn(move || {
+        for i in 0..1000 {
+            writer.set(10, i + 1000);
+        }
+        writer
+    });
+    let writer = h.join().unwrap();
+    assert_eq!(*value, 10);
+    assert!(value.elapsed() > time::Duration::from_nanos(0));
+    mem::drop(value);
+    mem::drop(writer);
+
+    assert_eq!(map.get(&10), Some(1999));
+}
+
+#[test]
+fn test_shared_pins() {
+    let seed: u128 = random();
+    let n_threads = [2, 4, 8][(seed % 3) as usize];
+    println!("test_shared_pins seed:{} n_threads:{}", seed, n_threads);
+
+    let mut map: Map<u64, String> = Map::new(2, DefaultHasher::new());
+    map.set_gc_period(0);
+    for key in 0..100 {
+        map.set(key, key.to_string());
+    }
+    let is_pinned =
+        |map: &Map<u64, String>| (map.access_log[map.id].load(SeqCst) & ENTER_MASK) > 0;
+
+    // a shared reader, pinned and unpinned from several threads, shall stay
+    // in the epoch as long as any of its pins is alive.
+    let reader = map.clone();
+    let writer = thread::spawn(move || {
+        for i in 0..20_000_u64 {
+            map.set(i % 100, (i % 100).to_string());
+        }
+        map
+    });
+    thread::scope(|s| {
+        for _id in 0..n_threads {
+            s.spawn(|| {
+                for i in 0..2_000_u64 {
+                    let key = i % 100;
+                    let pinned = reader.get_and_pin(&key).unwrap();
+                    assert!(is_pinned(&reader));
+                    assert_eq!(
+                        reader.get(&((key + 1) % 100)),
+                        Some(((key + 1) % 100).to_string())
+                    );
+                    assert_eq!(*pinned, key.to_string());
+                }
+            });
+        }
+    });
+    let mut map = writer.join().unwrap();
+
+    assert_eq!(reader.n_pins.load(SeqCst), 0);
+    assert!(!is_pinned(&reader));
+    mem::drop(reader);
+    map.flush_gc();
+    map.validate();
+}
+
 fn with_btreemap(
     id: Ky,
     seed: u128,
@@ -282,3 +697,1164 @@ fn merge_btmap(items: [BTreeMap<Ky, u64>; 2]) -> BTreeMap<Ky, u64> {
     }
     one
 }
+
+#[test]
+fn test_std_hasher() {
+    use std::collections::hash_map::RandomState;
+
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_std_hasher seed:{}", seed);
+
+    let mut map: Map<u64, u64, RandomState> = Map::new(1, RandomState::new());
+    let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let (key, value) = (rng.gen::<u64>() % 5000, rng.gen::<u64>());
+        map.set(key, value);
+        btmap.insert(key, value);
+    }
+
+    let hash = key_to_hash32(&10_u64, map.hasher().build_hasher());
+    assert_eq!(hash, map.to_hash(&10_u64));
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value));
+    }
+    assert_eq!(map.len(), btmap.len());
+    map.validate();
+}
+
+// Hash lower 32 bits of u64 keys into 16 groups, spread them by the upper 32
+// bits of the hash. Hashing the key again spreads it by all the 64 bits.
+#[derive(Clone, Default)]
+struct WideHasher {
+    code: u64,
+    n: usize,
+}
+
+impl Hasher for WideHasher {
+    fn finish(&self) -> u64 {
+        self.code
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!()
+    }
+
+    fn write_u8(&mut self, _marker: u8) {}
+
+    fn write_u64(&mut self, k: u64) {
+        let k = k & 0xFFFFFFFF;
+        self.code = match self.n {
+            0 => (k << 32) | (k ^ (k % 16)),
+            _ => self.code ^ k.wrapping_mul(0x9E3779B97F4A7C15),
+        };
+        self.n += 1;
+    }
+}
+
+impl BuildHasher for WideHasher {
+    type Hasher = WideHasher;
+
+    fn build_hasher(&self) -> WideHasher {
+        WideHasher::default()
+    }
+}
+
+#[test]
+fn test_hash_width() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_hash_width seed:{}", seed);
+
+    let widths = [
+        HashWidth::Bits32,
+        HashWidth::Rehash,
+        HashWidth::Bits64,
+        HashWidth::Bits128,
+    ];
+    for width in widths {
+        let mut map: Map<u64, u64, WideHasher> =
+            Map::with_hash_width(1, WideHasher::default(), width);
+        assert_eq!(map.hash_width(), width);
+
+        let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+        for _i in 0..20_000 {
+            let key = rng.gen::<u64>() % 2000;
+            match rng.gen::<u8>() % 4 {
+                0 => assert_eq!(map.remove(&key), btmap.remove(&key), "key {}", key),
+                _ => {
+                    let value = rng.gen::<u64>();
+                    assert_eq!(map.set(key, value), btmap.insert(key, value));
+                }
+            }
+        }
+
+        assert_eq!(map.to_hash(&17_u64), 1);
+        assert_eq!(map.to_trie_hash(&17_u64) as u32, 1);
+        for (key, value) in btmap.iter() {
+            assert_eq!(map.get(key), Some(*value), "key {}", key);
+        }
+        assert_eq!(map.len(), btmap.len());
+
+        map.set_repin_period(7);
+        let mut items: Vec<(u64, u64)> = map.iter().collect();
+        items.sort_unstable();
+        assert_eq!(items, btmap.clone().into_iter().collect::<Vec<_>>());
+
+        let (mut items, mut token) = (vec![], Some(0));
+        while let Some(from) = token {
+            let (batch, next) = map.scan_from(from, 10);
+            items.extend(batch);
+            token = next;
+        }
+        items.sort_unstable();
+        assert_eq!(items, btmap.clone().into_iter().collect::<Vec<_>>());
+
+        let stats = map.validate();
+        match width {
+            HashWidth::Bits32 => assert!(stats.n_lists > 0),
+            _ => assert_eq!(stats.n_lists, 0),
+        }
+    }
+}
+
+#[test]
+fn test_trie_hash() {
+    let hb = DefaultHasher::with_seed(random());
+    let maps: Vec<Map<u64, u64>> =
+        [HashWidth::Bits32, HashWidth::Bits64, HashWidth::Bits128]
+            .iter()
+            .map(|width| Map::with_hash_width(1, hb.clone(), *width))
+            .collect();
+    for key in 0..1000_u64 {
+        let hash32 = key_to_hash32(&key, hb.build_hasher());
+        let hashes: Vec<u128> = maps.iter().map(|m| m.to_trie_hash(&key)).collect();
+        assert_eq!(hashes[0], hash32 as u128);
+        assert_eq!(hashes[1] as u32, hash32);
+        assert_eq!(hashes[2] as u64, hashes[1] as u64);
+        assert_ne!((hashes[2] >> 64) as u64, hashes[1] as u64);
+        assert_eq!(maps[0].hash_key(&key), hb.hash_one(key));
+    }
+}
+
+#[test]
+fn test_hashed() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_hashed seed:{}", seed);
+
+    for width in [HashWidth::Bits32, HashWidth::Bits128, HashWidth::Rehash] {
+        let mut map: Map<u64, u64, WideHasher> =
+            Map::with_hash_width(1, WideHasher::default(), width);
+        let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+        for _i in 0..10_000 {
+            let key = rng.gen::<u64>() % 1000;
+            let hash = map.hash_key(&key);
+            match rng.gen::<u8>() % 4 {
+                0 => assert_eq!(map.remove_hashed(hash, &key), btmap.remove(&key)),
+                1 => assert_eq!(map.get_hashed(hash, &key), btmap.get(&key).cloned()),
+                _ => {
+                    let value = rng.gen::<u64>();
+                    assert_eq!(
+                        map.set_hashed(hash, key, value),
+                        btmap.insert(key, value)
+                    );
+                }
+            }
+        }
+        for (key, value) in btmap.iter() {
+            assert_eq!(map.get(key), Some(*value), "key {}", key);
+        }
+        assert_eq!(map.len(), btmap.len());
+        map.validate();
+    }
+}
+
+struct KeyRef<'a>(u64, &'a str);
+
+impl<'a> Hash for KeyRef<'a> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        (self.0, self.1).hash(state)
+    }
+}
+
+impl<'a> Equivalent<(u64, String)> for KeyRef<'a> {
+    fn equivalent(&self, key: &(u64, String)) -> bool {
+        self.0 == key.0 && self.1 == key.1
+    }
+}
+
+#[test]
+fn test_equivalent() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_equivalent seed:{}", seed);
+
+    let mut map: Map<(u64, String), u64> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<(u64, String), u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let (n, s) = (rng.gen::<u64>() % 100, (rng.gen::<u8>() % 10).to_string());
+        let key = KeyRef(n, &s);
+        match rng.gen::<u8>() % 4 {
+            0 => assert_eq!(map.remove(&key), btmap.remove(&(n, s.clone()))),
+            1 => {
+                let hash = map.hash_key(&key);
+                assert_eq!(map.hash_key(&(n, s.clone())), hash);
+                let val = btmap.get(&(n, s.clone())).cloned();
+                assert_eq!(map.get_hashed(hash, &key), val);
+                assert_eq!(map.get(&key), val);
+            }
+            _ => {
+                let value = rng.gen::<u64>();
+                assert_eq!(map.set((n, s.clone()), value), btmap.insert((n, s), value));
+            }
+        }
+    }
+    assert_eq!(map.len(), btmap.len());
+    map.validate();
+}
+
+#[test]
+fn test_collision_stats() {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    // WideHasher collide keys on 32-bit hash into 16 groups.
+    let n_keys: u64 = 1000;
+    let n_groups = 16;
+    let max_list_len = n_keys.div_ceil(n_groups) as usize;
+    let threshold = 10;
+
+    for width in [HashWidth::Bits32, HashWidth::Bits64, HashWidth::Rehash] {
+        let mut map: Map<u64, u64, WideHasher> =
+            Map::with_hash_width(2, WideHasher::default(), width);
+        assert_eq!(map.collision_stats(), CollisionStats::default());
+
+        let fired = Arc::new(AtomicUsize::new(0));
+        {
+            let fired = Arc::clone(&fired);
+            map.on_collision_pressure(threshold, move |stats| {
+                assert!(stats.max_list_len > threshold, "{:?}", stats);
+                fired.fetch_add(1, SeqCst);
+            });
+        }
+
+        let mut clone = map.clone();
+        for key in 0..n_keys {
+            clone.set(key, key + 1);
+        }
+        // updates are deep writes as well.
+        for key in 0..n_keys {
+            map.set(key, key + 2);
+        }
+
+        let stats = map.collision_stats();
+        println!("test_collision_stats {:?} {:?}", width, stats);
+        assert_eq!(stats, clone.collision_stats());
+        assert_eq!(stats.n_deep_writes, (n_keys * 2) - n_groups);
+        match width {
+            HashWidth::Bits32 => {
+                assert_eq!(stats.max_list_len, max_list_len);
+                assert_eq!(fired.load(SeqCst), max_list_len - threshold);
+            }
+            _ => {
+                assert_eq!(stats.max_list_len, 0);
+                assert_eq!(fired.load(SeqCst), 0);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_gc_stats() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    println!("test_gc_stats seed:{} n_threads:{}", seed, n_threads);
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    // root node is the only allocation.
+    let gc = GcStats {
+        n_allocs: 1,
+        ..GcStats::default()
+    };
+    assert_eq!(map.gc_stats(), gc);
+
+    let (mut n_removes, n_ops) = (0, 10_000);
+    for _i in 0..n_ops {
+        let key = rng.gen::<u64>() % 1000;
+        match rng.gen::<u8>() % 3 {
+            0 => n_removes += map.remove(&key).map(|_| 1).unwrap_or(0),
+            _ => {
+                map.set(key, key);
+            }
+        }
+    }
+    let gc = map.gc_stats();
+    println!("test_gc_stats {:?}", gc);
+    // single thread writes never fail their CAS.
+    assert_eq!(gc.n_retries, 0);
+    assert!(
+        gc.n_compacts <= n_removes,
+        "{} {}",
+        gc.n_compacts,
+        n_removes
+    );
+    let stats = map.validate();
+    assert_eq!(gc.n_allocs, stats.n_allocs);
+    assert_eq!(gc.n_frees, stats.n_frees);
+    assert_eq!(
+        gc.n_allocs - gc.n_frees,
+        stats.n_nodes + stats.n_childs + gc.n_pooled + gc.n_reclaims + gc.n_garbage
+    );
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            for _i in 0..n_ops {
+                let key = rng.gen::<u64>() % 100;
+                match rng.gen::<u8>() % 2 {
+                    0 => map.remove(&key),
+                    _ => map.set(key, key),
+                };
+            }
+            map.gc_stats()
+        }));
+    }
+    let mut n_retries = 0;
+    for handle in handles.into_iter() {
+        n_retries = cmp::max(n_retries, handle.join().unwrap().n_retries);
+    }
+    // counters of dropped clones are accumulated.
+    let gc = map.gc_stats();
+    println!("test_gc_stats {:?}", gc);
+    assert!(gc.n_retries >= n_retries, "{} {}", gc.n_retries, n_retries);
+    assert_eq!(gc.n_allocs, map.validate().n_allocs);
+}
+
+#[test]
+fn test_pool_size() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    let (size, spill) = match rng.gen::<u8>() % 3 {
+        0 => (0, Spill::Drop),
+        1 => (0, Spill::Shared(64)),
+        _ => (16, Spill::Shared(64)),
+    };
+    println!(
+        "test_pool_size seed:{} n_threads:{} size:{} spill:{:?}",
+        seed, n_threads, size, spill
+    );
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    map.set_pool_size(size, spill);
+    map.reserve(1000);
+    let n = map.gc_stats().n_pooled;
+    assert!(n <= size * 5, "{} {}", n, size);
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            for _i in 0..10_000 {
+                let key = rng.gen::<u64>() % 1000;
+                match rng.gen::<u8>() % 2 {
+                    0 => map.remove(&key),
+                    _ => map.set(key, key),
+                };
+            }
+        }));
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap();
+    }
+
+    let gc = map.gc_stats();
+    println!("test_pool_size {:?}", gc);
+    let max_pooled = match spill {
+        Spill::Drop => size * 5,
+        Spill::Shared(n) => (size + n) * 5,
+    };
+    assert!(gc.n_pooled <= max_pooled, "{} {}", gc.n_pooled, max_pooled);
+    // validate() checks the memory accounting, including the shared pool.
+    map.validate();
+}
+
+#[test]
+fn test_spawn_gc() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    let spill = [Spill::Drop, Spill::Shared(64)][rng.gen::<usize>() % 2];
+    println!(
+        "test_spawn_gc seed:{} n_threads:{} spill:{:?}",
+        seed, n_threads, spill
+    );
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    map.set_pool_size(0, spill);
+    map.spawn_gc(time::Duration::from_millis(1));
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            let mut btmap = BTreeMap::new();
+            for _i in 0..10_000 {
+                // keys are disjoint across threads.
+                let key = ((rng.gen::<u64>() % 1000) * 8) + (id as u64);
+                match rng.gen::<u8>() % 2 {
+                    0 => assert_eq!(map.remove(&key), btmap.remove(&key)),
+                    _ => {
+                        assert_eq!(map.set(key, id as u64), btmap.insert(key, id as u64))
+                    }
+                };
+            }
+            btmap
+        }));
+    }
+    let mut btmap = BTreeMap::new();
+    for handle in handles.into_iter() {
+        btmap.extend(handle.join().unwrap());
+    }
+
+    map.stop_gc();
+    assert_eq!(map.len(), btmap.len());
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value), "key:{}", key);
+    }
+    let gc = map.gc_stats();
+    println!("test_spawn_gc {:?}", gc);
+    assert!(gc.n_frees > 0, "{:?}", gc);
+    map.validate();
+}
+
+#[test]
+fn test_gc_latency() {
+    let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+    map.set_gc_period(1_000_000);
+    let gc = map.gc_stats();
+    assert_eq!(gc.gc_latency.count(), 0);
+    assert_eq!(gc.reclaim_age.max(), time::Duration::ZERO);
+
+    for key in 0..100 {
+        map.set(key, key);
+    }
+    // garbage pinned by a concurrent reader ages till the reader is done.
+    let reader = map.clone();
+    let pinned = reader.get_and_pin(&0);
+    map.set(0, 0);
+    thread::sleep(time::Duration::from_millis(20));
+    mem::drop(pinned);
+
+    let old = map.gc_stats();
+    map.flush_gc();
+    let gc = map.gc_stats();
+    println!("test_gc_latency {:?}", gc);
+    let n_reclaims = old.reclaim_age.count() + (old.n_reclaims as u64);
+    assert_eq!(gc.reclaim_age.count(), n_reclaims);
+    assert_eq!(gc.gc_latency.count(), old.gc_latency.count() + 1);
+    assert!(gc.reclaim_age.max() >= time::Duration::from_millis(20));
+    assert!(gc.reclaim_age.percentile(50.0) <= gc.reclaim_age.max());
+    let n: u64 = gc.reclaim_age.as_buckets().iter().sum();
+    assert_eq!(n, n_reclaims);
+
+    // latency of dropped clones is accumulated.
+    let mut reader = reader;
+    for key in 0..100 {
+        reader.set(key, key);
+    }
+    reader.flush_gc();
+    let n = reader.gc_stats().reclaim_age.count();
+    mem::drop(reader);
+    assert_eq!(map.gc_stats().reclaim_age.count(), n_reclaims + n);
+}
+
+#[test]
+fn test_flush_gc() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_flush_gc seed:{}", seed);
+
+    let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+    map.set_gc_period(1_000_000);
+    for _i in 0..1000 {
+        let key = rng.gen::<u64>() % 100;
+        map.set(key, key);
+    }
+    let gc = map.gc_stats();
+    assert!(gc.n_garbage > 0, "{:?}", gc);
+
+    // garbage pinned by a concurrent reader is not collected.
+    let reader = map.clone();
+    let pinned = reader.get_and_pin(&0);
+    map.set(0, 0);
+    let n = map.flush_gc();
+    assert_eq!(n, gc.n_reclaims + gc.n_garbage);
+    assert!(map.gc_stats().n_garbage > 0, "{:?}", map.gc_stats());
+
+    mem::drop(pinned);
+    mem::drop(reader);
+    map.flush_gc();
+    let gc = map.gc_stats();
+    assert_eq!((gc.n_reclaims, gc.n_garbage), (0, 0), "{:?}", gc);
+    map.validate();
+}
+
+#[test]
+fn test_qsbr() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    println!("test_qsbr seed:{} n_threads:{}", seed, n_threads);
+
+    // one more clone, for the reader.
+    let mut map: Map<u64, u64> = Map::new(n_threads + 2, DefaultHasher::new());
+    {
+        let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+        let _clone = map.clone();
+        assert!(map.enable_qsbr().is_err());
+    }
+    map.enable_qsbr().unwrap();
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            let mut btmap = BTreeMap::new();
+            for i in 0..10_000 {
+                // keys are disjoint across threads.
+                let key = ((rng.gen::<u64>() % 1000) * 8) + (id as u64);
+                match rng.gen::<u8>() % 3 {
+                    0 => assert_eq!(map.remove(&key), btmap.remove(&key)),
+                    1 => assert_eq!(map.get(&key), btmap.get(&key).copied()),
+                    _ => assert_eq!(map.set(key, i), btmap.insert(key, i)),
+                };
+                if i % 100 == 0 {
+                    map.quiescent()
+                }
+            }
+            btmap
+        }));
+    }
+    let mut btmap = BTreeMap::new();
+    for handle in handles.into_iter() {
+        btmap.extend(handle.join().unwrap());
+    }
+    for (key, value) in btmap.iter() {
+        assert_eq!(map.get(key), Some(*value), "key:{}", key);
+    }
+
+    // instance that is not quiescent holds back garbage collection.
+    let mut reader = map.clone();
+    let key = btmap.keys().next().copied().unwrap_or(0);
+    map.set(key, 0);
+    map.flush_gc();
+    let gc = map.gc_stats();
+    assert!(gc.n_garbage > 0, "{:?}", gc);
+
+    reader.quiescent();
+    map.flush_gc();
+    let gc = map.gc_stats();
+    assert_eq!((gc.n_reclaims, gc.n_garbage), (0, 0), "{:?}", gc);
+    assert!(gc.n_frees > 0 || gc.n_pooled > 0, "{:?}", gc);
+    mem::drop(reader);
+    map.validate();
+}
+
+#[test]
+fn test_backlog() {
+    let limit = 16;
+    let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+    map.set_backlog(limit);
+    map.set(0, 0);
+
+    // reader pinning an old epoch holds back garbage collection.
+    let reader = map.clone();
+    let pinned = reader.get_and_pin(&0).unwrap();
+    let mut n = 0;
+    let err = loop {
+        match map.try_set(n, n) {
+            Ok(_) => n += 1,
+            Err(err) => break err,
+        }
+        assert!(n <= 1000, "n:{}", n);
+    };
+    println!("test_backlog n:{} err:{}", n, err);
+    assert!(matches!(err, Error::Backpressure(_, _)), "{}", err);
+    assert!(n <= (limit as u64), "n:{}", n);
+    assert!(map.try_remove(&0).is_err());
+
+    // writes wait for the reader to release the epoch.
+    let handle = thread::spawn(move || {
+        for key in 0..100 {
+            map.set(key, key + 1);
+        }
+        map
+    });
+    thread::sleep(time::Duration::from_millis(10));
+    assert_eq!(*pinned, 0);
+    mem::drop(pinned);
+    let mut map = handle.join().unwrap();
+    assert_eq!(map.try_remove(&0).unwrap(), Some(1));
+    assert_eq!(map.len(), 99);
+
+    mem::drop(reader);
+    map.validate();
+}
+
+#[test]
+fn test_backlog_no_readers() {
+    // without readers garbage is always collectable, writes never wait.
+    for limit in [0, 1, 2] {
+        let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+        map.set_backlog(limit);
+        let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+        for i in 0..1000 {
+            let key = i % 10;
+            assert_eq!(map.try_set(key, i).unwrap(), btmap.insert(key, i));
+            if i % 3 == 0 {
+                assert_eq!(map.try_remove(&key).unwrap(), btmap.remove(&key));
+            }
+            assert_eq!(map.set(key, i + 1), btmap.insert(key, i + 1));
+            if i % 5 == 0 {
+                assert_eq!(map.remove(&key), btmap.remove(&key));
+            }
+        }
+        assert_eq!(map.len(), btmap.len());
+        map.validate();
+    }
+}
+
+#[test]
+fn test_spawn_destructor() {
+    #[derive(Clone)]
+    struct Probe(Arc<Mutex<Vec<thread::ThreadId>>>);
+
+    impl Drop for Probe {
+        fn drop(&mut self) {
+            self.0.lock().unwrap().push(thread::current().id())
+        }
+    }
+
+    let dropped = Arc::new(Mutex::new(vec![]));
+    let mut map: Map<u64, Probe> = Map::new(1, DefaultHasher::new());
+    map.spawn_destructor().set_gc_period(0);
+    for key in 0..1000 {
+        map.set(key % 100, Probe(Arc::clone(&dropped)));
+    }
+    for key in 0..100 {
+        map.remove(&key);
+    }
+    map.flush_gc();
+
+    // reclaimed items are dropped by the destructor thread.
+    let main = thread::current().id();
+    let since = time::Instant::now();
+    while !dropped.lock().unwrap().iter().any(|id| *id != main) {
+        assert!(since.elapsed() < time::Duration::from_secs(10));
+        thread::sleep(time::Duration::from_millis(1));
+    }
+    assert!(map.is_empty());
+    map.validate();
+}
+
+#[test]
+fn test_footprint() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_keys = [0, 1, 10, 1000, 10_000][rng.gen::<usize>() % 5];
+    println!("test_footprint seed:{} n_keys:{}", seed, n_keys);
+
+    let mut map: Map<String, u64> = Map::new(1, DefaultHasher::new());
+    let empty = map.footprint(|_, _| 0);
+    let mut heap_bytes = 0;
+    for i in 0..n_keys {
+        let key = format!("key-{}", rng.gen::<u64>() % 100_000);
+        if map.get(&key).is_none() {
+            heap_bytes += key.len();
+        }
+        map.set(key, i);
+    }
+
+    let fp = map.footprint(|key, _| key.len());
+    println!("test_footprint {:?} total:{}", fp, fp.total());
+    assert_eq!(fp.heap_bytes, heap_bytes);
+    assert!(fp.total() >= empty.total(), "{:?} {:?}", fp, empty);
+
+    let stats = map.validate();
+    let n_mem = fp.trie_bytes - mem::size_of::<In<String, u64>>()
+        + fp.list_bytes
+        + fp.child_bytes;
+    assert_eq!(n_mem, stats.n_mem);
+
+    map.shrink_to_fit();
+    let fp = map.footprint(|_, _| 0);
+    assert_eq!((fp.pool_bytes, fp.reclaim_bytes), (0, 0), "{:?}", fp);
+}
+
+#[test]
+fn test_trim() {
+    let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+    map.set_pool_size(16, Spill::Shared(64));
+    map.reserve(16);
+    for key in 0..1000 {
+        map.set(key % 100, key);
+    }
+    let gc = map.gc_stats();
+    assert!(gc.n_pooled > 0, "{:?}", gc);
+
+    assert_eq!(map.trim(), gc.n_pooled);
+    let gc = map.gc_stats();
+    assert_eq!(gc.n_pooled, 0, "{:?}", gc);
+    assert_eq!(map.trim(), 0);
+    map.validate();
+}
+
+#[cfg(feature = "leak-check")]
+#[test]
+fn test_leak_check() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_leak_check seed:{}", seed);
+
+    let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+    map.set_gc_period(1000);
+
+    let handle = {
+        let mut map = map.clone();
+        let seed: u64 = rng.gen();
+        thread::spawn(move || {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            for _ in 0..10_000 {
+                let key = rng.gen::<u64>() % 1000;
+                match rng.gen::<u8>() % 2 {
+                    0 => map.set(key, key),
+                    _ => map.remove(&key),
+                };
+            }
+        })
+    };
+    for _ in 0..10_000 {
+        let key = rng.gen::<u64>() % 1000;
+        match rng.gen::<u8>() % 2 {
+            0 => map.set(key, key),
+            _ => map.remove(&key),
+        };
+    }
+    handle.join().unwrap();
+
+    assert!(map.outstanding_allocations() > 0);
+    map.flush_gc();
+    assert_eq!(map.outstanding_allocations(), 0);
+    map.validate();
+}
+
+#[test]
+fn test_backoff() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let backoff = [
+        Backoff::None,
+        Backoff::Spin(10),
+        Backoff::Exponential(6),
+        Backoff::Exponential(u32::MAX),
+        Backoff::Yield,
+        Backoff::Lock(0),
+        Backoff::Lock(4),
+    ][rng.gen::<usize>() % 7];
+    println!("test_backoff seed:{} backoff:{:?}", seed, backoff);
+
+    for attempt in 0..40 {
+        backoff.snooze(attempt)
+    }
+
+    let n_threads = 4;
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    map.set_backoff(backoff);
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        assert_eq!(map.backoff, backoff);
+        handles.push(thread::spawn(move || {
+            // contend on a small set of keys.
+            for i in 0..10_000_u64 {
+                let key = i % 16;
+                match i % 3 {
+                    0 => map.remove(&key),
+                    _ => map.set(key, (id as u64) * 100_000 + i),
+                };
+            }
+            map.gc_stats().n_retries
+        }));
+    }
+    let n_retries: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    println!("test_backoff n_retries:{}", n_retries);
+
+    for key in 0..16 {
+        map.set(key, key);
+    }
+    for key in 0..16 {
+        assert_eq!(map.get(&key), Some(key));
+    }
+    assert_eq!(map.len(), 16);
+    map.validate();
+}
+
+#[test]
+fn test_backoff_lock() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [2, 4, 8][rng.gen::<usize>() % 3];
+    println!("test_backoff_lock seed:{} n_threads:{}", seed, n_threads);
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 2, DefaultHasher::new());
+    let mut taken = map.clone();
+    assert!(map.stripes.get().is_none());
+    map.set_backoff(Backoff::Lock(0));
+    assert_eq!(map.stripes.get().unwrap().len(), STRIPES);
+    map.set_backoff(Backoff::Lock(2));
+    // clones taken before, and after, lock the same stripes.
+    taken.set_backoff(Backoff::Lock(2));
+    let stripes = map.stripes.get().unwrap();
+    assert!(std::ptr::eq(taken.stripes.get().unwrap(), stripes));
+
+    // attempts past the limit hold the stripe, till the write is done.
+    let mut lock = None;
+    map.snooze(2, 0x312, &mut lock);
+    assert!(lock.is_none());
+    map.snooze(3, 0x312, &mut lock);
+    assert_eq!(stripes[0x12].load(SeqCst), 1);
+    let handle = thread::spawn(move || {
+        let mut lock = None;
+        taken.snooze(3, 0x412, &mut lock);
+        lock.is_some()
+    });
+    thread::sleep(time::Duration::from_millis(10));
+    assert!(!handle.is_finished());
+    mem::drop(lock);
+    assert!(handle.join().unwrap());
+    assert_eq!(stripes[0x12].load(SeqCst), 0);
+
+    let mut handles = vec![];
+    for _id in 0..n_threads {
+        let mut m = map.clone();
+        assert!(Arc::ptr_eq(&m.stripes, &map.stripes));
+        assert!(m.stripes.get().is_some());
+        assert_eq!(m.backoff, Backoff::Lock(2));
+        handles.push(thread::spawn(move || {
+            // hammer a single hot key, along with its neighbours.
+            let hash = m.to_trie_hash(&0_u64);
+            for i in 0..10_000_u64 {
+                match i % 4 {
+                    0 => m.remove(&(1 + i % 7)),
+                    _ => {
+                        m.do_update(0, hash, |v| Some(v.copied().unwrap_or(0) + 1))
+                            .1
+                    }
+                };
+            }
+            m.gc_stats().n_set_retries
+        }));
+    }
+    let n_retries: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    println!("test_backoff_lock n_set_retries:{}", n_retries);
+
+    // no increment is lost.
+    assert_eq!(map.get(&0), Some((n_threads as u64) * 7_500));
+
+    // stripes are released once the writes are done.
+    assert!(map
+        .stripes
+        .get()
+        .unwrap()
+        .iter()
+        .all(|s| s.load(SeqCst) == 0));
+    map.validate();
+}
+
+#[test]
+fn test_op_retries() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [2, 4, 8][rng.gen::<usize>() % 3];
+    println!("test_op_retries seed:{} n_threads:{}", seed, n_threads);
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    // single thread writes never retry.
+    for i in 0..10_000_u64 {
+        match rng.gen::<u8>() % 2 {
+            0 => map.remove(&(i % 64)),
+            _ => map.set(i % 64, i),
+        };
+    }
+    let gc = map.gc_stats();
+    assert_eq!(gc.n_set_retries, 0, "{:?}", gc);
+    assert_eq!(gc.n_remove_retries, 0, "{:?}", gc);
+    assert_eq!(gc.n_compact_retries, 0, "{:?}", gc);
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            for i in 0..10_000_u64 {
+                let key = rng.gen::<u64>() % 64;
+                match rng.gen::<u8>() % 2 {
+                    0 => map.remove(&key),
+                    _ => map.set(key, i),
+                };
+            }
+        }));
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap()
+    }
+
+    let gc = map.gc_stats();
+    println!("test_op_retries {:?}", gc);
+    // every failed CAS is retried by the operation.
+    let n = gc.n_set_retries + gc.n_remove_retries + gc.n_compact_retries;
+    assert!(n >= gc.n_retries, "{:?}", gc);
+    map.validate();
+}
+
+#[test]
+fn test_epoch_period() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4][rng.gen::<usize>() % 3];
+    let period = [1, 16, 1000][rng.gen::<usize>() % 3];
+    println!(
+        "test_epoch_period seed:{} n_threads:{} period:{}",
+        seed, n_threads, period
+    );
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    map.set_epoch_period(100).set_gc_period(1);
+    let epoch = map.epoch.load(SeqCst);
+    for key in 0..99 {
+        map.set(key, key);
+    }
+    assert_eq!(map.epoch.load(SeqCst), epoch);
+    map.set(99, 99);
+    assert_eq!(map.epoch.load(SeqCst), epoch + 1);
+
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + ((id as u128) * 100);
+        handles.push(thread::spawn(move || {
+            map.set_epoch_period(period);
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            for i in 0..10_000 {
+                let key = rng.gen::<u64>() % 1000;
+                match rng.gen::<u8>() % 2 {
+                    0 => map.remove(&key),
+                    _ => map.set(key, i),
+                };
+            }
+        }));
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap()
+    }
+
+    map.flush_gc();
+    let gc = map.gc_stats();
+    assert_eq!((gc.n_reclaims, gc.n_garbage), (0, 0), "{:?}", gc);
+    map.validate();
+}
+
+#[test]
+fn test_qsbr_reads() {
+    assert_eq!(mem::size_of::<Slot>(), 128);
+
+    let mut map: Map<u64, u64> = Map::new(2, DefaultHasher::new());
+    map.enable_qsbr().unwrap();
+    for key in 0..1000 {
+        map.set(key, key);
+    }
+    let reader = map.clone();
+
+    // reads do not touch the epoch slot.
+    let log: Vec<u64> = map.access_log.iter().map(|e| e.load(SeqCst)).collect();
+    for key in 0..1000 {
+        assert_eq!(reader.get(&key), Some(key));
+    }
+    assert_eq!(reader.iter().count(), 1000);
+    let after: Vec<u64> = map.access_log.iter().map(|e| e.load(SeqCst)).collect();
+    assert_eq!(log, after);
+    assert_eq!(reader.n_pins.load(SeqCst), 0);
+}
+
+#[test]
+fn test_single_handle() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_single_handle seed:{}", seed);
+
+    let mut map: Map<u64, u64> = Map::new(1, DefaultHasher::new());
+    map.set_gc_period(1_000_000);
+    let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+    for i in 0..10_000 {
+        let key = rng.gen::<u64>() % 1000;
+        match rng.gen::<u8>() % 3 {
+            0 => assert_eq!(map.remove(&key), btmap.remove(&key)),
+            _ => assert_eq!(map.set(key, i), btmap.insert(key, i)),
+        }
+        // no garbage is left behind by writes.
+        let gc = map.gc_stats();
+        assert_eq!((gc.n_reclaims, gc.n_garbage), (0, 0), "{:?}", gc);
+    }
+    assert_eq!(map.len(), btmap.len());
+    map.validate();
+}
+
+#[test]
+fn test_drop_frees() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_clones = rng.gen::<usize>() % 4;
+    println!("test_drop_frees seed:{} n_clones:{}", seed, n_clones);
+
+    let value = Arc::new(10_u64);
+    {
+        let mut map: Map<u64, Arc<u64>> = Map::new(4, DefaultHasher::new());
+        let clones: Vec<Map<u64, Arc<u64>>> =
+            (0..n_clones).map(|_| map.clone()).collect();
+        for key in 0..1000 {
+            map.set(key, Arc::clone(&value));
+        }
+        for key in 0..500 {
+            map.remove(&key);
+        }
+        map.flush_gc();
+        assert_eq!(Arc::strong_count(&value), 501);
+        mem::drop(map);
+        // trie is alive as long as there are handles.
+        if n_clones > 0 {
+            assert_eq!(clones[0].get(&999).as_deref(), Some(&10));
+        }
+    }
+    // all the handles are dropped, along with the trie.
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_drop_handoff() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = 1 + rng.gen::<usize>() % 4;
+    println!("test_drop_handoff seed:{} n_threads:{}", seed, n_threads);
+
+    let mut map: Map<u64, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+    map.set(0, 0);
+
+    // a reader pinned to an old epoch shall not block dropping other handles.
+    let pinned = map.get_and_pin(&0).unwrap();
+    let mut handles = vec![];
+    for id in 0..n_threads {
+        let mut map = map.clone();
+        let seed = seed + (id as u128);
+        handles.push(thread::spawn(move || {
+            let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+            for i in 0..1000 {
+                let key = rng.gen::<u64>() % 100;
+                match rng.gen::<u8>() % 3 {
+                    0 => map.remove(&key),
+                    _ => map.set(key, i),
+                };
+            }
+        }));
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap()
+    }
+    let gc = map.gc_stats();
+    assert!(gc.n_reclaims > 0, "{:?}", gc);
+    assert_eq!(*pinned, 0);
+    mem::drop(pinned);
+
+    // garbage left over by the dropped handles is collected by the survivor.
+    map.flush_gc();
+    let gc = map.gc_stats();
+    assert_eq!((gc.n_reclaims, gc.n_garbage), (0, 0), "{:?}", gc);
+    map.validate();
+}
+
+#[test]
+fn test_bulk_load() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n = [0, 1, 10, 1000, 10_000][rng.gen::<usize>() % 5];
+    println!("test_bulk_load seed:{} n:{}", seed, n);
+
+    let widths = [
+        HashWidth::Bits32,
+        HashWidth::Rehash,
+        HashWidth::Bits64,
+        HashWidth::Bits128,
+    ];
+    for width in widths.iter().copied() {
+        // keys repeat, and keys apart by 2^32 collide on the 64-bit hash.
+        let entries: Vec<(u64, u64)> = (0..n)
+            .map(|_| {
+                let key = rng.gen::<u64>() % (n as u64 + 1);
+                (key + ((rng.gen::<u64>() % 2) << 32), rng.gen::<u64>())
+            })
+            .collect();
+
+        let mut map: Map<u64, u64, WideHasher> =
+            Map::with_hash_width(1, WideHasher::default(), width);
+        let mut btmap: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut refmap: Map<u64, u64, WideHasher> =
+            Map::with_hash_width(1, WideHasher::default(), width);
+        for (key, value) in entries.iter() {
+            btmap.insert(*key, *value);
+            refmap.set(*key, *value);
+        }
+
+        assert_eq!(map.bulk_load(entries).unwrap(), btmap.len(), "{:?}", width);
+        assert_eq!(map.len(), btmap.len());
+        for (key, value) in btmap.iter() {
+            assert_eq!(map.get(key), Some(*value), "{:?} key:{}", width, key);
+        }
+
+        // same trie as that built by set().
+        let (stats, refstats) = (map.validate(), refmap.validate());
+        assert_eq!(
+            (stats.n_nodes, stats.n_childs, stats.n_items, stats.n_lists),
+            (
+                refstats.n_nodes,
+                refstats.n_childs,
+                refstats.n_items,
+                refstats.n_lists
+            ),
+            "{:?}",
+            width
+        );
+        let (cs, refcs) = (map.collision_stats(), refmap.collision_stats());
+        assert_eq!(cs.max_list_len, refcs.max_list_len, "{:?}", width);
+        map.check_invariants().unwrap();
+
+        if !btmap.is_empty() {
+            assert!(map.bulk_load(vec![(1, 1)]).is_err());
+        }
+        for (key, value) in btmap.iter().take(100) {
+            assert_eq!(map.remove(key), Some(*value));
+        }
+        map.validate();
+    }
+}
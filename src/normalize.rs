@@ -0,0 +1,169 @@
+//! Module implement key normalization, applied before hashing and equality.
+//!
+//! Parametrise [Map] over [Normalized] keys, say `Map<Normalized<Lowercase>, V>`,
+//! for case-insensitive keys. Keys are stored in their canonical form, as
+//! computed by [Normalize::normalize], and lookups are done using [Lookup]
+//! without normalizing the key at the call site, refer to [Equivalent].
+//! Stored canonical key can be retrieved via [Map::get_key_value].
+//!
+//! ```
+//! use cmap::normalize::{Lookup, Lowercase, Normalized};
+//! use cmap::{DefaultHasher, Map};
+//!
+//! let mut map: Map<Normalized<Lowercase>, u64> = Map::new(1, DefaultHasher::new());
+//! map.set(Normalized::new("/Index.HTML"), 10);
+//!
+//! assert_eq!(map.get(&Lookup::new("/INDEX.html")), Some(10));
+//! let (key, _) = map.get_key_value(&Lookup::new("/index.html")).unwrap();
+//! assert_eq!(key.as_ref(), "/index.html");
+//! ```
+//!
+//! Implement [Normalize] for other normalizations, like Unicode NFC.
+//!
+//! [Map]: crate::Map
+//! [Map::get_key_value]: crate::Map::get_key_value
+//! [Equivalent]: crate::Equivalent
+
+use std::{
+    borrow::Borrow,
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::Equivalent;
+
+/// Trait to normalize keys into their canonical form.
+pub trait Normalize {
+    /// Type of the key, say `str`.
+    type Key: ?Sized + ToOwned;
+
+    /// Return the canonical form of `key`.
+    fn normalize(key: &Self::Key) -> <Self::Key as ToOwned>::Owned;
+
+    /// Hash the canonical form of `key`, without computing it. Hashing a
+    /// key, and hashing its canonical form, shall feed `state` the same.
+    fn hash<S: Hasher>(key: &Self::Key, state: &mut S);
+
+    /// Return whether `a` and `b` have the same canonical form.
+    fn eq(a: &Self::Key, b: &Self::Key) -> bool;
+}
+
+/// Normalize string keys to lowercase, one char at a time, that is
+/// `Σ` is always normalized to `σ`, unlike [str::to_lowercase].
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Lowercase;
+
+impl Normalize for Lowercase {
+    type Key = str;
+
+    fn normalize(key: &str) -> String {
+        key.chars().flat_map(char::to_lowercase).collect()
+    }
+
+    fn hash<S: Hasher>(key: &str, state: &mut S) {
+        for ch in key.chars().flat_map(char::to_lowercase) {
+            state.write_u32(ch as u32)
+        }
+        state.write_u8(0xff)
+    }
+
+    fn eq(a: &str, b: &str) -> bool {
+        let a = a.chars().flat_map(char::to_lowercase);
+        a.eq(b.chars().flat_map(char::to_lowercase))
+    }
+}
+
+/// Key stored in its canonical form, as normalized by `N`.
+pub struct Normalized<N: Normalize> {
+    key: <N::Key as ToOwned>::Owned,
+    _normalize: PhantomData<fn() -> N>,
+}
+
+impl<N: Normalize> Normalized<N> {
+    /// Normalize `key` into its canonical form.
+    pub fn new(key: &N::Key) -> Normalized<N> {
+        Normalized {
+            key: N::normalize(key),
+            _normalize: PhantomData,
+        }
+    }
+
+    /// Return the canonical key.
+    pub fn into_inner(self) -> <N::Key as ToOwned>::Owned {
+        self.key
+    }
+}
+
+impl<N: Normalize> AsRef<N::Key> for Normalized<N> {
+    fn as_ref(&self) -> &N::Key {
+        self.key.borrow()
+    }
+}
+
+impl<N: Normalize> Clone for Normalized<N>
+where
+    <N::Key as ToOwned>::Owned: Clone,
+{
+    fn clone(&self) -> Self {
+        Normalized {
+            key: self.key.clone(),
+            _normalize: PhantomData,
+        }
+    }
+}
+
+impl<N: Normalize> Hash for Normalized<N> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        N::hash(self.as_ref(), state)
+    }
+}
+
+impl<N: Normalize> PartialEq for Normalized<N> {
+    fn eq(&self, other: &Self) -> bool {
+        N::eq(self.as_ref(), other.as_ref())
+    }
+}
+
+impl<N: Normalize> Eq for Normalized<N> {}
+
+impl<N: Normalize> Debug for Normalized<N>
+where
+    <N::Key as ToOwned>::Owned: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.key.fmt(f)
+    }
+}
+
+/// Lookup a [Normalized] key, without normalizing `key` upfront.
+pub struct Lookup<'a, N: Normalize> {
+    key: &'a N::Key,
+    _normalize: PhantomData<fn() -> N>,
+}
+
+impl<'a, N: Normalize> Lookup<'a, N> {
+    /// Create a lookup for `key`, normalized while hashing and comparing.
+    pub fn new(key: &'a N::Key) -> Lookup<'a, N> {
+        Lookup {
+            key,
+            _normalize: PhantomData,
+        }
+    }
+}
+
+impl<'a, N: Normalize> Hash for Lookup<'a, N> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        N::hash(self.key, state)
+    }
+}
+
+impl<'a, N: Normalize> Equivalent<Normalized<N>> for Lookup<'a, N> {
+    fn equivalent(&self, key: &Normalized<N>) -> bool {
+        N::eq(self.key, key.as_ref())
+    }
+}
+
+#[cfg(test)]
+#[path = "normalize_test.rs"]
+mod normalize_test;
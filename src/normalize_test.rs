@@ -0,0 +1,63 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, hash::BuildHasher};
+
+use super::*;
+use crate::{DefaultHasher, Map};
+
+fn random_case(rng: &mut SmallRng, key: &str) -> String {
+    key.chars()
+        .map(|ch| match rng.gen::<bool>() {
+            true => ch.to_uppercase().next().unwrap(),
+            false => ch,
+        })
+        .collect()
+}
+
+#[test]
+fn test_lowercase() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_lowercase seed:{}", seed);
+
+    let hb = DefaultHasher::new();
+    for key in ["", "abc", "/Path/To/Index.HTML", "ΑΣ", "straße"].iter() {
+        let norm = Normalized::<Lowercase>::new(key);
+        let lookup = Lookup::<Lowercase>::new(key);
+        assert_eq!(hb.hash_one(&norm), hb.hash_one(&lookup), "{}", key);
+        assert!(lookup.equivalent(&norm), "{}", key);
+        assert_eq!(Normalized::<Lowercase>::new(norm.as_ref()), norm);
+        assert_eq!(norm.as_ref(), Lowercase::normalize(key));
+    }
+    assert_eq!(Normalized::<Lowercase>::new("ΑΣ").as_ref(), "ασ");
+
+    let mut map: Map<Normalized<Lowercase>, u64> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<String, u64> = BTreeMap::new();
+    for _i in 0..10_000 {
+        let key = format!("/path/to/key-{}", rng.gen::<u64>() % 1000);
+        let value = rng.gen::<u64>();
+        let rkey = random_case(&mut rng, &key);
+        match rng.gen::<u8>() % 4 {
+            0 => assert_eq!(map.remove(&Lookup::new(rkey.as_str())), btmap.remove(&key)),
+            1 => assert_eq!(
+                map.get(&Lookup::new(rkey.as_str())),
+                btmap.get(&key).cloned()
+            ),
+            _ => assert_eq!(
+                map.set(Normalized::new(rkey.as_str()), value),
+                btmap.insert(key, value)
+            ),
+        }
+    }
+
+    assert_eq!(map.len(), btmap.len());
+    for (key, value) in btmap.iter() {
+        let rkey = random_case(&mut rng, key);
+        let (skey, svalue) = map.get_key_value(&Lookup::new(rkey.as_str())).unwrap();
+        assert_eq!(skey.as_ref(), key);
+        assert_eq!(svalue, *value);
+    }
+    let mut keys: Vec<String> = map.keys().map(|k| k.into_inner()).collect();
+    keys.sort();
+    assert_eq!(keys, btmap.keys().cloned().collect::<Vec<String>>());
+}
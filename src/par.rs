@@ -0,0 +1,190 @@
+//! Module implement parallel iteration using [rayon].
+//!
+//! **This module is available only with `rayon` feature.**
+//!
+//! Parallel iterators split the hash-trie on the children of the root
+//! node, and each split is walked by a rayon worker. The calling instance
+//! of the map stays pinned to an epoch until the parallel iteration is
+//! complete, and the consistency guarantees are the same as that of
+//! [iter][crate::iter] module.
+//!
+//! [Map] also implement [ParallelExtend], for bulk loading entries from
+//! multiple threads.
+
+use rayon::iter::{
+    plumbing::UnindexedConsumer, IndexedParallelIterator, IntoParallelIterator,
+    ParallelExtend, ParallelIterator,
+};
+
+use std::{
+    cmp,
+    hash::{BuildHasher, Hash},
+    sync::atomic::Ordering::SeqCst,
+};
+
+use crate::{
+    iter::Walker,
+    map::{Child, Node},
+    DefaultHasher, Map,
+};
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return a parallel iterator over all {key, value} entries in the map.
+    pub fn par_iter(&self) -> ParIter<'_, K, V, H> {
+        let seqno = self.pin_epoch();
+        ParIter { map: self, seqno }
+    }
+}
+
+impl<K, V, H> Map<K, V, H>
+where
+    K: Send + Sync,
+    V: Send + Sync,
+    H: Send + Sync,
+{
+    /// Parallel version of [Map::fold]. Each split of the map is folded
+    /// into an accumulator, starting with `identity()`, and accumulators
+    /// from all splits are combined using `reduce`. Keys and values are
+    /// not cloned.
+    pub fn par_reduce<T, ID, F, R>(&self, identity: ID, fold: F, reduce: R) -> T
+    where
+        T: Send,
+        ID: Fn() -> T + Send + Sync,
+        F: Fn(T, &K, &V) -> T + Send + Sync,
+        R: Fn(T, T) -> T + Send + Sync,
+    {
+        let seqno = self.pin_epoch();
+        let res = self
+            .to_root_childs()
+            .into_par_iter()
+            .map(|child| {
+                Walker::from_child(child)
+                    .fold(identity(), |acc, item| fold(acc, &item.key, &item.value))
+            })
+            .reduce(&identity, &reduce);
+        self.unpin_epoch(seqno);
+        res
+    }
+
+    // Children of the root node, the unit of split for parallel walks, valid
+    // only while this instance stays pinned.
+    fn to_root_childs(&self) -> Vec<&Child<K, V>> {
+        match self.as_root_node() {
+            Node::Trie { childs, .. } => childs
+                .iter()
+                .map(|ptr| unsafe { ptr.load(SeqCst).as_ref().unwrap() })
+                .collect(),
+            Node::Tomb { .. } | Node::List { .. } => unreachable!(),
+        }
+    }
+}
+
+/// ParIter is returned by [Map::par_iter], yield a clone of each entry in
+/// the map.
+pub struct ParIter<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    seqno: u64,
+}
+
+impl<'a, K, V, H> Drop for ParIter<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> ParallelIterator for ParIter<'a, K, V, H>
+where
+    K: Clone + Send + Sync,
+    V: Clone + Send + Sync,
+    H: Send + Sync,
+{
+    type Item = (K, V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        // self, and hence the pin, outlive the walk.
+        self.map
+            .to_root_childs()
+            .into_par_iter()
+            .flat_map_iter(Walker::from_child)
+            .map(|item| (item.key.clone(), item.value.clone()))
+            .drive_unindexed(consumer)
+    }
+}
+
+impl<K, V, H> ParallelExtend<(K, V)> for Map<K, V, H>
+where
+    K: Clone + PartialEq + Hash + Send + Sync,
+    V: Clone + Send + Sync,
+    H: BuildHasher + Send + Sync,
+{
+    /// Insert entries from a parallel iterator. Entries are partitioned on
+    /// the first slot of their hash, that is on the children of the root
+    /// node, and each partition is inserted by a single worker thereby
+    /// avoiding CAS contention between workers. Workers use this instance
+    /// and the clones that are not yet handed out, if any, from the pool.
+    /// A concurrent clone() of this map, on another handle, waits till the
+    /// workers are done and their clones are back in the pool.
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = (K, V)>,
+    {
+        let mut parts: Vec<Vec<(K, V)>> = {
+            let this: &Self = self;
+            par_iter
+                .into_par_iter()
+                .fold(new_parts, |mut parts, (key, value)| {
+                    let w = (this.to_hash(&key) & SLOT_MASK) as usize;
+                    parts[w].push((key, value));
+                    parts
+                })
+                .reduce(new_parts, |mut parts, others| {
+                    for (part, other) in parts.iter_mut().zip(others) {
+                        part.extend(other)
+                    }
+                    parts
+                })
+        };
+
+        let mut clones = self.take_clones(N_SLOTS - 1);
+
+        // assign partitions to workers, larger ones first to the least
+        // loaded worker.
+        let n_workers = clones.len() + 1;
+        let mut loads: Vec<Vec<Vec<(K, V)>>> = (0..n_workers).map(|_| vec![]).collect();
+        parts.sort_by_key(|part| cmp::Reverse(part.len()));
+        for part in parts.into_iter().filter(|part| !part.is_empty()) {
+            let load = loads
+                .iter_mut()
+                .min_by_key(|load| load.iter().map(|p| p.len()).sum::<usize>())
+                .unwrap();
+            load.push(part);
+        }
+
+        let mut workers: Vec<&mut Map<K, V, H>> = vec![self];
+        workers.extend(clones.iter_mut());
+        workers
+            .into_par_iter()
+            .zip(loads.into_par_iter())
+            .for_each(|(map, load)| {
+                for (key, value) in load.into_iter().flatten() {
+                    map.set(key, value);
+                }
+            });
+
+        self.put_clones(clones);
+    }
+}
+
+const N_SLOTS: usize = 16;
+const SLOT_MASK: u32 = 0xF;
+
+fn new_parts<K, V>() -> Vec<Vec<(K, V)>> {
+    (0..N_SLOTS).map(|_| vec![]).collect()
+}
+
+#[cfg(test)]
+#[path = "par_test.rs"]
+mod par_test;
@@ -0,0 +1,135 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc,
+    },
+    thread,
+};
+
+use super::*;
+use crate::iter::iter_test::{load_keys, seed_keys, with_writer};
+
+#[test]
+fn test_par_iter() {
+    let (_, mut rng, n_keys) = seed_keys("test_par_iter");
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut items: Vec<(u32, u64)> = map.par_iter().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+
+    let sum: u64 = map.par_iter().map(|(_, v)| v % 1000).sum();
+    assert_eq!(sum, refs.iter().map(|(_, v)| v % 1000).sum());
+}
+
+#[test]
+fn test_par_iter_concurrent() {
+    let seed: u128 = random();
+    let n_keys = 10_000;
+    println!("test_par_iter_concurrent seed:{}", seed);
+
+    let mut map: Map<u32, u64> = Map::new(2, DefaultHasher::new());
+    with_writer(seed, n_keys, &mut map, 4, |map| {
+        map.par_iter().map(|(k, _)| k).collect()
+    });
+}
+
+#[test]
+fn test_par_extend() {
+    let (_, mut rng, n_keys) = seed_keys("test_par_extend");
+    let concurrency = [1, 2, 4, 32][rng.gen::<usize>() % 4];
+    println!("test_par_extend concurrency:{}", concurrency);
+
+    let mut map: Map<u32, u64> = Map::new(concurrency, DefaultHasher::new());
+    map.set(0, 0);
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    btmap.insert(0, 0);
+
+    let items: Vec<(u32, u64)> = (0..n_keys).map(|_| (rng.gen(), rng.gen())).collect();
+    btmap.extend(items.clone());
+    map.par_extend(items.into_par_iter());
+
+    let mut items: Vec<(u32, u64)> = map.iter().collect();
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+    assert_eq!(map.len(), refs.len());
+
+    // clones used by workers are back in the pool.
+    let clones: Vec<Map<u32, u64>> = (1..concurrency).map(|_| map.clone()).collect();
+    assert_eq!(clones.len(), concurrency - 1);
+}
+
+#[test]
+fn test_par_extend_clone() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    println!("test_par_extend_clone seed:{}", seed);
+
+    let mut map: Map<u32, u64> = Map::new(4, DefaultHasher::new());
+    let other = map.clone();
+
+    // clone() on another handle, while par_extend() has the clones on loan.
+    let done = Arc::new(AtomicBool::new(false));
+    let handle = {
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            while other.n_loaned.load(SeqCst) == 0 && !done.load(SeqCst) {
+                thread::yield_now()
+            }
+            let loaned = other.n_loaned.load(SeqCst);
+            let clone = other.clone();
+            (other, clone, loaned)
+        })
+    };
+    let items: Vec<(u32, u64)> = (0..100_000).map(|_| (rng.gen(), rng.gen())).collect();
+    let n = items
+        .iter()
+        .map(|(k, _)| *k)
+        .collect::<BTreeSet<u32>>()
+        .len();
+    map.par_extend(items.into_par_iter());
+    done.store(true, SeqCst);
+    let (other, clone, loaned) = handle.join().unwrap();
+    println!("test_par_extend_clone loaned:{}", loaned);
+
+    assert_eq!(map.len(), n);
+    assert_eq!(other.len(), n);
+    assert_eq!(clone.len(), n);
+}
+
+#[test]
+fn test_par_reduce() {
+    let (_, mut rng, n_keys) = seed_keys("test_par_reduce");
+
+    let mut map: Map<u32, u64> = Map::new(1, DefaultHasher::new());
+    let mut btmap: BTreeMap<u32, u64> = BTreeMap::new();
+    for _i in 0..n_keys {
+        let (key, value) = (rng.gen::<u32>(), rng.gen::<u64>() % 1000);
+        map.set(key, value);
+        btmap.insert(key, value);
+    }
+
+    // histogram of values.
+    let hist = map.par_reduce(
+        || vec![0_usize; 10],
+        |mut hist, _, v| {
+            hist[(*v / 100) as usize] += 1;
+            hist
+        },
+        |mut a, b| {
+            a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+            a
+        },
+    );
+
+    let mut refs = vec![0_usize; 10];
+    btmap.values().for_each(|v| refs[(*v / 100) as usize] += 1);
+    assert_eq!(hist, refs);
+}
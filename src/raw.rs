@@ -0,0 +1,106 @@
+//! Module implement raw-entry API, for advanced use cases.
+//!
+//! **This module is available only with `raw` feature, and it is not
+//! covered by semantic versioning. Its API might change between minor
+//! releases.**
+//!
+//! A [RawEntry] give access to the hash computed for a key, to the value
+//! currently indexed by the key and a commit hook that is called from
+//! within the CAS loop. Libraries built on top of this package can use this
+//! to implement custom operations, like merging values or probabilistic
+//! caching, without forking the package.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::Map;
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return a [RawEntry] for `key`. Refer to [raw][crate::raw] module for
+    /// details.
+    pub fn raw_entry(&mut self, key: K) -> RawEntry<'_, K, V, H>
+    where
+        K: Hash,
+        H: BuildHasher,
+    {
+        let hash = self.to_trie_hash(&key);
+        RawEntry {
+            map: self,
+            key,
+            hash,
+        }
+    }
+}
+
+/// RawEntry for a single key within [Map]. Hash is computed once, when the
+/// entry is created, and re-used for all operations on the entry.
+pub struct RawEntry<'a, K, V, H> {
+    map: &'a mut Map<K, V, H>,
+    key: K,
+    hash: u128,
+}
+
+impl<'a, K, V, H> RawEntry<'a, K, V, H> {
+    /// Return the key for this entry.
+    pub fn as_key(&self) -> &K {
+        &self.key
+    }
+
+    /// Return the 32-bit hash for this entry's key, as used by the trie.
+    /// With wider [HashWidth][crate::HashWidth], this is the lower 32 bits of
+    /// the hash.
+    pub fn to_hash(&self) -> u32 {
+        self.hash as u32
+    }
+
+    /// Locate the leaf for this entry's key and call `callb` with its
+    /// value. Return None if key is not present in the map.
+    pub fn get_with<F, T>(&self, callb: F) -> Option<T>
+    where
+        K: PartialEq + Hash,
+        H: BuildHasher,
+        F: Fn(&V) -> T,
+    {
+        self.map.do_get_with(&self.key, self.hash, callb)
+    }
+
+    /// Commit a new value for this entry's key. `callb` is called with the
+    /// value currently indexed, if any, just before the CAS operation and
+    /// it shall return the new value. If `callb` returns None, map is left
+    /// untouched. On CAS failure `callb` will be called again with the
+    /// latest value, so it must be free from side-effects, other than the
+    /// ones that can be safely repeated. Return the old value, if a new
+    /// value was committed in place of an old value.
+    pub fn commit<F>(self, callb: F) -> Option<V>
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+        F: FnMut(Option<&V>) -> Option<V>,
+    {
+        let (seqno, res) = self.map.do_update(self.key, self.hash, callb);
+        self.map.do_gc(seqno);
+        res
+    }
+
+    /// Remove this entry's key from map, only if `pred` returns true for the
+    /// value currently indexed. `pred` follow the same rules as the callback
+    /// for [RawEntry::commit]. Return the removed value.
+    pub fn remove_if<F>(self, pred: F) -> Option<V>
+    where
+        K: Clone + PartialEq + Hash,
+        V: Clone,
+        H: BuildHasher,
+        F: FnMut(&V) -> bool,
+    {
+        let (seqno, compact, res) = self.map.do_remove(&self.key, self.hash, pred);
+        if compact {
+            self.map.do_compact(&self.key, self.hash)
+        }
+        self.map.do_gc(seqno);
+        res
+    }
+}
+
+#[cfg(test)]
+#[path = "raw_test.rs"]
+mod raw_test;
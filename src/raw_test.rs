@@ -0,0 +1,56 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::{collections::BTreeMap, thread};
+
+use super::*;
+use crate::DefaultHasher;
+
+#[test]
+fn test_raw_entry() {
+    let seed: u128 = random();
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    let n_threads = [1, 2, 4, 8][rng.gen::<usize>() % 4];
+    println!("test_raw_entry seed:{} threads:{}", seed, n_threads);
+
+    let mut map: Map<u32, u64> = Map::new(n_threads + 1, DefaultHasher::new());
+
+    // merge values, concurrently, using the commit hook.
+    let mut handles = vec![];
+    for _id in 0..n_threads {
+        let mut map = map.clone();
+        let h = thread::spawn(move || {
+            for key in 0..1000_u32 {
+                let entry = map.raw_entry(key);
+                entry.commit(|old| Some(old.copied().unwrap_or(0) + (key as u64)));
+            }
+        });
+        handles.push(h);
+    }
+    for handle in handles.into_iter() {
+        handle.join().unwrap()
+    }
+
+    let mut btmap = BTreeMap::new();
+    for key in 0..1000_u32 {
+        let hash = map.to_hash(&key);
+        let entry = map.raw_entry(key);
+        assert_eq!(entry.to_hash(), hash);
+        let value = entry.get_with(|v| *v).unwrap();
+        assert_eq!(value, (key as u64) * (n_threads as u64), "key {}", key);
+        btmap.insert(key, value);
+    }
+
+    for key in 0..1000_u32 {
+        let entry = map.raw_entry(key);
+        assert_eq!(entry.commit(|_| None), None);
+        let res = map.raw_entry(key).remove_if(|v| (*v % 2) == 0);
+        match btmap.get(&key) {
+            Some(v) if (*v % 2) == 0 => assert_eq!(res, Some(*v)),
+            Some(_) => assert_eq!(res, None),
+            None => unreachable!(),
+        }
+    }
+    let n = btmap.values().filter(|v| (**v % 2) == 1).count();
+    assert_eq!(map.len(), n);
+    map.validate();
+}
@@ -0,0 +1,179 @@
+//! Module implement random sampling of [Map] entries.
+//!
+//! **This module is available only with `rand` feature.**
+//!
+//! Trie nodes do not track the number of entries under them, instead
+//! sampling rely on the hash being uniformly distributed. Random positions
+//! are drawn in walk order, refer to [to_walk_order], and for each position
+//! the trie is descended to the first entry at or after that position. An
+//! entry's chance of being picked is proportional to the gap in between its
+//! hash and the hash of its predecessor, which for well distributed hashes
+//! is close to uniform.
+
+use rand::Rng;
+
+use std::{
+    collections::{btree_map, BTreeMap},
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    iter::{to_walk_order, Walker},
+    map::{Item, Node},
+    DefaultHasher, Map,
+};
+
+// Number of times a position is redrawn, when it lands on an entry that is
+// already sampled, before falling back to the next entry not yet sampled.
+const REDRAWS: usize = 8;
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return an iterator yielding `n` randomly sampled entries, without
+    /// walking the entire map. If the map has less than `n` entries, all of
+    /// them are yielded. Sampled entries are yielded in walk order, and an
+    /// entry is yielded at most once.
+    ///
+    /// Entries are drawn upfront, when the iterator is created. A position
+    /// landing on an entry that is already sampled is redrawn, and after a
+    /// few redraws the next entry not yet sampled, in walk order, is picked.
+    /// Entries are weighted by the gap in hash to their predecessor, and not
+    /// by the number of entries under each trie node, refer to the
+    /// [module][crate::sample] documentation.
+    /// Keys colliding on the 32-bit hash are sampled as a group, yielding
+    /// one of them, hence for such keys less than `n` entries are yielded.
+    ///
+    /// Instance is pinned to an epoch until the iterator is dropped and the
+    /// consistency guarantees are the same as that of [Map::iter].
+    pub fn sample_iter<R>(&self, n: usize, mut rng: R) -> SampleIter<'_, K, V, H>
+    where
+        K: Hash,
+        H: BuildHasher,
+        R: Rng,
+    {
+        let seqno = self.pin_epoch();
+        let root = self.as_root_node();
+
+        let mut items: BTreeMap<u32, &Item<K, V>> = BTreeMap::new();
+        'outer: while items.len() < n {
+            let mut res = None;
+            for _ in 0..REDRAWS {
+                match self.to_sample(root, rng.gen(), |_| false) {
+                    Some((order, _)) if items.contains_key(&order) => (),
+                    Some(group) => {
+                        res = Some(group);
+                        break;
+                    }
+                    None => break 'outer,
+                }
+            }
+            let skip = |order| items.contains_key(&order);
+            let (order, group) = match res {
+                Some(res) => res,
+                None => match self.to_sample(root, rng.gen(), skip) {
+                    Some(res) => res,
+                    None => break,
+                },
+            };
+
+            // collision groups are sampled as a whole, pick one of them.
+            items.insert(order, group[rng.gen_range(0, group.len())]);
+        }
+
+        SampleIter {
+            map: self,
+            items: items.into_iter(),
+            seqno,
+        }
+    }
+
+    // Return the first group of entries at or after walk position `from`,
+    // that is not skipped, wrapping around to the first entry, when there
+    // are none after.
+    fn to_sample<'a, F>(
+        &self,
+        root: &'a Node<K, V>,
+        from: u32,
+        skip: F,
+    ) -> Option<(u32, Vec<&'a Item<K, V>>)>
+    where
+        K: Hash,
+        H: BuildHasher,
+        F: Fn(u32) -> bool,
+    {
+        let walker = Walker::new_at(root, to_walk_order(from));
+        match self.to_group(walker, from, &skip) {
+            Some(res) => Some(res),
+            None => self.to_group(Walker::new(root), 0, &skip),
+        }
+    }
+
+    // Return the first group of entries at or after walk position `from`,
+    // that is not skipped.
+    fn to_group<'a, F>(
+        &self,
+        walker: Walker<'a, K, V>,
+        from: u32,
+        skip: &F,
+    ) -> Option<(u32, Vec<&'a Item<K, V>>)>
+    where
+        K: Hash,
+        H: BuildHasher,
+        F: Fn(u32) -> bool,
+    {
+        let mut group: Vec<&Item<K, V>> = vec![];
+        let mut order = None;
+        for item in walker {
+            let o = to_walk_order(self.to_hash(&item.key));
+            match order {
+                None if o < from || skip(o) => continue,
+                None => order = Some(o),
+                Some(order) if order != o => break,
+                Some(_) => (),
+            }
+            group.push(item);
+        }
+        Some((order?, group))
+    }
+}
+
+/// SampleIter is returned by [Map::sample_iter], yield a clone of each
+/// sampled entry.
+pub struct SampleIter<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    items: btree_map::IntoIter<u32, &'a Item<K, V>>,
+    seqno: u64,
+}
+
+impl<'a, K, V, H> Drop for SampleIter<'a, K, V, H> {
+    fn drop(&mut self) {
+        self.map.unpin_epoch(self.seqno)
+    }
+}
+
+impl<'a, K, V, H> Iterator for SampleIter<'a, K, V, H>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, item) = self.items.next()?;
+        Some((item.key.clone(), item.value.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<'a, K, V, H> ExactSizeIterator for SampleIter<'a, K, V, H>
+where
+    K: Clone,
+    V: Clone,
+{
+}
+
+#[cfg(test)]
+#[path = "sample_test.rs"]
+mod sample_test;
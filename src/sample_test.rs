@@ -0,0 +1,44 @@
+use std::cmp;
+
+use super::*;
+use crate::iter::iter_test::{load_keys, seed_keys};
+
+#[test]
+fn test_sample_iter() {
+    let (_, mut rng, n_keys) = seed_keys("test_sample_iter");
+    let n = [0, 1, 10, 1000][rng.gen::<usize>() % 4];
+    println!("test_sample_iter n:{}", n);
+
+    let mut map: Map<u32, u64> = Map::new(1, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    // keys colliding on the walk order are sampled as one.
+    let mut orders: Vec<u32> = btmap
+        .keys()
+        .map(|k| to_walk_order(map.to_hash(k)))
+        .collect();
+    orders.sort_unstable();
+    orders.dedup();
+
+    let iter = map.sample_iter(n, &mut rng);
+    assert_eq!(iter.len(), cmp::min(n, orders.len()));
+    let items: Vec<(u32, u64)> = iter.collect();
+    assert_eq!(items.len(), cmp::min(n, orders.len()));
+    for (key, value) in items.iter() {
+        assert_eq!(btmap.get(key), Some(value), "key:{}", key);
+    }
+
+    let mut keys: Vec<u32> = items.iter().map(|(k, _)| *k).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), items.len(), "duplicate samples");
+
+    let len = btmap.len();
+    if n >= 1000 && len >= n * 100 {
+        // with a large map, samples are spread evenly across the key space.
+        let median = btmap.keys().nth(len / 2).copied().unwrap();
+        let lower = keys.iter().filter(|k| **k < median).count();
+        let (lo, hi) = ((n * 4) / 10, (n * 6) / 10);
+        assert!((lo..hi).contains(&lower), "{} {}", lower, n);
+    }
+}
@@ -0,0 +1,90 @@
+//! Module implement asynchronous [Stream] of map entries.
+//!
+//! **This module is available only with `async` feature.**
+//!
+//! Stream is built on top of [Map::scan_from], entries are fetched in
+//! batches of bounded size and the map instance is pinned to an epoch only
+//! while fetching a batch. Hence a poll never blocks for long, and the
+//! epoch is not held in between polls. Consistency guarantees are the same
+//! as that of [Map::scan_from].
+
+use futures_core::stream::Stream;
+
+use std::{
+    hash::{BuildHasher, Hash},
+    pin::Pin,
+    task::{Context, Poll},
+    vec,
+};
+
+use crate::{DefaultHasher, Map};
+
+/// Default number of entries fetched by [MapStream] for every batch.
+pub const STREAM_BATCH: usize = 1024;
+
+impl<K, V, H> Map<K, V, H> {
+    /// Return an asynchronous stream of all {key, value} entries in the map,
+    /// fetched in batches of [STREAM_BATCH] entries.
+    pub fn stream(&self) -> MapStream<'_, K, V, H> {
+        self.stream_with(STREAM_BATCH)
+    }
+
+    /// Same as [Map::stream], but fetch `batch` entries at a time.
+    pub fn stream_with(&self, batch: usize) -> MapStream<'_, K, V, H> {
+        MapStream {
+            map: self,
+            batch: usize::max(batch, 1),
+            token: Some(0),
+            entries: vec![].into_iter(),
+        }
+    }
+}
+
+/// MapStream is returned by [Map::stream], yield a clone of each entry in the
+/// map.
+pub struct MapStream<'a, K, V, H = DefaultHasher> {
+    map: &'a Map<K, V, H>,
+    batch: usize,
+    token: Option<u64>,
+    entries: vec::IntoIter<(K, V)>,
+}
+
+// fields are never pinned.
+impl<'a, K, V, H> Unpin for MapStream<'a, K, V, H> {}
+
+impl<'a, K, V, H> Stream for MapStream<'a, K, V, H>
+where
+    K: Clone + Hash,
+    V: Clone,
+    H: BuildHasher,
+{
+    type Item = (K, V);
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.entries.next() {
+                Some(entry) => break Poll::Ready(Some(entry)),
+                None => match this.token {
+                    Some(token) => {
+                        let (entries, token) = this.map.scan_from(token, this.batch);
+                        this.entries = entries.into_iter();
+                        this.token = token;
+                    }
+                    None => break Poll::Ready(None),
+                },
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.token {
+            Some(_) => (self.entries.len(), None),
+            None => (self.entries.len(), Some(self.entries.len())),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "stream_test.rs"]
+mod stream_test;
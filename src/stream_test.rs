@@ -0,0 +1,29 @@
+use rand::Rng;
+
+use std::task::Waker;
+
+use super::*;
+use crate::iter::iter_test::{load_keys, seed_keys};
+
+#[test]
+fn test_stream() {
+    let (_, mut rng, n_keys) = seed_keys("test_stream");
+    let batch = [0, 1, 7, 100, STREAM_BATCH][rng.gen::<usize>() % 5];
+    println!("test_stream batch:{}", batch);
+
+    let mut map: Map<u32, u64> = Map::new(1, DefaultHasher::new());
+    let btmap = load_keys(&mut map, &mut rng, n_keys);
+
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let mut stream = map.stream_with(batch);
+    let mut items = vec![];
+    while let Poll::Ready(Some(item)) = Pin::new(&mut stream).poll_next(&mut cx) {
+        items.push(item)
+    }
+    assert_eq!(stream.size_hint(), (0, Some(0)));
+
+    items.sort_unstable();
+    let refs: Vec<(u32, u64)> = btmap.into_iter().collect();
+    assert_eq!(items, refs);
+}